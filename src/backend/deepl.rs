@@ -0,0 +1,506 @@
+use super::{all_pairs, parse_json, parse_languages, HttpBackend, HttpClient, TranslationBackend};
+use crate::{Detection, Language, Translation, TranslationError, AUTO_DETECT};
+use async_trait::async_trait;
+use reqwest::RequestBuilder;
+use serde_json::Value;
+
+const FREE_API_URL: &str = "https://api-free.deepl.com";
+const PRO_API_URL: &str = "https://api.deepl.com";
+
+/// Most texts DeepL accepts in one translate request.
+const MAX_BATCH_SIZE: usize = 50;
+
+/// Whether translations should lean towards formal or informal language.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Formality {
+    Default,
+    More,
+    Less,
+    PreferMore,
+    PreferLess,
+}
+
+impl Formality {
+    fn as_str(self) -> &'static str {
+        match self {
+            Formality::Default => "default",
+            Formality::More => "more",
+            Formality::Less => "less",
+            Formality::PreferMore => "prefer_more",
+            Formality::PreferLess => "prefer_less",
+        }
+    }
+}
+
+/// Markup that DeepL should parse and preserve in the input text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TagHandling {
+    Xml,
+    Html,
+}
+
+impl TagHandling {
+    fn as_str(self) -> &'static str {
+        match self {
+            TagHandling::Xml => "xml",
+            TagHandling::Html => "html",
+        }
+    }
+}
+
+/// How DeepL splits the input into sentences.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitSentences {
+    /// Treat the whole input as one sentence.
+    None,
+    /// Split on punctuation and on newlines.
+    All,
+    /// Split on punctuation only.
+    NoNewlines,
+}
+
+impl SplitSentences {
+    fn as_str(self) -> &'static str {
+        match self {
+            SplitSentences::None => "0",
+            SplitSentences::All => "1",
+            SplitSentences::NoNewlines => "nonewlines",
+        }
+    }
+}
+
+/// Backend for the DeepL REST API (v2).
+#[derive(Debug)]
+pub struct DeepLBackend {
+    client: HttpClient,
+    base_url: String,
+    auth_key: String,
+    formality: Option<Formality>,
+    glossary_id: Option<String>,
+    tag_handling: Option<TagHandling>,
+    split_sentences: Option<SplitSentences>,
+}
+
+impl DeepLBackend {
+    /// Creates a backend for `auth_key`, using the free API host for keys
+    /// ending in `:fx` and the pro host otherwise.
+    pub fn new(auth_key: impl Into<String>) -> Self {
+        let auth_key = auth_key.into();
+        let base_url = if auth_key.ends_with(":fx") {
+            FREE_API_URL
+        } else {
+            PRO_API_URL
+        };
+
+        Self {
+            client: HttpClient::default(),
+            base_url: base_url.to_owned(),
+            auth_key,
+            formality: None,
+            glossary_id: None,
+            tag_handling: None,
+            split_sentences: None,
+        }
+    }
+
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_owned();
+        self
+    }
+
+    pub fn with_formality(mut self, formality: Formality) -> Self {
+        self.formality = Some(formality);
+        self
+    }
+
+    /// Uses the glossary with the given id. DeepL requires an explicit
+    /// source language when a glossary is set, so translating from
+    /// [`AUTO_DETECT`] fails with `InvalidLanguage`.
+    pub fn with_glossary_id(mut self, glossary_id: impl Into<String>) -> Self {
+        self.glossary_id = Some(glossary_id.into());
+        self
+    }
+
+    pub fn with_tag_handling(mut self, tag_handling: TagHandling) -> Self {
+        self.tag_handling = Some(tag_handling);
+        self
+    }
+
+    pub fn with_split_sentences(mut self, split_sentences: SplitSentences) -> Self {
+        self.split_sentences = Some(split_sentences);
+        self
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        request.header(
+            reqwest::header::AUTHORIZATION,
+            format!("DeepL-Auth-Key {}", self.auth_key),
+        )
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Value, TranslationError> {
+        match self.client.send(self.authorize(request)).await {
+            Ok(body) => parse_json(&body),
+            // DeepL reports an exhausted character quota with status 456.
+            Err(TranslationError::Http(error)) if error.status == 456 => {
+                Err(TranslationError::QuotaExceeded(error))
+            }
+            Err(error) => Err(error),
+        }
+    }
+
+    /// Fetches the `source` or `target` language codes.
+    async fn languages(&self, kind: &str) -> Result<Vec<String>, TranslationError> {
+        let url = format!("{}/v2/languages", self.base_url);
+        let json = self
+            .send(self.client.get(url).query(&[("type", kind)]))
+            .await?;
+
+        match json.as_array() {
+            Some(languages) => Ok(languages
+                .iter()
+                .filter_map(|language| language["language"].as_str())
+                .map(|code| code.to_owned())
+                .collect()),
+            None => Err(TranslationError::invalid_response(&json.to_string(), None)),
+        }
+    }
+
+    async fn translate_text(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        context: Option<&str>,
+    ) -> Result<Translation, TranslationError> {
+        let json = self
+            .request_translation(&[text], Some(source_lang), target_lang, context)
+            .await?;
+
+        match parse_translation(&json["translations"][0], text) {
+            Some(translation) => Ok(translation),
+            None => Err(TranslationError::NoTranslationFound(text.to_owned())),
+        }
+    }
+
+    async fn request_translation(
+        &self,
+        texts: &[&str],
+        source_lang: Option<&str>,
+        target_lang: &str,
+        context: Option<&str>,
+    ) -> Result<Value, TranslationError> {
+        // Detection passes no source language and uses no glossary.
+        let glossary_id = self.glossary_id.as_ref().filter(|_| source_lang.is_some());
+        let source_lang = source_lang.filter(|lang| *lang != AUTO_DETECT);
+        if glossary_id.is_some() && source_lang.is_none() {
+            // DeepL rejects glossaries without an explicit source language.
+            return Err(TranslationError::InvalidLanguage(AUTO_DETECT.to_owned()));
+        }
+
+        let mut form: Vec<_> = texts
+            .iter()
+            .map(|text| ("text", text.to_string()))
+            .collect();
+        form.push(("target_lang", target_lang.to_uppercase()));
+        if let Some(source_lang) = source_lang {
+            // DeepL source languages never carry a regional variant.
+            let primary = source_lang.split(['-', '_']).next().unwrap_or(source_lang);
+            form.push(("source_lang", primary.to_uppercase()));
+        }
+        if let Some(formality) = self.formality {
+            form.push(("formality", formality.as_str().to_owned()));
+        }
+        if let Some(glossary_id) = glossary_id {
+            form.push(("glossary_id", glossary_id.clone()));
+        }
+        if let Some(tag_handling) = self.tag_handling {
+            form.push(("tag_handling", tag_handling.as_str().to_owned()));
+        }
+        if let Some(split_sentences) = self.split_sentences {
+            form.push(("split_sentences", split_sentences.as_str().to_owned()));
+        }
+        if let Some(context) = context {
+            form.push(("context", context.to_owned()));
+        }
+
+        let url = format!("{}/v2/translate", self.base_url);
+        self.send(self.client.post(url).form(&form)).await
+    }
+}
+
+/// Reads one entry of the `translations` array.
+fn parse_translation(json: &Value, text: &str) -> Option<Translation> {
+    let translation = Translation::new(text, json["text"].as_str()?);
+    match json["detected_source_language"].as_str() {
+        Some(lang) => Some(translation.with_detected_source_lang(lang.to_lowercase(), None)),
+        None => Some(translation),
+    }
+}
+
+impl HttpBackend for DeepLBackend {
+    fn with_client(mut self, client: HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn with_base_url(self, base_url: impl Into<String>) -> Self {
+        DeepLBackend::with_base_url(self, base_url)
+    }
+}
+
+#[async_trait]
+impl TranslationBackend for DeepLBackend {
+    fn name(&self) -> &'static str {
+        "deepl"
+    }
+
+    fn options(&self) -> String {
+        format!(
+            "{:?} {:?} {:?} {:?}",
+            self.formality, self.glossary_id, self.tag_handling, self.split_sentences
+        )
+    }
+
+    /// Keeps the region, which DeepL uses for target variants such as
+    /// `EN-GB` and `PT-BR`, and names Chinese scripts explicitly.
+    fn language_code(&self, language: &Language) -> String {
+        match (language.code(), language.script(), language.region()) {
+            ("zh", Some(script), _) => format!("zh-{}", script),
+            ("no", _, _) => "nb".to_owned(),
+            (code, _, Some(region)) => format!("{}-{}", code, region),
+            (code, _, None) => code.to_owned(),
+        }
+    }
+
+    fn max_batch_size(&self) -> usize {
+        MAX_BATCH_SIZE
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Translation, TranslationError> {
+        self.translate_text(text, source_lang, target_lang, None)
+            .await
+    }
+
+    async fn translate_with_context(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        context: &str,
+    ) -> Result<Translation, TranslationError> {
+        self.translate_text(text, source_lang, target_lang, Some(context))
+            .await
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[&str],
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Vec<Translation>, TranslationError> {
+        let json = self
+            .request_translation(texts, Some(source_lang), target_lang, None)
+            .await?;
+
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| parse_translation(&json["translations"][i], text))
+            .collect::<Option<_>>()
+            .ok_or_else(|| TranslationError::invalid_response(&json.to_string(), None))
+    }
+
+    async fn detect(&self, text: &str) -> Result<Detection, TranslationError> {
+        // DeepL has no detection endpoint; the source language it detects
+        // while translating is reported alongside the translation.
+        let json = self
+            .request_translation(&[text], None, "EN-US", None)
+            .await?;
+
+        match json["translations"][0]["detected_source_language"].as_str() {
+            Some(lang) => Ok(Detection::new(lang.to_lowercase(), None)),
+            None => Err(TranslationError::invalid_response(&json.to_string(), None)),
+        }
+    }
+
+    async fn supported_languages(&self) -> Result<Vec<String>, TranslationError> {
+        self.languages("target").await
+    }
+
+    /// DeepL accepts fewer source than target languages, so the two lists
+    /// are fetched separately.
+    async fn supported_pairs(&self) -> Result<Vec<(Language, Language)>, TranslationError> {
+        let sources = parse_languages(self.languages("source").await?);
+        let targets = parse_languages(self.languages("target").await?);
+        Ok(all_pairs(&sources, &targets))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_translate_sends_options() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(
+                200,
+                json!({"translations": [{"detected_source_language": "EN", "text": "Hallo"}]}),
+            )
+        })
+        .await;
+        let backend = DeepLBackend::new("secret:fx")
+            .with_base_url(server.url())
+            .with_formality(Formality::Less)
+            .with_glossary_id("glossary-1")
+            .with_tag_handling(TagHandling::Html)
+            .with_split_sentences(SplitSentences::NoNewlines);
+
+        let translation = backend.translate("Hello", "en-US", "de").await.unwrap();
+        assert_eq!(translation.text, "Hallo");
+        assert_eq!(translation.detected_source_lang.as_deref(), Some("en"));
+
+        let request = &server.requests()[0];
+        assert_eq!(request.method, "POST");
+        assert_eq!(request.path, "/v2/translate");
+        assert_eq!(
+            request.header("authorization"),
+            Some("DeepL-Auth-Key secret:fx")
+        );
+        assert_eq!(request.param("text").as_deref(), Some("Hello"));
+        assert_eq!(request.param("source_lang").as_deref(), Some("EN"));
+        assert_eq!(request.param("target_lang").as_deref(), Some("DE"));
+        assert_eq!(request.param("formality").as_deref(), Some("less"));
+        assert_eq!(request.param("glossary_id").as_deref(), Some("glossary-1"));
+        assert_eq!(request.param("tag_handling").as_deref(), Some("html"));
+        assert_eq!(
+            request.param("split_sentences").as_deref(),
+            Some("nonewlines")
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auto_detect_omits_source_lang() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(
+                200,
+                json!({"translations": [{"detected_source_language": "FR", "text": "Hello"}]}),
+            )
+        })
+        .await;
+        let backend = DeepLBackend::new("secret").with_base_url(server.url());
+
+        let translation = backend.translate("Bonjour", "auto", "en-GB").await.unwrap();
+        assert_eq!(translation.detected_source_lang.as_deref(), Some("fr"));
+        assert_eq!(server.requests()[0].param("source_lang"), None);
+        assert_eq!(backend.detect("Bonjour").await.unwrap().language, "fr");
+    }
+
+    #[tokio::test]
+    async fn test_glossary_needs_source_lang() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(
+                200,
+                json!({"translations": [{"detected_source_language": "FR", "text": "Hello"}]}),
+            )
+        })
+        .await;
+        let backend = DeepLBackend::new("secret")
+            .with_base_url(server.url())
+            .with_glossary_id("glossary-1");
+
+        let error = backend
+            .translate("Bonjour", "auto", "en")
+            .await
+            .unwrap_err();
+        assert!(
+            matches!(&error, TranslationError::InvalidLanguage(tag) if tag == "auto"),
+            "{:?}",
+            error
+        );
+        assert!(server.requests().is_empty());
+
+        assert_eq!(backend.detect("Bonjour").await.unwrap().language, "fr");
+        assert_eq!(server.requests()[0].param("glossary_id"), None);
+    }
+
+    #[tokio::test]
+    async fn test_translate_batch_sends_all_texts() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(
+                200,
+                json!({"translations": [
+                    {"detected_source_language": "EN", "text": "Hallo"},
+                    {"detected_source_language": "EN", "text": "Welt"},
+                ]}),
+            )
+        })
+        .await;
+        let backend = DeepLBackend::new("secret").with_base_url(server.url());
+
+        let translations = backend
+            .translate_batch(&["Hello", "World"], "en", "de")
+            .await
+            .unwrap();
+        assert_eq!(translations[0].text, "Hallo");
+        assert_eq!(translations[1].source_text, "World");
+        assert_eq!(server.requests()[0].params("text"), ["Hello", "World"]);
+
+        let error = backend
+            .translate_batch(&["Hello", "World", "!"], "en", "de")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, TranslationError::ResponseParsingFailed(_)));
+    }
+
+    #[tokio::test]
+    async fn test_supported_pairs() {
+        let server = MockServer::start(|request| match request.param("type").as_deref() {
+            Some("source") => MockResponse::json(200, json!([{"language": "EN"}])),
+            _ => MockResponse::json(200, json!([{"language": "EN-GB"}, {"language": "ZH-HANT"}])),
+        })
+        .await;
+        let backend = DeepLBackend::new("secret").with_base_url(server.url());
+
+        let pairs = backend.supported_pairs().await.unwrap();
+        let pairs: Vec<_> = pairs
+            .iter()
+            .map(|(source, target)| format!("{}>{}", source, target))
+            .collect();
+        assert_eq!(pairs, ["en>en-GB", "en>zh-Hant"]);
+
+        let language = Language::parse("zh-TW").unwrap();
+        assert_eq!(backend.language_code(&language), "zh-Hant");
+    }
+
+    #[tokio::test]
+    async fn test_error_statuses() {
+        for (status, expected) in [
+            (403, "AuthorizationFailed"),
+            (456, "QuotaExceeded"),
+            (429, "RateLimited"),
+            (503, "ServerError"),
+        ] {
+            let server = MockServer::start(move |_| MockResponse::text(status, "")).await;
+            let backend = DeepLBackend::new("secret").with_base_url(server.url());
+
+            let error = backend.translate("Hello", "en", "de").await.unwrap_err();
+            assert!(format!("{:?}", error).starts_with(expected));
+            assert_eq!(error.status(), Some(status));
+        }
+    }
+
+    #[test]
+    fn test_host_selection() {
+        assert_eq!(DeepLBackend::new("key:fx").base_url, FREE_API_URL);
+        assert_eq!(DeepLBackend::new("key").base_url, PRO_API_URL);
+    }
+}
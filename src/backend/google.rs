@@ -0,0 +1,474 @@
+use super::{parse_json, HttpBackend, HttpClient, TranslationBackend};
+use crate::{
+    Alternatives, Candidate, Definition, Detection, DictionaryEntry, DictionaryTerm, Language,
+    Lookup, RateLimit, Romanization, Segment, Synonyms, Translation, TranslationError, AUTO_DETECT,
+};
+use async_trait::async_trait;
+use serde_json::Value;
+
+const BASE_URL: &str = "https://translate.googleapis.com";
+
+/// `dt` values requested for translations.
+const TRANSLATE_DATA: &[&str] = &["t"];
+
+/// `dt` values requested for lookups: translation, alternatives, dictionary,
+/// definitions, examples, synonyms, romanization and spelling correction.
+const LOOKUP_DATA: &[&str] = &["t", "at", "bd", "md", "ex", "ss", "rm", "qc"];
+
+/// Language codes understood by the gtx endpoint.
+const LANGUAGES: &[&str] = &[
+    "af", "sq", "am", "ar", "hy", "az", "eu", "be", "bn", "bs", "bg", "ca", "ceb", "ny", "zh-CN",
+    "zh-TW", "co", "hr", "cs", "da", "nl", "en", "eo", "et", "tl", "fi", "fr", "fy", "gl", "ka",
+    "de", "el", "gu", "ht", "ha", "haw", "iw", "hi", "hmn", "hu", "is", "ig", "id", "ga", "it",
+    "ja", "jw", "kn", "kk", "km", "ko", "ku", "ky", "lo", "la", "lv", "lt", "lb", "mk", "mg", "ms",
+    "ml", "mt", "mi", "mr", "mn", "my", "ne", "no", "ps", "fa", "pl", "pt", "pa", "ro", "ru", "sm",
+    "gd", "sr", "st", "sn", "sd", "si", "sk", "sl", "so", "es", "su", "sw", "sv", "tg", "ta", "te",
+    "th", "tr", "uk", "ur", "uz", "vi", "cy", "xh", "yi", "yo", "zu",
+];
+
+/// Longest text sent in the query string; longer texts are POSTed.
+const MAX_QUERY_TEXT_LEN: usize = 1500;
+
+/// Longest text, in characters, gtx translates in one request.
+const MAX_TEXT_LEN: usize = 5000;
+
+/// Requests per second sent to gtx by default, and how many may be sent at
+/// once.
+const REQUESTS_PER_SECOND: f64 = 5.0;
+const BURST: u32 = 5;
+
+/// Backend for Google's free `translate_a/single` endpoint (`client=gtx`).
+#[derive(Debug)]
+pub struct GoogleGtxBackend {
+    client: HttpClient,
+    base_url: String,
+}
+
+impl GoogleGtxBackend {
+    pub fn new() -> Self {
+        Self {
+            client: HttpClient::default(),
+            base_url: BASE_URL.to_owned(),
+        }
+    }
+
+    async fn request(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        data: &[&str],
+    ) -> Result<Value, TranslationError> {
+        let url = format!("{}/translate_a/single", self.base_url);
+        let mut query = vec![("client", "gtx")];
+        query.extend(data.iter().map(|dt| ("dt", *dt)));
+        query.extend([("sl", source_lang), ("tl", target_lang)]);
+
+        // Long texts would overflow the URL, so they go in a form body.
+        let request = if text.len() > MAX_QUERY_TEXT_LEN {
+            self.client.post(url).query(&query).form(&[("q", text)])
+        } else {
+            query.push(("q", text));
+            self.client.get(url).query(&query)
+        };
+        parse_json(&self.client.send(request).await?)
+    }
+}
+
+impl Default for GoogleGtxBackend {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HttpBackend for GoogleGtxBackend {
+    fn with_client(mut self, client: HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_owned();
+        self
+    }
+}
+
+/// The parts of a `translate_a/single` response used by this backend.
+#[derive(Debug, Default, PartialEq)]
+struct GtxResponse {
+    /// Sentence segments from `json[0]`.
+    segments: Vec<Segment>,
+    /// Detected source language from `json[2]`.
+    source_lang: Option<String>,
+    /// Source language confidence from `json[6]`.
+    confidence: Option<f64>,
+}
+
+impl GtxResponse {
+    fn parse(json: &Value) -> Result<Self, TranslationError> {
+        if !json.is_array() {
+            return Err(TranslationError::invalid_response(&json.to_string(), None));
+        }
+
+        // Besides `[target, source, ...]` sentences, `json[0]` may end with
+        // a transliteration entry whose target is null; it is skipped here.
+        let segments = json[0]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|segment| {
+                Some(Segment {
+                    target: segment[0].as_str()?.to_owned(),
+                    source: segment[1].as_str()?.to_owned(),
+                })
+            })
+            .collect();
+
+        Ok(Self {
+            segments,
+            source_lang: json[2].as_str().map(|lang| lang.to_owned()),
+            confidence: json[6].as_f64(),
+        })
+    }
+
+    fn into_translation(self, text: &str) -> Result<Translation, TranslationError> {
+        if self.segments.is_empty() {
+            return Err(TranslationError::NoTranslationFound(text.to_owned()));
+        }
+
+        let translation = Translation::from_segments(self.segments);
+        match self.source_lang {
+            Some(lang) => Ok(translation.with_detected_source_lang(lang, self.confidence)),
+            None => Ok(translation),
+        }
+    }
+}
+
+fn parse_lookup(json: &Value, text: &str) -> Result<Lookup, TranslationError> {
+    let translation = GtxResponse::parse(json)?.into_translation(text)?;
+
+    // `json[5]`: [[source, _, [[alternative, score, ...], ...], ...], ...]
+    let alternatives = items(&json[5])
+        .filter_map(|segment| {
+            Some(Alternatives {
+                source: segment[0].as_str()?.to_owned(),
+                candidates: items(&segment[2])
+                    .filter_map(|candidate| {
+                        Some(Candidate {
+                            text: candidate[0].as_str()?.to_owned(),
+                            score: candidate[1].as_f64(),
+                        })
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+
+    // `json[1]`: [[pos, [terms], [[term, [reverse translations], _, score], ...], base form], ...]
+    let dictionary = items(&json[1])
+        .filter_map(|entry| {
+            Some(DictionaryEntry {
+                part_of_speech: entry[0].as_str()?.to_owned(),
+                base_form: string(&entry[3]),
+                terms: items(&entry[2])
+                    .filter_map(|term| {
+                        Some(DictionaryTerm {
+                            word: term[0].as_str()?.to_owned(),
+                            reverse_translations: strings(&term[1]),
+                            score: term[3].as_f64(),
+                        })
+                    })
+                    .collect(),
+            })
+        })
+        .collect();
+
+    // `json[12]`: [[pos, [[gloss, id, example], ...], base form], ...]
+    let definitions = items(&json[12])
+        .flat_map(|group| {
+            let part_of_speech = group[0].as_str().unwrap_or_default().to_owned();
+            items(&group[1]).filter_map(move |definition| {
+                Some(Definition {
+                    part_of_speech: part_of_speech.clone(),
+                    gloss: definition[0].as_str()?.to_owned(),
+                    example: string(&definition[2]),
+                })
+            })
+        })
+        .collect();
+
+    // `json[13]`: [[[example html, ...], ...]]
+    let examples = items(&json[13][0])
+        .filter_map(|example| example[0].as_str())
+        .map(strip_tags)
+        .collect();
+
+    // `json[11]`: [[pos, [[[synonym, ...], id], ...], base form], ...]
+    let synonyms = items(&json[11])
+        .flat_map(|group| {
+            let part_of_speech = group[0].as_str().unwrap_or_default().to_owned();
+            items(&group[1]).map(move |set| Synonyms {
+                part_of_speech: part_of_speech.clone(),
+                words: strings(&set[0]),
+            })
+        })
+        .collect();
+
+    // Romanization is a `[null, null, target, source]` entry in `json[0]`.
+    let romanization = items(&json[0])
+        .find(|segment| segment[0].is_null() && segment[1].is_null())
+        .map(|segment| Romanization {
+            target: string(&segment[2]),
+            source: string(&segment[3]),
+        })
+        .filter(|romanization| romanization.source.is_some() || romanization.target.is_some());
+
+    // `json[7]`: [corrected html, corrected text, ...]
+    let spelling_correction = string(&json[7][1]);
+
+    Ok(Lookup {
+        translation,
+        alternatives,
+        dictionary,
+        definitions,
+        examples,
+        synonyms,
+        romanization,
+        spelling_correction,
+    })
+}
+
+fn items(value: &Value) -> impl Iterator<Item = &Value> {
+    value.as_array().into_iter().flatten()
+}
+
+fn string(value: &Value) -> Option<String> {
+    value.as_str().map(|text| text.to_owned())
+}
+
+fn strings(value: &Value) -> Vec<String> {
+    items(value)
+        .filter_map(|item| item.as_str())
+        .map(|item| item.to_owned())
+        .collect()
+}
+
+/// Removes the `<b>` highlighting gtx puts around words in examples.
+fn strip_tags(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+    for c in html.chars() {
+        match c {
+            '<' => in_tag = true,
+            '>' => in_tag = false,
+            c if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+    text
+}
+
+#[async_trait]
+impl TranslationBackend for GoogleGtxBackend {
+    fn name(&self) -> &'static str {
+        "google"
+    }
+
+    /// gtx answers bursts of parallel requests with 429s and captcha pages.
+    fn rate_limit(&self) -> Option<RateLimit> {
+        Some(
+            RateLimit::new()
+                .with_requests_per_second(REQUESTS_PER_SECOND)
+                .with_burst(BURST),
+        )
+    }
+
+    fn max_text_len(&self) -> Option<usize> {
+        Some(MAX_TEXT_LEN)
+    }
+
+    /// gtx still uses legacy codes (`iw`, `jw`) and tells Chinese scripts
+    /// apart by region only.
+    fn language_code(&self, language: &Language) -> String {
+        match language.code() {
+            "he" => "iw".to_owned(),
+            "jv" => "jw".to_owned(),
+            "fil" => "tl".to_owned(),
+            "nb" | "nn" => "no".to_owned(),
+            "zh" if language.script() == Some("Hant") => "zh-TW".to_owned(),
+            "zh" => "zh-CN".to_owned(),
+            code => code.to_owned(),
+        }
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Translation, TranslationError> {
+        let json = self
+            .request(text, source_lang, target_lang, TRANSLATE_DATA)
+            .await?;
+        GtxResponse::parse(&json)?.into_translation(text)
+    }
+
+    async fn lookup(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Lookup, TranslationError> {
+        let json = self
+            .request(text, source_lang, target_lang, LOOKUP_DATA)
+            .await?;
+        parse_lookup(&json, text)
+    }
+
+    async fn detect(&self, text: &str) -> Result<Detection, TranslationError> {
+        let json = self
+            .request(text, AUTO_DETECT, "en", TRANSLATE_DATA)
+            .await?;
+        let response = GtxResponse::parse(&json)?;
+
+        match response.source_lang {
+            Some(lang) => Ok(Detection::new(lang, response.confidence)),
+            None => Err(TranslationError::invalid_response(&json.to_string(), None)),
+        }
+    }
+
+    async fn supported_languages(&self) -> Result<Vec<String>, TranslationError> {
+        Ok(LANGUAGES.iter().map(|lang| lang.to_string()).collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_language_codes() {
+        let backend = GoogleGtxBackend::new();
+        let code = |tag| backend.language_code(&Language::parse(tag).unwrap());
+
+        assert_eq!(code("he"), "iw");
+        assert_eq!(code("zh-Hant"), "zh-TW");
+        assert_eq!(code("zh-SG"), "zh-CN");
+        assert_eq!(code("pt-BR"), "pt");
+        for lang in LANGUAGES {
+            assert_eq!(code(lang), *lang);
+        }
+    }
+
+    #[test]
+    fn test_parse_multiple_sentences() {
+        let json = json!([
+            [
+                ["Bonjour. ", "Hello. ", null, null, 10],
+                ["Comment vas-tu?", "How are you?", null, null, 10],
+                [null, null, "bonzhur", "heloh"]
+            ],
+            null,
+            "en",
+            null,
+            null,
+            null,
+            0.97,
+            [],
+            [["en"], null, [0.97], ["en"]]
+        ]);
+
+        let response = GtxResponse::parse(&json).unwrap();
+        assert_eq!(response.source_lang.as_deref(), Some("en"));
+        assert_eq!(response.confidence, Some(0.97));
+
+        let translation = Translation::from_segments(response.segments);
+        assert_eq!(translation.text, "Bonjour. Comment vas-tu?");
+        assert_eq!(translation.source_text, "Hello. How are you?");
+        assert_eq!(translation.segments.len(), 2);
+        assert_eq!(translation.segments[1].source, "How are you?");
+    }
+
+    #[test]
+    fn test_parse_lookup() {
+        let json = json!([
+            [
+                ["Bonjour", "hello", null, null, 10],
+                [null, null, "bonzhur", "heloh"]
+            ],
+            [[
+                "interjection",
+                ["Bonjour!", "Salut!"],
+                [
+                    ["Bonjour!", ["Hello!", "Hi!"], null, 0.8],
+                    ["Salut!", ["Hi!"], null, 0.05]
+                ],
+                "hello",
+                9
+            ]],
+            "en",
+            null,
+            null,
+            [[
+                "hello",
+                null,
+                [["Bonjour", 1000, true, false], ["Salut", 0, true, false]],
+                [[0, 5]],
+                "hello",
+                0,
+                0
+            ]],
+            1,
+            ["<b><i>hello</i></b>", "hello", null, null, null, 0],
+            [["en"], null, [1], ["en"]],
+            null,
+            null,
+            [["noun", [[["greeting", "welcome"], "m_1"]], "hello"]],
+            [[
+                "noun",
+                [["an utterance of “hello”.", "m_2", "polite hellos"]],
+                "hello"
+            ]],
+            [[["<b>hello</b> there, Katie!", null, null, null, null, "m_3"]]]
+        ]);
+
+        let lookup = parse_lookup(&json, "helo").unwrap();
+        assert_eq!(lookup.translation.text, "Bonjour");
+        assert_eq!(lookup.alternatives[0].source, "hello");
+        assert_eq!(
+            lookup.alternatives[0].candidates[1],
+            Candidate {
+                text: "Salut".to_owned(),
+                score: Some(0.0)
+            }
+        );
+        assert_eq!(lookup.dictionary[0].part_of_speech, "interjection");
+        assert_eq!(lookup.dictionary[0].base_form.as_deref(), Some("hello"));
+        assert_eq!(
+            lookup.dictionary[0].terms[0].reverse_translations,
+            ["Hello!", "Hi!"]
+        );
+        assert_eq!(lookup.dictionary[0].terms[0].score, Some(0.8));
+        assert_eq!(lookup.definitions[0].gloss, "an utterance of “hello”.");
+        assert_eq!(
+            lookup.definitions[0].example.as_deref(),
+            Some("polite hellos")
+        );
+        assert_eq!(lookup.examples, ["hello there, Katie!"]);
+        assert_eq!(lookup.synonyms[0].words, ["greeting", "welcome"]);
+        assert_eq!(
+            lookup.romanization,
+            Some(Romanization {
+                source: Some("heloh".to_owned()),
+                target: Some("bonzhur".to_owned()),
+            })
+        );
+        assert_eq!(lookup.spelling_correction.as_deref(), Some("hello"));
+    }
+
+    #[test]
+    fn test_parse_rejects_non_array() {
+        assert!(GtxResponse::parse(&json!({"error": "bad"})).is_err());
+    }
+}
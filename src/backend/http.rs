@@ -0,0 +1,121 @@
+//! Request helpers shared by the backends talking to HTTP APIs.
+
+use super::TranslationBackend;
+use reqwest::Client;
+use std::time::Duration;
+// Only the backends send requests.
+#[cfg(any(
+    feature = "google",
+    feature = "deepl",
+    feature = "libretranslate",
+    feature = "llm"
+))]
+use {
+    crate::error::is_captcha, crate::TranslationError, reqwest::RequestBuilder, serde_json::Value,
+    std::future::Future,
+};
+
+/// The HTTP client a backend sends its requests with.
+#[derive(Debug, Clone, Default)]
+pub struct HttpClient {
+    // Only the request methods below read it, which need a backend.
+    #[cfg_attr(
+        not(any(
+            feature = "google",
+            feature = "deepl",
+            feature = "libretranslate",
+            feature = "llm"
+        )),
+        allow(dead_code)
+    )]
+    client: Client,
+    read_timeout: Option<Duration>,
+}
+
+impl HttpClient {
+    pub fn new(client: Client) -> Self {
+        Self {
+            client,
+            read_timeout: None,
+        }
+    }
+
+    /// Fails requests that wait longer than `read_timeout` for the response
+    /// headers or the next part of the body.
+    pub fn with_read_timeout(mut self, read_timeout: Duration) -> Self {
+        self.read_timeout = Some(read_timeout);
+        self
+    }
+}
+
+#[cfg(any(
+    feature = "google",
+    feature = "deepl",
+    feature = "libretranslate",
+    feature = "llm"
+))]
+impl HttpClient {
+    // The LLM backend only ever posts.
+    #[cfg(any(feature = "google", feature = "deepl", feature = "libretranslate"))]
+    pub(super) fn get(&self, url: impl AsRef<str>) -> RequestBuilder {
+        self.client.get(url.as_ref())
+    }
+
+    pub(super) fn post(&self, url: impl AsRef<str>) -> RequestBuilder {
+        self.client.post(url.as_ref())
+    }
+
+    /// Sends `request` and returns the body of a successful response. Error
+    /// statuses and captcha pages become the matching `TranslationError`.
+    pub(super) async fn send(&self, request: RequestBuilder) -> Result<String, TranslationError> {
+        let mut response = self.read(request.send()).await??;
+        let status = response.status();
+        let headers = response.headers().clone();
+        let mut body = Vec::new();
+        while let Some(chunk) = self.read(response.chunk()).await?? {
+            body.extend_from_slice(&chunk);
+        }
+        let body = String::from_utf8_lossy(&body);
+
+        if !status.is_success() || is_captcha(&body) {
+            return Err(TranslationError::from_status(status, &headers, &body));
+        }
+        Ok(body.into_owned())
+    }
+
+    async fn read<T>(&self, future: impl Future<Output = T>) -> Result<T, TranslationError> {
+        match self.read_timeout {
+            Some(timeout) => tokio::time::timeout(timeout, future)
+                .await
+                .map_err(|_| TranslationError::Timeout(None)),
+            None => Ok(future.await),
+        }
+    }
+}
+
+impl From<Client> for HttpClient {
+    fn from(client: Client) -> Self {
+        Self::new(client)
+    }
+}
+
+/// A backend talking to an HTTP API, whose client and server
+/// [`TranslatorBuilder`](crate::TranslatorBuilder) can replace.
+pub trait HttpBackend: TranslationBackend + Sized {
+    fn with_client(self, client: HttpClient) -> Self;
+
+    /// Sends requests to the server at `base_url` instead, e.g. a proxy or
+    /// a mock server.
+    fn with_base_url(self, base_url: impl Into<String>) -> Self;
+}
+
+#[cfg(any(
+    feature = "google",
+    feature = "deepl",
+    feature = "libretranslate",
+    feature = "llm"
+))]
+pub(super) fn parse_json(body: &str) -> Result<Value, TranslationError> {
+    serde_json::from_str(body)
+        .map_err(|error| TranslationError::invalid_response(body, Some(error)))
+}
@@ -0,0 +1,430 @@
+use super::{parse_json, HttpBackend, HttpClient, TranslationBackend};
+use crate::{Alternatives, Candidate, Detection, Language, Lookup, Translation, TranslationError};
+use async_trait::async_trait;
+use reqwest::RequestBuilder;
+use serde_json::{json, Value};
+
+/// Texts sent per batch request; LibreTranslate servers cap the total
+/// characters of a request, so batches are kept small.
+const MAX_BATCH_SIZE: usize = 25;
+
+/// Format of the text sent to LibreTranslate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextFormat {
+    #[default]
+    Text,
+    Html,
+}
+
+impl TextFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            TextFormat::Text => "text",
+            TextFormat::Html => "html",
+        }
+    }
+}
+
+/// A LibreTranslate translation together with its alternatives.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LibreTranslation {
+    pub translation: Translation,
+    pub alternatives: Vec<String>,
+}
+
+/// Backend for a (usually self-hosted) LibreTranslate server.
+#[derive(Debug)]
+pub struct LibreTranslateBackend {
+    client: HttpClient,
+    base_url: String,
+    api_key: Option<String>,
+    format: TextFormat,
+    alternatives: u32,
+}
+
+impl LibreTranslateBackend {
+    /// Creates a backend for the server at `base_url`, e.g.
+    /// `http://localhost:5000`.
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self {
+            client: HttpClient::default(),
+            base_url: base_url.into().trim_end_matches('/').to_owned(),
+            api_key: None,
+            format: TextFormat::default(),
+            alternatives: 0,
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn with_format(mut self, format: TextFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Requests up to `alternatives` additional translations per call.
+    pub fn with_alternatives(mut self, alternatives: u32) -> Self {
+        self.alternatives = alternatives;
+        self
+    }
+
+    /// Translates `text` and returns the alternatives reported by the server
+    /// alongside the main translation.
+    pub async fn translate_with_alternatives(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<LibreTranslation, TranslationError> {
+        let mut body = json!({
+            "q": text,
+            "source": source_lang,
+            "target": target_lang,
+            "format": self.format.as_str(),
+        });
+        if self.alternatives > 0 {
+            body["alternatives"] = json!(self.alternatives);
+        }
+
+        let json = self.send(self.post("translate", body)).await?;
+
+        let translation = match json["translatedText"].as_str() {
+            Some(translation) => Translation::new(text, translation),
+            None => return Err(TranslationError::NoTranslationFound(text.to_owned())),
+        };
+        let translation = with_detected_language(translation, &json["detectedLanguage"]);
+        let alternatives = json["alternatives"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|alternative| alternative.as_str())
+            .map(|alternative| alternative.to_owned())
+            .collect();
+
+        Ok(LibreTranslation {
+            translation,
+            alternatives,
+        })
+    }
+
+    fn post(&self, endpoint: &str, mut body: Value) -> RequestBuilder {
+        if let Some(api_key) = &self.api_key {
+            body["api_key"] = json!(api_key);
+        }
+        self.client
+            .post(format!("{}/{}", self.base_url, endpoint))
+            .json(&body)
+    }
+
+    async fn send(&self, request: RequestBuilder) -> Result<Value, TranslationError> {
+        parse_json(&self.client.send(request).await?)
+    }
+
+    async fn languages(&self) -> Result<Vec<Value>, TranslationError> {
+        let url = format!("{}/languages", self.base_url);
+        match self.send(self.client.get(url)).await? {
+            Value::Array(languages) => Ok(languages),
+            json => Err(TranslationError::invalid_response(&json.to_string(), None)),
+        }
+    }
+}
+
+/// Adds the source language LibreTranslate reports when auto-detecting.
+fn with_detected_language(translation: Translation, detected: &Value) -> Translation {
+    match detected["language"].as_str() {
+        Some(lang) => {
+            // LibreTranslate reports confidence as a percentage.
+            let confidence = detected["confidence"]
+                .as_f64()
+                .map(|confidence| confidence / 100.0);
+            translation.with_detected_source_lang(lang, confidence)
+        }
+        None => translation,
+    }
+}
+
+/// Parses a LibreTranslate language code, which uses `zt` for Traditional
+/// Chinese.
+fn parse_code(code: &str) -> Result<Language, TranslationError> {
+    match code {
+        "zt" => Language::parse("zh-Hant"),
+        code => Language::parse(code),
+    }
+}
+
+impl HttpBackend for LibreTranslateBackend {
+    fn with_client(mut self, client: HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_owned();
+        self
+    }
+}
+
+#[async_trait]
+impl TranslationBackend for LibreTranslateBackend {
+    fn name(&self) -> &'static str {
+        "libretranslate"
+    }
+
+    fn options(&self) -> String {
+        format!("{} {:?}", self.base_url, self.format)
+    }
+
+    /// LibreTranslate uses bare language codes, with `zt` for
+    /// Traditional Chinese.
+    fn language_code(&self, language: &Language) -> String {
+        match (language.code(), language.script()) {
+            ("zh", Some("Hant")) => "zt".to_owned(),
+            (code, _) => code.to_owned(),
+        }
+    }
+
+    fn max_batch_size(&self) -> usize {
+        MAX_BATCH_SIZE
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Translation, TranslationError> {
+        self.translate_with_alternatives(text, source_lang, target_lang)
+            .await
+            .map(|result| result.translation)
+    }
+
+    /// Reports the alternatives the server offers for the whole text, of
+    /// which it only sends as many as `with_alternatives` asks for.
+    async fn lookup(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Lookup, TranslationError> {
+        let LibreTranslation {
+            translation,
+            alternatives,
+        } = self
+            .translate_with_alternatives(text, source_lang, target_lang)
+            .await?;
+        let candidates: Vec<_> = alternatives
+            .into_iter()
+            .map(|text| Candidate { text, score: None })
+            .collect();
+        let alternatives = if candidates.is_empty() {
+            Vec::new()
+        } else {
+            vec![Alternatives {
+                source: text.to_owned(),
+                candidates,
+            }]
+        };
+
+        Ok(Lookup {
+            translation,
+            alternatives,
+            dictionary: Vec::new(),
+            definitions: Vec::new(),
+            examples: Vec::new(),
+            synonyms: Vec::new(),
+            romanization: None,
+            spelling_correction: None,
+        })
+    }
+
+    /// Sends the texts as a `q` array; alternatives are not requested.
+    async fn translate_batch(
+        &self,
+        texts: &[&str],
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Vec<Translation>, TranslationError> {
+        let body = json!({
+            "q": texts,
+            "source": source_lang,
+            "target": target_lang,
+            "format": self.format.as_str(),
+        });
+        let json = self.send(self.post("translate", body)).await?;
+
+        texts
+            .iter()
+            .enumerate()
+            .map(|(i, text)| {
+                let translation = Translation::new(*text, json["translatedText"][i].as_str()?);
+                Some(with_detected_language(
+                    translation,
+                    &json["detectedLanguage"][i],
+                ))
+            })
+            .collect::<Option<_>>()
+            .ok_or_else(|| TranslationError::invalid_response(&json.to_string(), None))
+    }
+
+    async fn detect(&self, text: &str) -> Result<Detection, TranslationError> {
+        let json = self.send(self.post("detect", json!({ "q": text }))).await?;
+
+        match json[0]["language"].as_str() {
+            Some(lang) => Ok(Detection::new(
+                lang,
+                json[0]["confidence"]
+                    .as_f64()
+                    .map(|confidence| confidence / 100.0),
+            )),
+            None => Err(TranslationError::invalid_response(&json.to_string(), None)),
+        }
+    }
+
+    async fn supported_languages(&self) -> Result<Vec<String>, TranslationError> {
+        let languages = self.languages().await?;
+        Ok(languages
+            .iter()
+            .filter_map(|language| language["code"].as_str())
+            .map(|code| code.to_owned())
+            .collect())
+    }
+
+    /// Each language lists the targets it can be translated to.
+    async fn supported_pairs(&self) -> Result<Vec<(Language, Language)>, TranslationError> {
+        let mut pairs = Vec::new();
+        for language in self.languages().await? {
+            let Some(Ok(source)) = language["code"].as_str().map(parse_code) else {
+                continue;
+            };
+            let targets = language["targets"].as_array().into_iter().flatten();
+            let targets = targets.filter_map(|target| parse_code(target.as_str()?).ok());
+            for target in targets {
+                if target != source {
+                    pairs.push((source.clone(), target));
+                }
+            }
+        }
+        Ok(pairs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer};
+
+    async fn server() -> MockServer {
+        MockServer::start(|request| match request.path.as_str() {
+            "/translate" if request.json()["q"].is_array() => MockResponse::json(
+                200,
+                json!({
+                    "translatedText": ["Bonjour", "Monde"],
+                    "detectedLanguage": [
+                        {"confidence": 90.0, "language": "en"},
+                        {"confidence": 60.0, "language": "en"},
+                    ],
+                }),
+            ),
+            "/translate" => MockResponse::json(
+                200,
+                json!({"translatedText": "Bonjour", "alternatives": ["Salut", "Coucou"]}),
+            ),
+            "/detect" => MockResponse::json(200, json!([{"confidence": 92.0, "language": "de"}])),
+            "/languages" => MockResponse::json(
+                200,
+                json!([
+                    {"code": "en", "name": "English", "targets": ["en", "fr", "zt"]},
+                    {"code": "fr", "name": "French", "targets": ["en"]},
+                ]),
+            ),
+            _ => MockResponse::text(404, "not found"),
+        })
+        .await
+    }
+
+    #[tokio::test]
+    async fn test_translate_with_alternatives() {
+        let server = server().await;
+        let backend = LibreTranslateBackend::new(server.url())
+            .with_api_key("key")
+            .with_format(TextFormat::Html)
+            .with_alternatives(2);
+
+        let translation = backend
+            .translate_with_alternatives("Hello", "en", "fr")
+            .await
+            .unwrap();
+        assert_eq!(translation.translation.text, "Bonjour");
+        assert_eq!(translation.alternatives, ["Salut", "Coucou"]);
+
+        let body = server.requests()[0].json();
+        assert_eq!(
+            body,
+            json!({
+                "q": "Hello",
+                "source": "en",
+                "target": "fr",
+                "format": "html",
+                "alternatives": 2,
+                "api_key": "key",
+            })
+        );
+    }
+
+    #[tokio::test]
+    async fn test_lookup_returns_alternatives() {
+        let server = server().await;
+        let backend: Box<dyn TranslationBackend> =
+            Box::new(LibreTranslateBackend::new(server.url()).with_alternatives(2));
+
+        let lookup = backend.lookup("Hello", "en", "fr").await.unwrap();
+        assert_eq!(lookup.translation.text, "Bonjour");
+        assert_eq!(lookup.alternatives.len(), 1);
+        assert_eq!(lookup.alternatives[0].source, "Hello");
+        let candidates: Vec<_> = lookup.alternatives[0]
+            .candidates
+            .iter()
+            .map(|candidate| candidate.text.as_str())
+            .collect();
+        assert_eq!(candidates, ["Salut", "Coucou"]);
+        assert_eq!(server.requests()[0].json()["alternatives"], 2);
+    }
+
+    #[tokio::test]
+    async fn test_translate_batch() {
+        let server = server().await;
+        let backend = LibreTranslateBackend::new(server.url());
+
+        let translations = backend
+            .translate_batch(&["Hello", "World"], "auto", "fr")
+            .await
+            .unwrap();
+        assert_eq!(translations[1].text, "Monde");
+        assert_eq!(translations[1].confidence, Some(0.6));
+        assert_eq!(server.requests()[0].json()["q"], json!(["Hello", "World"]));
+    }
+
+    #[tokio::test]
+    async fn test_detect_and_languages() {
+        let server = server().await;
+        let backend = LibreTranslateBackend::new(server.url());
+
+        let detection = backend.detect("Hallo").await.unwrap();
+        assert_eq!(detection.language, "de");
+        assert_eq!(detection.confidence, Some(0.92));
+        assert_eq!(backend.supported_languages().await.unwrap(), ["en", "fr"]);
+        assert!(server.requests()[0].json().get("api_key").is_none());
+
+        let pairs: Vec<_> = backend
+            .supported_pairs()
+            .await
+            .unwrap()
+            .iter()
+            .map(|(source, target)| format!("{}>{}", source, target))
+            .collect();
+        assert_eq!(pairs, ["en>fr", "en>zh-Hant", "fr>en"]);
+    }
+}
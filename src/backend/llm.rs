@@ -0,0 +1,515 @@
+use super::{parse_json, HttpBackend, HttpClient, TranslationBackend};
+use crate::{Detection, Translation, TranslationError, AUTO_DETECT};
+use async_trait::async_trait;
+use serde_json::json;
+
+const DEFAULT_SYSTEM_PROMPT: &str = "You are a professional translator. Translate the user's \
+text from {source_lang} to {target_lang}. Reply with the translation only, without quotes, \
+notes or explanations.{context}{glossary}";
+
+const DEFAULT_USER_PROMPT: &str = "{text}";
+
+const BATCH_INSTRUCTIONS: &str = "The user sends a JSON object {\"texts\": [...]}. Reply with a \
+JSON object {\"translations\": [...]} holding one translation per text, in the same order.";
+
+/// Texts sent per batch request, keeping replies well within model output
+/// limits.
+const MAX_BATCH_SIZE: usize = 20;
+
+const DETECT_PROMPT: &str = "Identify the language of the user's text. Reply with its ISO 639-1 \
+code only.";
+
+/// A prompt with `{source_lang}`, `{target_lang}`, `{context}`,
+/// `{glossary}` and `{text}` placeholders.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptTemplate(String);
+
+impl PromptTemplate {
+    pub fn new(template: impl Into<String>) -> Self {
+        Self(template.into())
+    }
+
+    /// Fills in the placeholders in a single pass, so that placeholders
+    /// written in the values themselves, say in the context, are kept.
+    fn render(&self, values: &PromptValues) -> String {
+        let placeholders = [
+            ("{source_lang}", values.source_lang),
+            ("{target_lang}", values.target_lang),
+            ("{context}", &values.context),
+            ("{glossary}", &values.glossary),
+            ("{text}", values.text),
+        ];
+
+        let mut rendered = String::with_capacity(self.0.len());
+        let mut rest = self.0.as_str();
+        while let Some(start) = rest.find('{') {
+            rendered.push_str(&rest[..start]);
+            rest = &rest[start..];
+            match placeholders.iter().find(|(name, _)| rest.starts_with(name)) {
+                Some((name, value)) => {
+                    rendered.push_str(value);
+                    rest = &rest[name.len()..];
+                }
+                None => {
+                    rendered.push('{');
+                    rest = &rest[1..];
+                }
+            }
+        }
+        rendered.push_str(rest);
+        rendered
+    }
+}
+
+struct PromptValues<'a> {
+    source_lang: &'a str,
+    target_lang: &'a str,
+    context: String,
+    glossary: String,
+    text: &'a str,
+}
+
+/// Backend for chat completion servers speaking the OpenAI API, such as
+/// Ollama, the llama.cpp server or vLLM.
+#[derive(Debug)]
+pub struct LlmBackend {
+    client: HttpClient,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+    system_prompt: PromptTemplate,
+    user_prompt: PromptTemplate,
+    temperature: f32,
+    context: Option<String>,
+    glossary: Vec<(String, String)>,
+}
+
+impl LlmBackend {
+    /// Creates a backend for `model` served at `base_url`, e.g.
+    /// `http://localhost:11434/v1` for Ollama.
+    pub fn new(base_url: impl Into<String>, model: impl Into<String>) -> Self {
+        Self {
+            client: HttpClient::default(),
+            base_url: base_url.into().trim_end_matches('/').to_owned(),
+            model: model.into(),
+            api_key: None,
+            system_prompt: PromptTemplate::new(DEFAULT_SYSTEM_PROMPT),
+            user_prompt: PromptTemplate::new(DEFAULT_USER_PROMPT),
+            temperature: 0.0,
+            context: None,
+            glossary: Vec::new(),
+        }
+    }
+
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    pub fn with_system_prompt(mut self, template: PromptTemplate) -> Self {
+        self.system_prompt = template;
+        self
+    }
+
+    pub fn with_user_prompt(mut self, template: PromptTemplate) -> Self {
+        self.user_prompt = template;
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = temperature;
+        self
+    }
+
+    /// Describes where the text comes from, e.g. "button label in a
+    /// checkout form", to disambiguate short strings.
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+
+    /// Adds a term that must always be translated as `translation`.
+    pub fn with_glossary_entry(
+        mut self,
+        term: impl Into<String>,
+        translation: impl Into<String>,
+    ) -> Self {
+        self.glossary.push((term.into(), translation.into()));
+        self
+    }
+
+    /// Translates all `texts` with a single JSON-mode request, sending each
+    /// text rendered with the user prompt.
+    pub async fn translate_many(
+        &self,
+        texts: &[&str],
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Vec<String>, TranslationError> {
+        let values = self.prompt_values("", source_lang, target_lang, None);
+        let system = format!(
+            "{}\n\n{}",
+            self.system_prompt.render(&values),
+            BATCH_INSTRUCTIONS
+        );
+        let texts: Vec<String> = texts
+            .iter()
+            .map(|text| {
+                let values = self.prompt_values(text, source_lang, target_lang, None);
+                self.user_prompt.render(&values)
+            })
+            .collect();
+        let user = json!({ "texts": texts }).to_string();
+
+        let content = self.complete(&system, &user, true).await?;
+        let json = parse_json(strip_code_fence(&content))?;
+        let invalid = || TranslationError::invalid_response(&content, None);
+
+        let translations: Vec<String> = match json["translations"].as_array() {
+            Some(translations) => translations
+                .iter()
+                .map(|translation| translation.as_str().map(|text| text.trim().to_owned()))
+                .collect::<Option<_>>()
+                .ok_or_else(invalid)?,
+            None => return Err(invalid()),
+        };
+
+        if translations.len() != texts.len() {
+            return Err(invalid());
+        }
+        Ok(translations)
+    }
+
+    /// Fills in the prompt placeholders. `context` describes `text` in
+    /// addition to the context configured on the backend.
+    fn prompt_values<'a>(
+        &self,
+        text: &'a str,
+        source_lang: &'a str,
+        target_lang: &'a str,
+        context: Option<&str>,
+    ) -> PromptValues<'a> {
+        let contexts: Vec<&str> = self.context.as_deref().into_iter().chain(context).collect();
+        let context = match contexts.is_empty() {
+            true => String::new(),
+            false => format!("\nContext: {}", contexts.join("\n")),
+        };
+        let glossary = if self.glossary.is_empty() {
+            String::new()
+        } else {
+            let entries: Vec<String> = self
+                .glossary
+                .iter()
+                .map(|(term, translation)| format!("- {} => {}", term, translation))
+                .collect();
+            format!("\nUse this glossary:\n{}", entries.join("\n"))
+        };
+
+        // Let the model work out the source language itself.
+        let source_lang = if source_lang == AUTO_DETECT {
+            "the language it is written in"
+        } else {
+            source_lang
+        };
+
+        PromptValues {
+            source_lang,
+            target_lang,
+            context,
+            glossary,
+            text,
+        }
+    }
+
+    async fn translate_text(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        context: Option<&str>,
+    ) -> Result<Translation, TranslationError> {
+        let values = self.prompt_values(text, source_lang, target_lang, context);
+        let system = self.system_prompt.render(&values);
+        let user = self.user_prompt.render(&values);
+
+        let content = self.complete(&system, &user, false).await?;
+        extract_translation(&content, text)
+            .map(|translation| Translation::new(text, translation))
+            .ok_or_else(|| TranslationError::NoTranslationFound(text.to_owned()))
+    }
+
+    async fn complete(
+        &self,
+        system: &str,
+        user: &str,
+        json_mode: bool,
+    ) -> Result<String, TranslationError> {
+        let mut body = json!({
+            "model": self.model,
+            "temperature": self.temperature,
+            "messages": [
+                { "role": "system", "content": system },
+                { "role": "user", "content": user },
+            ],
+        });
+        if json_mode {
+            body["response_format"] = json!({ "type": "json_object" });
+        }
+
+        let mut request = self
+            .client
+            .post(format!("{}/chat/completions", self.base_url))
+            .json(&body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let body = self.client.send(request).await?;
+        let json = parse_json(&body)?;
+
+        match json["choices"][0]["message"]["content"].as_str() {
+            Some(content) => Ok(content.to_owned()),
+            None => Err(TranslationError::invalid_response(&body, None)),
+        }
+    }
+}
+
+/// Removes a surrounding Markdown code fence, if any.
+fn strip_code_fence(content: &str) -> &str {
+    let content = content.trim();
+    match content
+        .strip_prefix("```")
+        .and_then(|rest| rest.strip_suffix("```"))
+    {
+        // Skip the info string (e.g. "json") on the opening line.
+        Some(inner) => inner
+            .split_once('\n')
+            .map_or(inner, |(_, body)| body)
+            .trim(),
+        None => content,
+    }
+}
+
+/// Extracts the translated text from a model reply, removing wrappers the
+/// model added around it but that were not part of `source`.
+fn extract_translation(content: &str, source: &str) -> Option<String> {
+    let mut text = strip_code_fence(content);
+
+    if let Some(inner) = text
+        .strip_prefix("<translation>")
+        .and_then(|rest| rest.strip_suffix("</translation>"))
+    {
+        text = inner.trim();
+    }
+
+    let source = source.trim();
+    for quote in ['"', '\'', '“', '«'] {
+        if source.starts_with(quote) {
+            continue;
+        }
+        let closing = match quote {
+            '“' => '”',
+            '«' => '»',
+            other => other,
+        };
+        if let Some(inner) = text
+            .strip_prefix(quote)
+            .and_then(|rest| rest.strip_suffix(closing))
+        {
+            text = inner.trim();
+        }
+    }
+
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_owned())
+    }
+}
+
+impl HttpBackend for LlmBackend {
+    fn with_client(mut self, client: HttpClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = base_url.into().trim_end_matches('/').to_owned();
+        self
+    }
+}
+
+#[async_trait]
+impl TranslationBackend for LlmBackend {
+    fn name(&self) -> &'static str {
+        "llm"
+    }
+
+    fn options(&self) -> String {
+        format!(
+            "{} {} {} {:?} {:?} {:?} {:?}",
+            self.base_url,
+            self.model,
+            self.temperature,
+            self.system_prompt,
+            self.user_prompt,
+            self.context,
+            self.glossary
+        )
+    }
+
+    fn max_batch_size(&self) -> usize {
+        MAX_BATCH_SIZE
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Translation, TranslationError> {
+        self.translate_text(text, source_lang, target_lang, None)
+            .await
+    }
+
+    async fn translate_with_context(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        context: &str,
+    ) -> Result<Translation, TranslationError> {
+        self.translate_text(text, source_lang, target_lang, Some(context))
+            .await
+    }
+
+    async fn translate_batch(
+        &self,
+        texts: &[&str],
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Vec<Translation>, TranslationError> {
+        let translations = self.translate_many(texts, source_lang, target_lang).await?;
+        Ok(texts
+            .iter()
+            .zip(translations)
+            .map(|(text, translation)| Translation::new(*text, translation))
+            .collect())
+    }
+
+    async fn detect(&self, text: &str) -> Result<Detection, TranslationError> {
+        let content = self.complete(DETECT_PROMPT, text, false).await?;
+        let code = strip_code_fence(&content)
+            .trim_matches(|c: char| !c.is_ascii_alphabetic())
+            .to_lowercase();
+
+        if (2..=3).contains(&code.len()) && code.chars().all(|c| c.is_ascii_lowercase()) {
+            Ok(Detection::new(code, None))
+        } else {
+            Err(TranslationError::invalid_response(&content, None))
+        }
+    }
+
+    /// Language models do not publish the languages they handle, so this
+    /// returns an empty list and any language code is passed through.
+    async fn supported_languages(&self) -> Result<Vec<String>, TranslationError> {
+        Ok(Vec::new())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer};
+
+    fn reply(content: &str) -> MockResponse {
+        MockResponse::json(
+            200,
+            json!({"choices": [{"message": {"role": "assistant", "content": content}}]}),
+        )
+    }
+
+    #[tokio::test]
+    async fn test_translate_renders_prompts() {
+        let server = MockServer::start(|_| reply("```\n\"Bonjour le monde\"\n```")).await;
+        let backend = LlmBackend::new(format!("{}/v1", server.url()), "llama3")
+            .with_api_key("token")
+            .with_temperature(0.2)
+            .with_user_prompt(PromptTemplate::new("[{source_lang}>{target_lang}] {text}"))
+            .with_context("greeting on a home page")
+            .with_glossary_entry("world", "monde");
+
+        let translation = backend.translate("Hello world", "en", "fr").await.unwrap();
+        assert_eq!(translation.text, "Bonjour le monde");
+
+        let request = &server.requests()[0];
+        assert_eq!(request.path, "/v1/chat/completions");
+        assert_eq!(request.header("authorization"), Some("Bearer token"));
+        let body = request.json();
+        assert_eq!(body["model"], "llama3");
+        assert!(body.get("response_format").is_none());
+        let system = body["messages"][0]["content"].as_str().unwrap();
+        assert!(system.contains("from en to fr"));
+        assert!(system.contains("Context: greeting on a home page"));
+        assert!(system.contains("- world => monde"));
+        assert_eq!(body["messages"][1]["content"], "[en>fr] Hello world");
+    }
+
+    #[test]
+    fn test_render_keeps_placeholders_in_values() {
+        let backend = LlmBackend::new("http://localhost", "llama3")
+            .with_context("a {text} field")
+            .with_glossary_entry("{source_lang}", "{target_lang}");
+        let values = backend.prompt_values("Hi {context}", "en", "fr", None);
+
+        let template =
+            PromptTemplate::new("{source_lang}>{target_lang}{context}{glossary} {x} {text}");
+        assert_eq!(
+            template.render(&values),
+            "en>fr\nContext: a {text} field\nUse this glossary:\n\
+             - {source_lang} => {target_lang} {x} Hi {context}"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_translate_many_uses_json_mode() {
+        let server =
+            MockServer::start(|_| reply(r#"{"translations": ["Bonjour", "Au revoir"]}"#)).await;
+        let backend = LlmBackend::new(server.url(), "llama3")
+            .with_user_prompt(PromptTemplate::new("<{text}>"));
+
+        let translations = backend
+            .translate_many(&["Hello", "Goodbye"], "en", "fr")
+            .await
+            .unwrap();
+        assert_eq!(translations, ["Bonjour", "Au revoir"]);
+
+        let body = server.requests()[0].json();
+        assert_eq!(body["response_format"]["type"], "json_object");
+        assert_eq!(
+            body["messages"][1]["content"],
+            r#"{"texts":["<Hello>","<Goodbye>"]}"#
+        );
+
+        let error = backend
+            .translate_many(&["Hello"], "en", "fr")
+            .await
+            .unwrap_err();
+        assert!(matches!(error, TranslationError::ResponseParsingFailed(_)));
+    }
+
+    #[test]
+    fn test_extract_translation() {
+        assert_eq!(extract_translation("  Hallo \n", "Hello").unwrap(), "Hallo");
+        assert_eq!(
+            extract_translation("<translation>Hallo</translation>", "Hello").unwrap(),
+            "Hallo"
+        );
+        assert_eq!(
+            extract_translation("\"Hallo\"", "\"Hello\"").unwrap(),
+            "\"Hallo\""
+        );
+        assert_eq!(extract_translation("« Salut »", "Hi").unwrap(), "Salut");
+        assert!(extract_translation("```\n```", "Hello").is_none());
+    }
+}
@@ -0,0 +1,161 @@
+use crate::{Detection, Language, Lookup, RateLimit, Translation, TranslationError};
+use async_trait::async_trait;
+use std::fmt;
+
+#[cfg(feature = "deepl")]
+mod deepl;
+#[cfg(feature = "google")]
+mod google;
+#[cfg(feature = "http")]
+mod http;
+#[cfg(feature = "libretranslate")]
+mod libretranslate;
+#[cfg(feature = "llm")]
+mod llm;
+#[cfg(feature = "offline")]
+mod offline;
+
+#[cfg(any(
+    feature = "google",
+    feature = "deepl",
+    feature = "libretranslate",
+    feature = "llm"
+))]
+use http::parse_json;
+
+#[cfg(feature = "deepl")]
+pub use deepl::{DeepLBackend, Formality, SplitSentences, TagHandling};
+#[cfg(feature = "google")]
+pub use google::GoogleGtxBackend;
+#[cfg(feature = "http")]
+pub use http::{HttpBackend, HttpClient};
+#[cfg(feature = "libretranslate")]
+pub use libretranslate::{LibreTranslateBackend, LibreTranslation, TextFormat};
+#[cfg(feature = "llm")]
+pub use llm::{LlmBackend, PromptTemplate};
+#[cfg(feature = "offline")]
+pub use offline::{Dictionary, OfflineBackend};
+
+/// A translation provider that `Translator` delegates its requests to.
+#[async_trait]
+pub trait TranslationBackend: fmt::Debug + Send + Sync {
+    /// Short identifier of the backend, e.g. `"google"`.
+    fn name(&self) -> &'static str;
+
+    /// Settings that change this backend's output, e.g. its model or
+    /// formality. Cached translations are keyed by them.
+    fn options(&self) -> String {
+        String::new()
+    }
+
+    /// Limits the provider enforces, applied by `Translator` unless it is
+    /// given its own.
+    fn rate_limit(&self) -> Option<RateLimit> {
+        None
+    }
+
+    /// Maps a language to the code this backend expects in requests.
+    fn language_code(&self, language: &Language) -> String {
+        language.to_string()
+    }
+
+    /// Translates `text` from `source_lang` to `target_lang`. A source
+    /// language of [`AUTO_DETECT`](crate::AUTO_DETECT) asks the backend to
+    /// detect it and report it on the result.
+    async fn translate(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Translation, TranslationError>;
+
+    /// Translates `text` like [`translate`](Self::translate), given
+    /// `context` describing where it appears, e.g. a comment left for
+    /// translators. The context is not translated itself; backends that
+    /// cannot use it ignore it.
+    async fn translate_with_context(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+        _context: &str,
+    ) -> Result<Translation, TranslationError> {
+        self.translate(text, source_lang, target_lang).await
+    }
+
+    /// Longest text, in characters, accepted in one request. Longer input
+    /// is split into chunks by `Translator`.
+    fn max_text_len(&self) -> Option<usize> {
+        None
+    }
+
+    /// Largest number of texts [`translate_batch`](Self::translate_batch)
+    /// accepts in one request; 1 when the backend cannot batch.
+    fn max_batch_size(&self) -> usize {
+        1
+    }
+
+    /// Translates several texts in a single request, returning one
+    /// translation per text in the same order.
+    async fn translate_batch(
+        &self,
+        _texts: &[&str],
+        _source_lang: &str,
+        _target_lang: &str,
+    ) -> Result<Vec<Translation>, TranslationError> {
+        Err(TranslationError::UnsupportedOperation("batch translation"))
+    }
+
+    /// Looks up word-level details such as alternatives and definitions.
+    async fn lookup(
+        &self,
+        _text: &str,
+        _source_lang: &str,
+        _target_lang: &str,
+    ) -> Result<Lookup, TranslationError> {
+        Err(TranslationError::UnsupportedOperation("lookup"))
+    }
+
+    /// Detects the language of `text`.
+    async fn detect(&self, text: &str) -> Result<Detection, TranslationError>;
+
+    /// Returns the language codes accepted by this backend.
+    async fn supported_languages(&self) -> Result<Vec<String>, TranslationError>;
+
+    /// Returns the source/target pairs this backend can translate between.
+    /// Defaults to every combination of the supported languages; codes that
+    /// are not known language tags are skipped.
+    async fn supported_pairs(&self) -> Result<Vec<(Language, Language)>, TranslationError> {
+        let languages = parse_languages(self.supported_languages().await?);
+        Ok(all_pairs(&languages, &languages))
+    }
+}
+
+/// Parses language codes reported by a backend, dropping unknown ones.
+fn parse_languages<I>(codes: I) -> Vec<Language>
+where
+    I: IntoIterator,
+    I::Item: AsRef<str>,
+{
+    let mut languages: Vec<Language> = Vec::new();
+    for code in codes {
+        if let Ok(language) = Language::parse(code.as_ref()) {
+            if !languages.contains(&language) {
+                languages.push(language);
+            }
+        }
+    }
+    languages
+}
+
+fn all_pairs(sources: &[Language], targets: &[Language]) -> Vec<(Language, Language)> {
+    sources
+        .iter()
+        .flat_map(|source| {
+            targets
+                .iter()
+                .filter(move |target| *target != source)
+                .map(move |target| (source.clone(), target.clone()))
+        })
+        .collect()
+}
@@ -0,0 +1,99 @@
+use super::{invalid_data, Dictionary};
+use quick_xml::events::Event;
+use quick_xml::Reader;
+use std::io;
+use std::path::Path;
+
+pub(super) fn load(dictionary: &mut Dictionary, path: &Path) -> io::Result<()> {
+    parse(dictionary, &std::fs::read_to_string(path)?)
+}
+
+/// Reads `<entry>` elements, taking headwords from `<orth>` and translations
+/// from `<cit type="trans"><quote>` (TEI P5) or `<trans><tr>` (TEI P4).
+fn parse(dictionary: &mut Dictionary, contents: &str) -> io::Result<()> {
+    let mut reader = Reader::from_str(contents);
+    let mut path: Vec<Vec<u8>> = Vec::new();
+    let mut in_translation = Vec::new();
+    let mut headwords: Vec<String> = Vec::new();
+    let mut translations: Vec<String> = Vec::new();
+    let mut text = String::new();
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) => {
+                let name = element.local_name().as_ref().to_vec();
+                let is_translation = match name.as_slice() {
+                    b"cit" => element.attributes().flatten().any(|attribute| {
+                        attribute.key.local_name().as_ref() == b"type"
+                            && attribute.value.as_ref() == b"trans"
+                    }),
+                    b"trans" => true,
+                    _ => false,
+                };
+                if name == b"entry" {
+                    headwords.clear();
+                    translations.clear();
+                }
+                if matches!(name.as_slice(), b"orth" | b"quote" | b"tr") {
+                    text.clear();
+                }
+                in_translation.push(is_translation);
+                path.push(name);
+            }
+            Ok(Event::Text(content)) => {
+                let content = content
+                    .unescape()
+                    .map_err(|error| invalid_data(error.to_string()))?;
+                text.push_str(&content);
+            }
+            Ok(Event::End(_)) => {
+                let name = path.pop().unwrap_or_default();
+                in_translation.pop();
+                let translating = in_translation.iter().any(|&flag| flag);
+                match name.as_slice() {
+                    b"orth" => headwords.push(text.trim().to_owned()),
+                    b"quote" | b"tr" if translating => translations.push(text.trim().to_owned()),
+                    b"entry" => {
+                        for headword in &headwords {
+                            for translation in &translations {
+                                dictionary.insert(headword, translation);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Ok(Event::Eof) => return Ok(()),
+            Ok(_) => {}
+            Err(error) => return Err(invalid_data(error.to_string())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let tei = r#"<?xml version="1.0" encoding="UTF-8"?>
+<TEI xmlns="http://www.tei-c.org/ns/1.0"><text><body>
+  <entry>
+    <form><orth>cat</orth></form>
+    <sense><cit type="trans"><quote>Katze</quote></cit></sense>
+    <sense><cit type="example"><quote>the cat sat</quote></cit></sense>
+  </entry>
+  <entry>
+    <form><orth>rock &amp; roll</orth></form>
+    <trans><tr>Rock 'n' Roll</tr></trans>
+  </entry>
+</body></text></TEI>"#;
+
+        let mut dictionary = Dictionary::new("en", "de");
+        parse(&mut dictionary, tei).unwrap();
+
+        assert_eq!(dictionary.lookup("cat").unwrap(), ["Katze"]);
+        assert_eq!(dictionary.lookup("rock & roll").unwrap(), ["Rock 'n' Roll"]);
+        assert_eq!(dictionary.len(), 2);
+    }
+}
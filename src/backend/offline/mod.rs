@@ -0,0 +1,425 @@
+use super::TranslationBackend;
+use crate::{Detection, Language, Translation, TranslationError, AUTO_DETECT};
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+
+mod freedict;
+mod stardict;
+mod tsv;
+
+/// An in-memory bilingual dictionary indexed by normalized headword.
+#[derive(Debug, Clone)]
+pub struct Dictionary {
+    source_lang: String,
+    target_lang: String,
+    entries: HashMap<String, Vec<String>>,
+    max_phrase_words: usize,
+}
+
+impl Dictionary {
+    pub fn new(source_lang: impl Into<String>, target_lang: impl Into<String>) -> Self {
+        Self {
+            source_lang: source_lang.into(),
+            target_lang: target_lang.into(),
+            entries: HashMap::new(),
+            max_phrase_words: 1,
+        }
+    }
+
+    /// Loads a tab-separated file with a headword in the first column and
+    /// translations in the remaining ones.
+    pub fn from_tsv(
+        path: impl AsRef<Path>,
+        source_lang: impl Into<String>,
+        target_lang: impl Into<String>,
+    ) -> io::Result<Self> {
+        let mut dictionary = Self::new(source_lang, target_lang);
+        tsv::load(&mut dictionary, path.as_ref())?;
+        Ok(dictionary)
+    }
+
+    /// Loads a StarDict dictionary from its `.ifo` file; the `.idx` and
+    /// `.dict` (or `.dict.dz`) files are expected next to it.
+    pub fn from_stardict(
+        ifo_path: impl AsRef<Path>,
+        source_lang: impl Into<String>,
+        target_lang: impl Into<String>,
+    ) -> io::Result<Self> {
+        let mut dictionary = Self::new(source_lang, target_lang);
+        stardict::load(&mut dictionary, ifo_path.as_ref())?;
+        Ok(dictionary)
+    }
+
+    /// Loads a FreeDict TEI XML file.
+    pub fn from_freedict(
+        path: impl AsRef<Path>,
+        source_lang: impl Into<String>,
+        target_lang: impl Into<String>,
+    ) -> io::Result<Self> {
+        let mut dictionary = Self::new(source_lang, target_lang);
+        freedict::load(&mut dictionary, path.as_ref())?;
+        Ok(dictionary)
+    }
+
+    pub fn source_lang(&self) -> &str {
+        &self.source_lang
+    }
+
+    pub fn target_lang(&self) -> &str {
+        &self.target_lang
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn insert(&mut self, headword: &str, translation: &str) {
+        let key = normalize(headword);
+        let translation = translation.trim();
+        if key.is_empty() || translation.is_empty() {
+            return;
+        }
+
+        self.max_phrase_words = self.max_phrase_words.max(key.split(' ').count());
+        let translations = self.entries.entry(key).or_default();
+        if !translations.iter().any(|existing| existing == translation) {
+            translations.push(translation.to_owned());
+        }
+    }
+
+    /// Looks up a word or phrase, ignoring case and falling back to simple
+    /// lemmatized forms ("cities" finds "city").
+    pub fn lookup(&self, text: &str) -> Option<&[String]> {
+        let key = normalize(text);
+        if let Some(translations) = self.entries.get(&key) {
+            return Some(translations);
+        }
+        lemmas(&key)
+            .iter()
+            .find_map(|lemma| self.entries.get(lemma))
+            .map(|translations| translations.as_slice())
+    }
+
+    /// Translates `text` entry by entry, preferring the longest phrase that
+    /// matches at each position. Unknown words are kept as they are; `None`
+    /// is returned if nothing matched at all.
+    pub fn translate(&self, text: &str) -> Option<String> {
+        if let Some(translations) = self.lookup(text) {
+            return Some(match_case(text.trim(), &translations[0]));
+        }
+
+        let tokens = tokenize(text);
+        let words: Vec<usize> = (0..tokens.len()).filter(|&i| tokens[i].1).collect();
+        let mut output = String::new();
+        let mut matched = false;
+        let mut next_token = 0;
+        let mut word = 0;
+
+        while word < words.len() {
+            let longest = (1..=self.max_phrase_words.min(words.len() - word))
+                .rev()
+                .find_map(|count| {
+                    let first = tokens[words[word]].0;
+                    let last = words[word + count - 1];
+                    let phrase: String = tokens[words[word]..=last]
+                        .iter()
+                        .map(|(token, _)| *token)
+                        .collect();
+                    self.lookup(&phrase)
+                        .map(|translations| (count, match_case(first, &translations[0])))
+                });
+
+            let start = words[word];
+            for (token, _) in &tokens[next_token..start] {
+                output.push_str(token);
+            }
+            match longest {
+                Some((count, translation)) => {
+                    output.push_str(&translation);
+                    matched = true;
+                    next_token = words[word + count - 1] + 1;
+                    word += count;
+                }
+                None => {
+                    output.push_str(tokens[start].0);
+                    next_token = start + 1;
+                    word += 1;
+                }
+            }
+        }
+        for (token, _) in &tokens[next_token..] {
+            output.push_str(token);
+        }
+
+        matched.then_some(output)
+    }
+
+    fn known_words(&self, text: &str) -> usize {
+        tokenize(text)
+            .into_iter()
+            .filter(|(token, is_word)| *is_word && self.lookup(token).is_some())
+            .count()
+    }
+}
+
+/// Backend answering lookups from bilingual dictionaries on disk, without
+/// any network access.
+#[derive(Debug, Default)]
+pub struct OfflineBackend {
+    dictionaries: Vec<Dictionary>,
+}
+
+impl OfflineBackend {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_dictionary(mut self, dictionary: Dictionary) -> Self {
+        self.dictionaries.push(dictionary);
+        self
+    }
+
+    fn dictionary(&self, source_lang: &str, target_lang: &str) -> Option<&Dictionary> {
+        self.dictionaries.iter().find(|dictionary| {
+            (source_lang == AUTO_DETECT || dictionary.source_lang == source_lang)
+                && dictionary.target_lang == target_lang
+        })
+    }
+}
+
+#[async_trait]
+impl TranslationBackend for OfflineBackend {
+    fn name(&self) -> &'static str {
+        "offline"
+    }
+
+    /// Dictionaries are keyed by primary language only.
+    fn language_code(&self, language: &Language) -> String {
+        language.code().to_owned()
+    }
+
+    async fn translate(
+        &self,
+        text: &str,
+        source_lang: &str,
+        target_lang: &str,
+    ) -> Result<Translation, TranslationError> {
+        let dictionary = match self.dictionary(source_lang, target_lang) {
+            Some(dictionary) => dictionary,
+            None => {
+                return Err(TranslationError::UnsupportedLanguagePair(
+                    source_lang.to_owned(),
+                    target_lang.to_owned(),
+                ))
+            }
+        };
+
+        let translation = match dictionary.translate(text) {
+            Some(translation) => Translation::new(text, translation),
+            None => return Err(TranslationError::NoTranslationFound(text.to_owned())),
+        };
+
+        if source_lang == AUTO_DETECT {
+            Ok(translation.with_detected_source_lang(&dictionary.source_lang, None))
+        } else {
+            Ok(translation)
+        }
+    }
+
+    async fn detect(&self, text: &str) -> Result<Detection, TranslationError> {
+        let words = tokenize(text)
+            .iter()
+            .filter(|(_, is_word)| *is_word)
+            .count();
+
+        self.dictionaries
+            .iter()
+            .map(|dictionary| (dictionary.known_words(text), dictionary))
+            .filter(|(known, _)| *known > 0)
+            .max_by_key(|(known, _)| *known)
+            .map(|(known, dictionary)| {
+                let confidence = known as f64 / words as f64;
+                Detection::new(&dictionary.source_lang, Some(confidence))
+            })
+            .ok_or_else(|| TranslationError::NoTranslationFound(text.to_owned()))
+    }
+
+    async fn supported_languages(&self) -> Result<Vec<String>, TranslationError> {
+        let mut languages: Vec<String> = self
+            .dictionaries
+            .iter()
+            .flat_map(|dictionary| [&dictionary.source_lang, &dictionary.target_lang])
+            .cloned()
+            .collect();
+        languages.sort();
+        languages.dedup();
+        Ok(languages)
+    }
+
+    async fn supported_pairs(&self) -> Result<Vec<(Language, Language)>, TranslationError> {
+        let mut pairs = Vec::new();
+        for dictionary in &self.dictionaries {
+            let source = Language::parse(&dictionary.source_lang);
+            let target = Language::parse(&dictionary.target_lang);
+            if let (Ok(source), Ok(target)) = (source, target) {
+                if !pairs.contains(&(source.clone(), target.clone())) {
+                    pairs.push((source, target));
+                }
+            }
+        }
+        Ok(pairs)
+    }
+}
+
+/// Lowercases `text`, collapses whitespace and trims surrounding punctuation.
+fn normalize(text: &str) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    words
+        .join(" ")
+        .trim_matches(|c: char| !c.is_alphanumeric())
+        .to_lowercase()
+}
+
+/// Candidate base forms of an English-style inflected word.
+fn lemmas(word: &str) -> Vec<String> {
+    let mut lemmas = Vec::new();
+    let stem = |suffix: &str| {
+        word.strip_suffix(suffix)
+            .filter(|stem| stem.chars().count() > 1)
+    };
+
+    if let Some(stem) = stem("ies") {
+        lemmas.push(format!("{}y", stem));
+    }
+    if let Some(stem) = stem("ied") {
+        lemmas.push(format!("{}y", stem));
+    }
+    for suffix in ["es", "s", "ed", "ing", "er", "est"] {
+        if suffix == "s" && word.ends_with("ss") {
+            continue;
+        }
+        if let Some(stem) = stem(suffix) {
+            lemmas.push(stem.to_owned());
+            if matches!(suffix, "ed" | "ing" | "er" | "est") {
+                lemmas.push(format!("{}e", stem));
+                // "stopped" -> "stop"
+                let mut chars = stem.chars().rev();
+                if let (Some(last), Some(previous)) = (chars.next(), chars.next()) {
+                    if last == previous {
+                        lemmas.push(stem[..stem.len() - last.len_utf8()].to_owned());
+                    }
+                }
+            }
+        }
+    }
+    lemmas
+}
+
+/// Splits `text` into alternating word and non-word tokens.
+fn tokenize(text: &str) -> Vec<(&str, bool)> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut in_word = None;
+
+    for (index, c) in text.char_indices() {
+        let is_word = c.is_alphanumeric() || c == '\'' || c == '-';
+        if in_word != Some(is_word) {
+            if let Some(previous) = in_word {
+                tokens.push((&text[start..index], previous));
+            }
+            start = index;
+            in_word = Some(is_word);
+        }
+    }
+    if let Some(previous) = in_word {
+        tokens.push((&text[start..], previous));
+    }
+    tokens
+}
+
+/// Capitalizes `translation` if `source` starts with an uppercase letter.
+fn match_case(source: &str, translation: &str) -> String {
+    let source_capitalized = source.chars().next().is_some_and(char::is_uppercase);
+    let mut chars = translation.chars();
+    match chars.next() {
+        Some(first) if source_capitalized => first.to_uppercase().chain(chars).collect(),
+        _ => translation.to_owned(),
+    }
+}
+
+/// Splits a dictionary definition into individual translations.
+fn split_translations(definition: &str) -> impl Iterator<Item = &str> {
+    definition
+        .split(['\n', ';'])
+        .map(str::trim)
+        .filter(|translation| !translation.is_empty())
+}
+
+fn invalid_data(message: impl Into<String>) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary() -> Dictionary {
+        let mut dictionary = Dictionary::new("en", "de");
+        dictionary.insert("city", "Stadt");
+        dictionary.insert("big", "groß");
+        dictionary.insert("good morning", "guten Morgen");
+        dictionary.insert("stop", "anhalten");
+        dictionary
+    }
+
+    #[test]
+    fn test_lookup_is_case_insensitive_and_lemmatized() {
+        let dictionary = dictionary();
+        assert_eq!(dictionary.lookup("City").unwrap(), ["Stadt"]);
+        assert_eq!(dictionary.lookup("cities").unwrap(), ["Stadt"]);
+        assert_eq!(dictionary.lookup("stopped").unwrap(), ["anhalten"]);
+        assert!(dictionary.lookup("village").is_none());
+    }
+
+    #[test]
+    fn test_translate_prefers_longest_phrase() {
+        let dictionary = dictionary();
+        assert_eq!(
+            dictionary.translate("Good morning, big city!").unwrap(),
+            "Guten Morgen, groß Stadt!"
+        );
+        assert_eq!(dictionary.translate("big village").unwrap(), "groß village");
+        assert!(dictionary.translate("village").is_none());
+    }
+
+    #[tokio::test]
+    async fn test_backend_selects_dictionary() {
+        let backend = OfflineBackend::new().with_dictionary(dictionary());
+
+        assert_eq!(
+            backend.translate("city", "en", "de").await.unwrap().text,
+            "Stadt"
+        );
+        assert_eq!(
+            backend.translate("city", "auto", "de").await.unwrap().text,
+            "Stadt"
+        );
+        assert!(matches!(
+            backend.translate("city", "en", "fr").await,
+            Err(TranslationError::UnsupportedLanguagePair(_, _))
+        ));
+        let detection = backend.detect("the big city").await.unwrap();
+        assert_eq!(detection.language, "en");
+        assert_eq!(detection.confidence, Some(2.0 / 3.0));
+
+        let en = Language::parse("en").unwrap();
+        let de = Language::parse("de").unwrap();
+        assert_eq!(backend.supported_pairs().await.unwrap(), [(en, de)]);
+    }
+}
@@ -0,0 +1,271 @@
+use super::{invalid_data, split_translations, Dictionary};
+use flate2::read::GzDecoder;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+const IFO_MAGIC: &str = "StarDict's dict ifo file";
+
+pub(super) fn load(dictionary: &mut Dictionary, ifo_path: &Path) -> io::Result<()> {
+    let info = parse_ifo(&fs::read_to_string(ifo_path)?)?;
+    let index = read_maybe_gzipped(&ifo_path.with_extension("idx"))?;
+    let data = read_maybe_gzipped(&ifo_path.with_extension("dict"))?;
+    let offset_bits = match info.get("idxoffsetbits").map(String::as_str) {
+        Some("64") => 64,
+        _ => 32,
+    };
+    let same_type_sequence = info.get("sametypesequence").map(String::as_str);
+
+    for (headword, offset, size) in parse_index(&index, offset_bits)? {
+        let entry = offset
+            .checked_add(size)
+            .and_then(|end| data.get(offset..end))
+            .ok_or_else(|| invalid_data(format!("entry for {:?} is out of bounds", headword)))?;
+        for definition in parse_entry(entry, same_type_sequence) {
+            for translation in split_translations(&definition) {
+                dictionary.insert(&headword, translation);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn parse_ifo(contents: &str) -> io::Result<HashMap<String, String>> {
+    let mut lines = contents.lines();
+    if lines.next().map(str::trim) != Some(IFO_MAGIC) {
+        return Err(invalid_data("not a StarDict .ifo file"));
+    }
+
+    Ok(lines
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_owned(), value.trim().to_owned()))
+        .collect())
+}
+
+/// Reads `path`, or `path.gz`/`path.dz` decompressed if only those exist.
+fn read_maybe_gzipped(path: &Path) -> io::Result<Vec<u8>> {
+    if path.exists() {
+        return fs::read(path);
+    }
+
+    for suffix in ["gz", "dz"] {
+        let mut compressed = path.as_os_str().to_owned();
+        compressed.push(".");
+        compressed.push(suffix);
+        let compressed = PathBuf::from(compressed);
+        if compressed.exists() {
+            let mut contents = Vec::new();
+            GzDecoder::new(fs::File::open(compressed)?).read_to_end(&mut contents)?;
+            return Ok(contents);
+        }
+    }
+
+    Err(io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not found", path.display()),
+    ))
+}
+
+fn parse_index(index: &[u8], offset_bits: u32) -> io::Result<Vec<(String, usize, usize)>> {
+    let offset_len = (offset_bits / 8) as usize;
+    let mut entries = Vec::new();
+    let mut rest = index;
+
+    while !rest.is_empty() {
+        let end = rest
+            .iter()
+            .position(|&byte| byte == 0)
+            .ok_or_else(|| invalid_data("unterminated headword in .idx file"))?;
+        let headword = String::from_utf8_lossy(&rest[..end]).into_owned();
+        rest = &rest[end + 1..];
+
+        if rest.len() < offset_len + 4 {
+            return Err(invalid_data("truncated .idx file"));
+        }
+        let offset = read_be(&rest[..offset_len]);
+        let size = read_be(&rest[offset_len..offset_len + 4]);
+        rest = &rest[offset_len + 4..];
+
+        entries.push((headword, offset, size));
+    }
+    Ok(entries)
+}
+
+fn read_be(bytes: &[u8]) -> usize {
+    bytes
+        .iter()
+        .fold(0, |value, &byte| (value << 8) | usize::from(byte))
+}
+
+/// Extracts the textual fields of a `.dict` entry.
+fn parse_entry(entry: &[u8], same_type_sequence: Option<&str>) -> Vec<String> {
+    let mut definitions = Vec::new();
+
+    match same_type_sequence {
+        Some(types) => {
+            let types: Vec<char> = types.chars().collect();
+            let mut rest = entry;
+            for (i, &kind) in types.iter().enumerate() {
+                let last = i + 1 == types.len();
+                let (field, remaining) = if kind.is_ascii_lowercase() {
+                    // The last field takes the rest of the entry.
+                    if last {
+                        (rest, &rest[rest.len()..])
+                    } else {
+                        split_at_nul(rest)
+                    }
+                } else {
+                    skip_binary(rest, last)
+                };
+                if is_text_type(kind) {
+                    definitions.push(field_text(field, kind));
+                }
+                rest = remaining;
+            }
+        }
+        None => {
+            let mut rest = entry;
+            while let Some((&kind, remaining)) = rest.split_first() {
+                let kind = char::from(kind);
+                let (field, remaining) = if kind.is_ascii_lowercase() {
+                    split_at_nul(remaining)
+                } else {
+                    skip_binary(remaining, false)
+                };
+                if is_text_type(kind) {
+                    definitions.push(field_text(field, kind));
+                }
+                rest = remaining;
+            }
+        }
+    }
+
+    definitions
+}
+
+fn split_at_nul(bytes: &[u8]) -> (&[u8], &[u8]) {
+    match bytes.iter().position(|&byte| byte == 0) {
+        Some(end) => (&bytes[..end], &bytes[end + 1..]),
+        None => (bytes, &bytes[bytes.len()..]),
+    }
+}
+
+/// Skips a binary field, which is prefixed by its size unless it is the
+/// last field of a `sametypesequence` entry.
+fn skip_binary(bytes: &[u8], last: bool) -> (&[u8], &[u8]) {
+    if last || bytes.len() < 4 {
+        return (&bytes[..0], &bytes[bytes.len()..]);
+    }
+    let size = read_be(&bytes[..4]).min(bytes.len() - 4);
+    (&bytes[..0], &bytes[4 + size..])
+}
+
+fn is_text_type(kind: char) -> bool {
+    matches!(kind, 'm' | 'l' | 't' | 'y' | 'g' | 'x' | 'h' | 'k' | 'w')
+}
+
+fn field_text(field: &[u8], kind: char) -> String {
+    let text = String::from_utf8_lossy(field);
+    if matches!(kind, 'g' | 'x' | 'h' | 'k' | 'w') {
+        strip_markup(&text)
+    } else {
+        text.into_owned()
+    }
+}
+
+/// Removes markup tags, turning line breaks into newlines.
+fn strip_markup(text: &str) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut tag = None;
+
+    for c in text.chars() {
+        match (c, &mut tag) {
+            ('<', None) => tag = Some(String::new()),
+            ('>', Some(name)) => {
+                let name = name.trim_start_matches('/').to_lowercase();
+                if name.starts_with("br") || name == "p" || name == "div" {
+                    output.push('\n');
+                }
+                tag = None;
+            }
+            (c, Some(name)) => name.push(c),
+            (c, None) => output.push(c),
+        }
+    }
+
+    output
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&amp;", "&")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_load() {
+        let dir =
+            std::env::temp_dir().join(format!("rustranslate-stardict-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+
+        let entries = [("apple", "Apfel"), ("house", "<b>Haus</b><br>Gebäude")];
+        let mut index = Vec::new();
+        let mut data = Vec::new();
+        for (headword, definition) in entries {
+            index.extend_from_slice(headword.as_bytes());
+            index.push(0);
+            index.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            index.extend_from_slice(&(definition.len() as u32).to_be_bytes());
+            data.extend_from_slice(definition.as_bytes());
+        }
+        fs::write(
+            dir.join("en-de.ifo"),
+            format!(
+                "{}\nversion=2.4.2\nwordcount=2\nsametypesequence=h\n",
+                IFO_MAGIC
+            ),
+        )
+        .unwrap();
+        fs::write(dir.join("en-de.idx"), index).unwrap();
+        fs::write(dir.join("en-de.dict"), data).unwrap();
+
+        let dictionary = Dictionary::from_stardict(dir.join("en-de.ifo"), "en", "de").unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(dictionary.lookup("apple").unwrap(), ["Apfel"]);
+        assert_eq!(dictionary.lookup("houses").unwrap(), ["Haus", "Gebäude"]);
+    }
+
+    #[test]
+    fn test_load_rejects_overflowing_entries() {
+        let dir = std::env::temp_dir().join(format!(
+            "rustranslate-stardict-overflow-{}",
+            std::process::id()
+        ));
+        fs::create_dir_all(&dir).unwrap();
+        let mut index = b"apple\0".to_vec();
+        index.extend_from_slice(&u64::MAX.to_be_bytes());
+        index.extend_from_slice(&2u32.to_be_bytes());
+        fs::write(
+            dir.join("en-de.ifo"),
+            format!("{}\nidxoffsetbits=64\n", IFO_MAGIC),
+        )
+        .unwrap();
+        fs::write(dir.join("en-de.idx"), index).unwrap();
+        fs::write(dir.join("en-de.dict"), "Apfel").unwrap();
+
+        let result = Dictionary::from_stardict(dir.join("en-de.ifo"), "en", "de");
+        fs::remove_dir_all(&dir).unwrap();
+        let error = result.unwrap_err();
+        assert!(error.to_string().contains("out of bounds"), "{}", error);
+    }
+
+    #[test]
+    fn test_parse_entry_without_same_type_sequence() {
+        let entry = b"mfirst\0tfa\xc9\xaa\0";
+        assert_eq!(parse_entry(entry, None), ["first", "faɪ"]);
+    }
+}
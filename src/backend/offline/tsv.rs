@@ -0,0 +1,44 @@
+use super::{split_translations, Dictionary};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub(super) fn load(dictionary: &mut Dictionary, path: &Path) -> io::Result<()> {
+    parse(dictionary, &fs::read_to_string(path)?);
+    Ok(())
+}
+
+fn parse(dictionary: &mut Dictionary, contents: &str) {
+    for line in contents.lines() {
+        if line.trim().is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let mut columns = line.split('\t');
+        let headword = columns.next().unwrap_or_default();
+        for translation in columns.flat_map(split_translations) {
+            dictionary.insert(headword, translation);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        let mut dictionary = Dictionary::new("en", "fr");
+        parse(
+            &mut dictionary,
+            "# English-French\nhouse\tmaison; demeure\tlogis\n\ncat\tchat\n",
+        );
+
+        assert_eq!(
+            dictionary.lookup("house").unwrap(),
+            ["maison", "demeure", "logis"]
+        );
+        assert_eq!(dictionary.lookup("cat").unwrap(), ["chat"]);
+        assert_eq!(dictionary.len(), 2);
+    }
+}
@@ -0,0 +1,251 @@
+//! Configuration of the HTTP client a `Translator` sends requests with.
+
+#[cfg(feature = "google")]
+use crate::backend::GoogleGtxBackend;
+use crate::backend::{HttpBackend, HttpClient};
+use crate::{TranslationError, Translator};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Certificate, Client, Proxy};
+use std::time::Duration;
+
+/// Builds a [`Translator`] for an HTTP backend, with control over the server
+/// it talks to and the client it uses.
+///
+/// ```no_run
+/// # fn example() -> Result<(), Box<dyn std::error::Error>> {
+/// use rustranslate::{reqwest::Proxy, Translator};
+/// use std::time::Duration;
+///
+/// let translator = Translator::builder("en", "de")
+///     .with_proxy(Proxy::all("http://proxy.example.com:3128")?)
+///     .with_connect_timeout(Duration::from_secs(5))
+///     .with_timeout(Duration::from_secs(30))
+///     .with_user_agent("my-app/1.0")
+///     .build()?;
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Debug)]
+pub struct TranslatorBuilder {
+    source_lang: String,
+    target_lang: String,
+    base_url: Option<String>,
+    client: Option<Client>,
+    connect_timeout: Option<Duration>,
+    read_timeout: Option<Duration>,
+    timeout: Option<Duration>,
+    proxies: Vec<Proxy>,
+    no_proxy: bool,
+    root_certificates: Vec<Certificate>,
+    user_agent: Option<String>,
+    headers: HeaderMap,
+}
+
+impl TranslatorBuilder {
+    /// Starts a translator between two BCP-47 tags, as for
+    /// [`Translator::with_backend`].
+    pub fn new(source_lang: &str, target_lang: &str) -> Self {
+        Self {
+            source_lang: source_lang.to_owned(),
+            target_lang: target_lang.to_owned(),
+            base_url: None,
+            client: None,
+            connect_timeout: None,
+            read_timeout: None,
+            timeout: None,
+            proxies: Vec::new(),
+            no_proxy: false,
+            root_certificates: Vec::new(),
+            user_agent: None,
+            headers: HeaderMap::new(),
+        }
+    }
+
+    /// Sends requests to `base_url` instead of the backend's default server,
+    /// e.g. `http://127.0.0.1:8080` for a mock server.
+    pub fn with_base_url(mut self, base_url: impl Into<String>) -> Self {
+        self.base_url = Some(base_url.into());
+        self
+    }
+
+    /// Sends requests with `client` instead of building one. The proxy,
+    /// certificate, user agent, header, connect and total timeout settings
+    /// only apply to built clients and are ignored.
+    pub fn with_client(mut self, client: Client) -> Self {
+        self.client = Some(client);
+        self
+    }
+
+    /// Limits how long connecting to the server may take.
+    pub fn with_connect_timeout(mut self, timeout: Duration) -> Self {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits how long to wait for the response headers or the next part
+    /// of the body.
+    pub fn with_read_timeout(mut self, timeout: Duration) -> Self {
+        self.read_timeout = Some(timeout);
+        self
+    }
+
+    /// Limits how long each request may take from connecting until the
+    /// response has been read.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sends requests through `proxy`. Without one, the `HTTP_PROXY` and
+    /// `HTTPS_PROXY` environment variables are used.
+    ///
+    /// Only `http://` and `https://` proxies are supported. SOCKS proxies
+    /// are out of scope: reqwest is built without its `socks` feature, so
+    /// `Proxy::all("socks5://…")` fails with an "unknown proxy scheme"
+    /// error. Point a local HTTP proxy at the SOCKS proxy instead.
+    pub fn with_proxy(mut self, proxy: Proxy) -> Self {
+        self.proxies.push(proxy);
+        self
+    }
+
+    /// Connects directly, ignoring the proxy environment variables.
+    pub fn without_proxy(mut self) -> Self {
+        self.no_proxy = true;
+        self
+    }
+
+    /// Trusts `certificate` in addition to the system's root certificates,
+    /// e.g. a corporate CA.
+    pub fn with_root_certificate(mut self, certificate: Certificate) -> Self {
+        self.root_certificates.push(certificate);
+        self
+    }
+
+    /// Sends `user_agent` as the `User-Agent` header of every request.
+    pub fn with_user_agent(mut self, user_agent: impl Into<String>) -> Self {
+        self.user_agent = Some(user_agent.into());
+        self
+    }
+
+    /// Sends `name: value` with every request.
+    pub fn with_header(mut self, name: HeaderName, value: HeaderValue) -> Self {
+        self.headers.insert(name, value);
+        self
+    }
+
+    /// Builds a translator using Google's gtx endpoint.
+    #[cfg(feature = "google")]
+    pub fn build(self) -> Result<Translator, TranslationError> {
+        self.build_with_backend(GoogleGtxBackend::new())
+    }
+
+    /// Builds a translator sending requests to `backend` with the configured
+    /// client and server.
+    pub fn build_with_backend(
+        self,
+        backend: impl HttpBackend + 'static,
+    ) -> Result<Translator, TranslationError> {
+        let mut backend = backend.with_client(self.http_client()?);
+        if let Some(base_url) = self.base_url {
+            backend = backend.with_base_url(base_url);
+        }
+        Translator::with_backend(&self.source_lang, &self.target_lang, backend)
+    }
+
+    fn http_client(&self) -> Result<HttpClient, TranslationError> {
+        let client = match &self.client {
+            Some(client) => client.clone(),
+            None => self
+                .client_builder()
+                .build()
+                .map_err(|error| TranslationError::ClientSetupFailed(error.into()))?,
+        };
+        let client = HttpClient::new(client);
+        Ok(match self.read_timeout {
+            Some(timeout) => client.with_read_timeout(timeout),
+            None => client,
+        })
+    }
+
+    fn client_builder(&self) -> reqwest::ClientBuilder {
+        let mut builder = Client::builder().default_headers(self.headers.clone());
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if self.no_proxy {
+            builder = builder.no_proxy();
+        }
+        for proxy in &self.proxies {
+            builder = builder.proxy(proxy.clone());
+        }
+        for certificate in &self.root_certificates {
+            builder = builder.add_root_certificate(certificate.clone());
+        }
+        if let Some(user_agent) = &self.user_agent {
+            builder = builder.user_agent(user_agent);
+        }
+        builder
+    }
+}
+
+#[cfg(all(test, feature = "google"))]
+mod tests {
+    use super::*;
+    use crate::backend::TranslationBackend;
+    use crate::test_support::{MockResponse, MockServer};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_builder_configures_the_client() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(
+                200,
+                json!([[["Hallo", "Hello", null, null, 1]], null, "en"]),
+            )
+        })
+        .await;
+
+        let translator = Translator::builder("en", "de")
+            .with_base_url(server.url())
+            .with_user_agent("test-agent/1.0")
+            .with_header(
+                HeaderName::from_static("x-request-source"),
+                HeaderValue::from_static("tests"),
+            )
+            .with_timeout(Duration::from_secs(5))
+            .without_proxy()
+            .build()
+            .unwrap();
+        assert_eq!(translator.translate("Hello").await.unwrap().text, "Hallo");
+
+        let request = &server.requests()[0];
+        assert!(request.path.starts_with("/translate_a/single?client=gtx"));
+        assert_eq!(request.header("user-agent"), Some("test-agent/1.0"));
+        assert_eq!(request.header("x-request-source"), Some("tests"));
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout() {
+        let server = MockServer::start(|_| {
+            MockResponse::json(200, json!([[["Hallo", "Hello"]]]))
+                .with_delay(Duration::from_millis(500))
+        })
+        .await;
+
+        let client = TranslatorBuilder::new("en", "de")
+            .with_client(Client::new())
+            .with_read_timeout(Duration::from_millis(50))
+            .http_client()
+            .unwrap();
+        let backend = GoogleGtxBackend::new()
+            .with_client(client)
+            .with_base_url(server.url());
+        assert!(matches!(
+            backend.translate("Hello", "en", "de").await,
+            Err(TranslationError::Timeout(None))
+        ));
+    }
+}
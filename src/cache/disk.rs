@@ -0,0 +1,719 @@
+use super::{Cache, CacheKey};
+use crate::{Segment, Translation};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Version of the file format, stored in the header line of every cache
+/// file and export.
+pub const SCHEMA_VERSION: u64 = 1;
+
+const DEFAULT_MAX_SIZE: u64 = 64 * 1024 * 1024;
+
+/// A translation stored in a [`DiskCache`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct CacheEntry {
+    pub key: CacheKey,
+    pub translation: Translation,
+    pub created: SystemTime,
+    pub accessed: SystemTime,
+}
+
+impl CacheEntry {
+    pub fn new(key: CacheKey, translation: Translation) -> Self {
+        let now = SystemTime::now();
+        Self {
+            key,
+            translation,
+            created: now,
+            accessed: now,
+        }
+    }
+
+    fn to_json(&self) -> Value {
+        let segments: Vec<_> = self
+            .translation
+            .segments
+            .iter()
+            .map(|segment| json!([segment.source, segment.target]))
+            .collect();
+        json!({
+            "backend": self.key.backend,
+            "options": self.key.options,
+            "source_lang": self.key.source_lang,
+            "target_lang": self.key.target_lang,
+            "text": self.key.text,
+            "translation": {
+                "text": self.translation.text,
+                "source_text": self.translation.source_text,
+                "segments": segments,
+                "detected_source_lang": self.translation.detected_source_lang,
+                "confidence": self.translation.confidence,
+            },
+            "created": unix_nanos(self.created),
+            "accessed": unix_nanos(self.accessed),
+        })
+    }
+
+    fn from_json(json: &Value) -> Option<Self> {
+        let string = |value: &Value| value.as_str().map(str::to_owned);
+        let time = |value: &Value| Some(UNIX_EPOCH + Duration::from_nanos(value.as_u64()?));
+
+        let translation = &json["translation"];
+        let segments = translation["segments"]
+            .as_array()?
+            .iter()
+            .map(|segment| {
+                Some(Segment {
+                    source: string(&segment[0])?,
+                    target: string(&segment[1])?,
+                })
+            })
+            .collect::<Option<_>>()?;
+
+        Some(Self {
+            key: CacheKey {
+                backend: string(&json["backend"])?,
+                options: string(&json["options"])?,
+                source_lang: string(&json["source_lang"])?,
+                target_lang: string(&json["target_lang"])?,
+                text: string(&json["text"])?,
+            },
+            translation: Translation {
+                text: string(&translation["text"])?,
+                source_text: string(&translation["source_text"])?,
+                segments,
+                detected_source_lang: string(&translation["detected_source_lang"]),
+                confidence: translation["confidence"].as_f64(),
+                from_cache: false,
+            },
+            created: time(&json["created"])?,
+            accessed: time(&json["accessed"])?,
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum CacheError {
+    Io(io::Error),
+    /// The file was written by a newer version with another schema.
+    UnsupportedSchema(u64),
+    /// The file does not start with a schema header.
+    InvalidFormat(PathBuf),
+}
+
+impl From<io::Error> for CacheError {
+    fn from(error: io::Error) -> Self {
+        CacheError::Io(error)
+    }
+}
+
+impl fmt::Display for CacheError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheError::Io(error) => write!(f, "Cache file access failed: {}", error),
+            CacheError::UnsupportedSchema(version) => write!(
+                f,
+                "Unsupported cache schema version {} (expected {})",
+                version, SCHEMA_VERSION
+            ),
+            CacheError::InvalidFormat(path) => {
+                write!(f, "Not a translation cache: {}", path.display())
+            }
+        }
+    }
+}
+
+impl Error for CacheError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CacheError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+/// Summary of a [`DiskCache`] file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DiskCacheInfo {
+    pub path: PathBuf,
+    pub schema: u64,
+    /// Size of the file in bytes.
+    pub size: u64,
+    pub entries: usize,
+    /// Entries older than the TTL, removed on the next compaction.
+    pub expired: usize,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<CacheKey, CacheEntry>,
+    /// Length of the file when this process last read or wrote it.
+    len: u64,
+}
+
+/// Translation cache persisted in a file so that it survives between runs.
+///
+/// The file holds one JSON object per line after a header naming the schema
+/// version, with times given in nanoseconds since the Unix epoch. New
+/// translations are appended; the file is rewritten without expired and
+/// least recently used entries once it outgrows its maximum size. Several
+/// processes may share a file: writes hold an exclusive lock on a `.lock`
+/// file next to it, and entries written by other processes are read when a
+/// lookup misses. As a [`Cache`], it reads and writes the file on tokio's
+/// blocking threads.
+#[derive(Debug)]
+pub struct DiskCache {
+    path: PathBuf,
+    ttl: Option<Duration>,
+    max_size: u64,
+    state: Arc<Mutex<State>>,
+}
+
+impl DiskCache {
+    /// Opens the cache stored at `path`, creating it if it does not exist.
+    pub fn open(path: impl Into<PathBuf>) -> Result<Self, CacheError> {
+        let cache = Self {
+            path: path.into(),
+            ttl: None,
+            max_size: DEFAULT_MAX_SIZE,
+            state: Arc::default(),
+        };
+        if let Some(parent) = cache.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let mut state = cache.lock();
+        let _lock = cache.lock_file(true)?;
+        cache.reload(&mut state)?;
+        if state.len == 0 {
+            state.len = cache.rewrite(&[])?;
+        }
+        drop(state);
+        Ok(cache)
+    }
+
+    /// The cache file used when none is configured: `translations.jsonl` in
+    /// the `rustranslate` directory of the user's cache directory.
+    pub fn default_path() -> Option<PathBuf> {
+        let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+        let dir = var("XDG_CACHE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("LOCALAPPDATA").map(PathBuf::from))
+            .or_else(|| var("HOME").map(|home| Path::new(&home).join(".cache")))?;
+        Some(dir.join("rustranslate").join("translations.jsonl"))
+    }
+
+    /// Treats entries as missing `ttl` after they were stored.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Compacts the file once it grows beyond `max_size` bytes.
+    pub fn with_max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn info(&self) -> Result<DiskCacheInfo, CacheError> {
+        let mut state = self.lock();
+        self.refresh(&mut state)?;
+        let now = SystemTime::now();
+        let expired = state
+            .entries
+            .values()
+            .filter(|entry| self.is_expired(entry, now))
+            .count();
+
+        Ok(DiskCacheInfo {
+            path: self.path.clone(),
+            schema: SCHEMA_VERSION,
+            size: state.len,
+            entries: state.entries.len() - expired,
+            expired,
+        })
+    }
+
+    /// The entries that have not expired, ordered by key.
+    pub fn entries(&self) -> Result<Vec<CacheEntry>, CacheError> {
+        let mut state = self.lock();
+        self.refresh(&mut state)?;
+        let now = SystemTime::now();
+        let mut entries: Vec<_> = state
+            .entries
+            .values()
+            .filter(|entry| !self.is_expired(entry, now))
+            .cloned()
+            .collect();
+        entries.sort_by(|a, b| a.key.cmp(&b.key));
+        Ok(entries)
+    }
+
+    /// Writes the entries that have not expired to `writer` in the cache file
+    /// format, returning how many were written.
+    pub fn export(&self, writer: impl Write) -> Result<usize, CacheError> {
+        let entries = self.entries()?;
+        let mut writer = BufWriter::new(writer);
+        writeln!(writer, "{}", header())?;
+        for entry in &entries {
+            writeln!(writer, "{}", entry.to_json())?;
+        }
+        writer.flush()?;
+        Ok(entries.len())
+    }
+
+    /// Reads the entries of an export or another cache file. Lines that are
+    /// not valid entries, such as one cut short by a crash, are skipped.
+    pub fn read_export(reader: impl BufRead, path: &Path) -> Result<Vec<CacheEntry>, CacheError> {
+        let mut lines = reader.lines();
+        let header = match lines.next() {
+            Some(line) => line?,
+            None => return Ok(Vec::new()),
+        };
+        let schema = serde_json::from_str::<Value>(&header)
+            .ok()
+            .and_then(|header| header["schema"].as_u64())
+            .ok_or_else(|| CacheError::InvalidFormat(path.to_owned()))?;
+        if schema != SCHEMA_VERSION {
+            return Err(CacheError::UnsupportedSchema(schema));
+        }
+
+        let mut entries = Vec::new();
+        for line in lines {
+            let entry = serde_json::from_str(&line?)
+                .ok()
+                .and_then(|json| CacheEntry::from_json(&json));
+            entries.extend(entry);
+        }
+        Ok(entries)
+    }
+
+    /// Stores `entries`, replacing cached translations with the same key.
+    /// Returns how many entries were stored.
+    pub fn import(
+        &self,
+        entries: impl IntoIterator<Item = CacheEntry>,
+    ) -> Result<usize, CacheError> {
+        self.add(entries, |_, _| true)
+    }
+
+    /// Stores `entries` unless the cache holds a translation for the same
+    /// key that was created later. Returns how many entries were stored.
+    pub fn merge(
+        &self,
+        entries: impl IntoIterator<Item = CacheEntry>,
+    ) -> Result<usize, CacheError> {
+        self.add(entries, |new, old| new.created > old.created)
+    }
+
+    /// Removes the entries matching `predicate` along with expired ones,
+    /// returning how many were removed.
+    pub fn purge(&self, predicate: impl Fn(&CacheEntry) -> bool) -> Result<usize, CacheError> {
+        let mut state = self.lock();
+        let _lock = self.lock_file(true)?;
+        self.reload(&mut state)?;
+
+        let now = SystemTime::now();
+        let before = state.entries.len();
+        state
+            .entries
+            .retain(|_, entry| !predicate(entry) && !self.is_expired(entry, now));
+        let removed = before - state.entries.len();
+        self.compact_locked(&mut state)?;
+        Ok(removed)
+    }
+
+    /// Removes every entry.
+    pub fn clear(&self) -> Result<usize, CacheError> {
+        self.purge(|_| true)
+    }
+
+    /// Rewrites the file without expired entries, evicting the least
+    /// recently used ones if it is larger than the maximum size.
+    pub fn compact(&self) -> Result<(), CacheError> {
+        let mut state = self.lock();
+        let _lock = self.lock_file(true)?;
+        self.reload(&mut state)?;
+        self.compact_locked(&mut state)
+    }
+
+    /// Returns the stored translation for `key`, marked as coming from the
+    /// cache, and re-reads the file first if it is missing.
+    pub fn get(&self, key: &CacheKey) -> Option<Translation> {
+        let mut state = self.lock();
+        if !state.entries.contains_key(key) {
+            self.refresh(&mut state).ok()?;
+        }
+
+        let now = SystemTime::now();
+        let entry = state.entries.get_mut(key)?;
+        if self.is_expired(entry, now) {
+            return None;
+        }
+        entry.accessed = now;
+        let mut translation = entry.translation.clone();
+        translation.from_cache = true;
+        Some(translation)
+    }
+
+    /// Appends a translation to the file.
+    pub fn store(&self, key: CacheKey, translation: Translation) -> Result<(), CacheError> {
+        let entry = CacheEntry::new(key, translation);
+        let line = format!("{}\n", entry.to_json());
+
+        let mut state = self.lock();
+        let _lock = self.lock_file(true)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+        let mut len = file.metadata()?.len();
+        let unchanged = len == state.len;
+        if len == 0 {
+            let header = format!("{}\n", header());
+            file.write_all(header.as_bytes())?;
+            len += header.len() as u64;
+        }
+        file.write_all(line.as_bytes())?;
+        len += line.len() as u64;
+
+        // Leave the length stale if another process wrote to the file, so that
+        // its entries are read on the next miss.
+        if unchanged {
+            state.len = len;
+        }
+        state.entries.insert(entry.key.clone(), entry);
+        if len > self.max_size {
+            self.reload(&mut state)?;
+            self.compact_locked(&mut state)?;
+        }
+        Ok(())
+    }
+
+    fn add(
+        &self,
+        entries: impl IntoIterator<Item = CacheEntry>,
+        replace: impl Fn(&CacheEntry, &CacheEntry) -> bool,
+    ) -> Result<usize, CacheError> {
+        let mut state = self.lock();
+        let _lock = self.lock_file(true)?;
+        self.reload(&mut state)?;
+
+        let mut added = 0;
+        for entry in entries {
+            let keep = state
+                .entries
+                .get(&entry.key)
+                .is_some_and(|old| !replace(&entry, old));
+            if !keep {
+                state.entries.insert(entry.key.clone(), entry);
+                added += 1;
+            }
+        }
+        self.compact_locked(&mut state)?;
+        Ok(added)
+    }
+
+    /// Re-reads the file if another process changed it.
+    fn refresh(&self, state: &mut State) -> Result<(), CacheError> {
+        let len = match fs::metadata(&self.path) {
+            Ok(metadata) => metadata.len(),
+            Err(error) if error.kind() == io::ErrorKind::NotFound => 0,
+            Err(error) => return Err(error.into()),
+        };
+        if len != state.len {
+            let _lock = self.lock_file(false)?;
+            self.reload(state)?;
+        }
+        Ok(())
+    }
+
+    /// Replaces the entries with those in the file, keeping the later access
+    /// time known to this process.
+    fn reload(&self, state: &mut State) -> Result<(), CacheError> {
+        let file = match File::open(&self.path) {
+            Ok(file) => file,
+            Err(error) if error.kind() == io::ErrorKind::NotFound => {
+                *state = State::default();
+                return Ok(());
+            }
+            Err(error) => return Err(error.into()),
+        };
+        let len = file.metadata()?.len();
+        let entries = Self::read_export(BufReader::new(file), &self.path)?;
+
+        let mut loaded = HashMap::with_capacity(entries.len());
+        for mut entry in entries {
+            if let Some(known) = state.entries.get(&entry.key) {
+                if known.created == entry.created {
+                    entry.accessed = entry.accessed.max(known.accessed);
+                }
+            }
+            loaded.insert(entry.key.clone(), entry);
+        }
+        state.entries = loaded;
+        state.len = len;
+        Ok(())
+    }
+
+    /// Rewrites the file from `state` without expired entries. When the
+    /// entries would exceed the maximum size, the least recently used ones
+    /// are evicted until three quarters of it are used, so that appending
+    /// does not trigger another compaction right away.
+    fn compact_locked(&self, state: &mut State) -> Result<(), CacheError> {
+        let now = SystemTime::now();
+        let mut entries: Vec<_> = state
+            .entries
+            .values()
+            .filter(|entry| !self.is_expired(entry, now))
+            .map(|entry| (entry, entry.to_json().to_string()))
+            .collect();
+
+        let total: u64 = entries.iter().map(|(_, line)| line.len() as u64 + 1).sum();
+        if total > self.max_size {
+            entries.sort_by_key(|(entry, _)| Reverse(entry.accessed));
+            let mut size = 0;
+            entries.retain(|(_, line)| {
+                size += line.len() as u64 + 1;
+                size <= self.max_size / 4 * 3
+            });
+        }
+
+        let keys: Vec<_> = entries.iter().map(|(entry, _)| entry.key.clone()).collect();
+        let lines: Vec<_> = entries.into_iter().map(|(_, line)| line).collect();
+        state.len = self.rewrite(&lines)?;
+        let mut kept = HashMap::with_capacity(keys.len());
+        for key in keys {
+            if let Some(entry) = state.entries.remove(&key) {
+                kept.insert(key, entry);
+            }
+        }
+        state.entries = kept;
+        Ok(())
+    }
+
+    /// Atomically replaces the file with `lines`, returning its new length.
+    fn rewrite(&self, lines: &[String]) -> Result<u64, CacheError> {
+        let temp = with_suffix(&self.path, ".tmp");
+        let file = File::create(&temp)?;
+        let mut writer = BufWriter::new(file);
+        writeln!(writer, "{}", header())?;
+        for line in lines {
+            writeln!(writer, "{}", line)?;
+        }
+        let file = writer.into_inner().map_err(|error| error.into_error())?;
+        file.sync_all()?;
+        let len = file.metadata()?.len();
+        fs::rename(&temp, &self.path)?;
+        Ok(len)
+    }
+
+    /// Locks the `.lock` file next to the cache, which unlike the cache file
+    /// itself is never replaced. The lock is released when the returned file
+    /// is dropped.
+    fn lock_file(&self, exclusive: bool) -> io::Result<File> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(with_suffix(&self.path, ".lock"))?;
+        if exclusive {
+            file.lock()?;
+        } else {
+            file.lock_shared()?;
+        }
+        Ok(file)
+    }
+
+    fn is_expired(&self, entry: &CacheEntry, now: SystemTime) -> bool {
+        self.ttl.is_some_and(|ttl| {
+            now.duration_since(entry.created)
+                .is_ok_and(|age| age >= ttl)
+        })
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// Another handle on the same file and entries, for a blocking thread.
+    fn share(&self) -> Self {
+        Self {
+            path: self.path.clone(),
+            ttl: self.ttl,
+            max_size: self.max_size,
+            state: self.state.clone(),
+        }
+    }
+}
+
+#[async_trait]
+impl Cache for DiskCache {
+    async fn get(&self, key: &CacheKey) -> Option<Translation> {
+        let (cache, key) = (self.share(), key.clone());
+        tokio::task::spawn_blocking(move || DiskCache::get(&cache, &key))
+            .await
+            .ok()
+            .flatten()
+    }
+
+    /// Stores the translation, ignoring write errors: a translation that
+    /// could not be cached is requested again next time.
+    async fn insert(&self, key: CacheKey, translation: Translation) {
+        let cache = self.share();
+        let _ = tokio::task::spawn_blocking(move || cache.store(key, translation)).await;
+    }
+}
+
+fn header() -> Value {
+    json!({ "schema": SCHEMA_VERSION })
+}
+
+fn unix_nanos(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map_or(0, |duration| duration.as_nanos() as u64)
+}
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    path.into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    fn key(text: &str) -> CacheKey {
+        CacheKey::new("echo", "", "en", "fr", text)
+    }
+
+    fn entry(text: &str, created: u64) -> CacheEntry {
+        let time = UNIX_EPOCH + Duration::from_secs(created);
+        CacheEntry {
+            key: key(text),
+            translation: Translation::new(text, text.to_uppercase()),
+            created: time,
+            accessed: time,
+        }
+    }
+
+    #[test]
+    fn test_entries_persist_and_are_shared() {
+        let dir = TempDir::new();
+        let path = dir.join("cache/translations.jsonl");
+        let first = DiskCache::open(&path).unwrap();
+        let second = DiskCache::open(&path).unwrap();
+
+        let translation =
+            Translation::new("hello", "bonjour").with_detected_source_lang("en", None);
+        first.store(key("hello"), translation.clone()).unwrap();
+
+        let hit = second.get(&key("hello")).unwrap();
+        assert!(hit.from_cache);
+        assert_eq!(hit.segments, translation.segments);
+        assert_eq!(hit.detected_source_lang.as_deref(), Some("en"));
+        assert!(second.get(&key("bye")).is_none());
+
+        drop((first, second));
+        let reopened = DiskCache::open(&path).unwrap();
+        assert_eq!(reopened.entries().unwrap().len(), 1);
+        assert_eq!(reopened.info().unwrap().schema, SCHEMA_VERSION);
+    }
+
+    #[tokio::test]
+    async fn test_cache_trait() {
+        let dir = TempDir::new();
+        let cache = DiskCache::open(dir.join("translations.jsonl")).unwrap();
+        Cache::insert(&cache, key("hello"), Translation::new("hello", "bonjour")).await;
+
+        let hit = Cache::get(&cache, &key("hello")).await.unwrap();
+        assert_eq!(hit.text, "bonjour");
+        assert!(hit.from_cache);
+        assert_eq!(cache.entries().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_schema_version_is_checked() {
+        let dir = TempDir::new();
+        let path = dir.join("translations.jsonl");
+        fs::write(&path, "{\"schema\":99}\n").unwrap();
+        assert!(matches!(
+            DiskCache::open(&path),
+            Err(CacheError::UnsupportedSchema(99))
+        ));
+
+        fs::write(&path, "hello\n").unwrap();
+        assert!(matches!(
+            DiskCache::open(&path),
+            Err(CacheError::InvalidFormat(_))
+        ));
+    }
+
+    #[test]
+    fn test_ttl_and_size_eviction() {
+        let dir = TempDir::new();
+        let cache = DiskCache::open(dir.join("translations.jsonl"))
+            .unwrap()
+            .with_ttl(Duration::from_secs(3600));
+        cache.import([entry("old", 0)]).unwrap();
+        cache
+            .store(key("new"), Translation::new("new", "NEW"))
+            .unwrap();
+        assert!(cache.get(&key("old")).is_none());
+        assert_eq!(cache.info().unwrap().entries, 1);
+
+        let cache = cache.with_max_size(2000);
+        for i in 0..20 {
+            let text = format!("text {}", i);
+            cache
+                .store(key(&text), Translation::new(&text, &text))
+                .unwrap();
+        }
+        let info = cache.info().unwrap();
+        assert!(info.size <= 2000, "{:?}", info);
+        assert!(cache.get(&key("text 19")).is_some());
+        assert!(cache.get(&key("text 0")).is_none());
+    }
+
+    #[test]
+    fn test_export_import_and_merge() {
+        let dir = TempDir::new();
+        let cache = DiskCache::open(dir.join("a.jsonl")).unwrap();
+        cache.import([entry("a", 100), entry("b", 100)]).unwrap();
+
+        let mut export = Vec::new();
+        assert_eq!(cache.export(&mut export).unwrap(), 2);
+        let entries = DiskCache::read_export(&export[..], Path::new("export")).unwrap();
+        assert_eq!(entries, cache.entries().unwrap());
+
+        let other = DiskCache::open(dir.join("b.jsonl")).unwrap();
+        let mut newer = entry("a", 200);
+        newer.translation = Translation::new("a", "newer");
+        other.import([newer, entry("b", 50)]).unwrap();
+
+        assert_eq!(cache.merge(other.entries().unwrap()).unwrap(), 1);
+        assert_eq!(cache.get(&key("a")).unwrap().text, "newer");
+        assert_eq!(cache.get(&key("b")).unwrap().text, "B");
+
+        assert_eq!(cache.purge(|entry| entry.key.text == "a").unwrap(), 1);
+        assert_eq!(cache.clear().unwrap(), 1);
+        assert_eq!(cache.info().unwrap().entries, 0);
+    }
+}
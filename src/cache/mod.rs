@@ -0,0 +1,290 @@
+#[cfg(feature = "cache")]
+mod disk;
+
+#[cfg(feature = "cache")]
+pub use disk::{CacheEntry, CacheError, DiskCache, DiskCacheInfo};
+
+use crate::retry::{Clock, TokioClock};
+use crate::Translation;
+use async_trait::async_trait;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Storage for translations that `Translator` consults before asking its
+/// backend. Translators await it on their own task, so implementations
+/// that block, such as on file I/O, should do so on a blocking thread.
+#[async_trait]
+pub trait Cache: fmt::Debug + Send + Sync {
+    /// Returns the stored translation for `key`, marked as coming from the
+    /// cache.
+    async fn get(&self, key: &CacheKey) -> Option<Translation>;
+
+    async fn insert(&self, key: CacheKey, translation: Translation);
+}
+
+/// Identifies a cached translation.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct CacheKey {
+    /// Name of the backend that produced the translation.
+    pub backend: String,
+    /// Backend settings that change the output, see
+    /// `TranslationBackend::options`.
+    pub options: String,
+    pub source_lang: String,
+    pub target_lang: String,
+    /// The source text, trimmed and with runs of spaces and tabs within a
+    /// line collapsed to one space. Line breaks are kept as `\n`, whether
+    /// written `\n` or `\r\n`, since they shape the translation; no Unicode
+    /// normalization is applied.
+    pub text: String,
+}
+
+impl CacheKey {
+    pub fn new(
+        backend: impl Into<String>,
+        options: impl Into<String>,
+        source_lang: impl Into<String>,
+        target_lang: impl Into<String>,
+        text: &str,
+    ) -> Self {
+        Self {
+            backend: backend.into(),
+            options: options.into(),
+            source_lang: source_lang.into(),
+            target_lang: target_lang.into(),
+            text: normalize(text),
+        }
+    }
+}
+
+/// The text a key is made of; see [`CacheKey::text`].
+fn normalize(text: &str) -> String {
+    text.trim()
+        .lines()
+        .map(|line| {
+            let words: Vec<&str> = line.split([' ', '\t']).filter(|w| !w.is_empty()).collect();
+            words.join(" ")
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Hit and miss counts of a cache.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub entries: usize,
+}
+
+#[derive(Debug)]
+struct Entry {
+    translation: Translation,
+    inserted: Instant,
+    /// Position in `State::order`.
+    tick: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<CacheKey, Entry>,
+    /// Keys from least to most recently used.
+    order: BTreeMap<u64, CacheKey>,
+    tick: u64,
+    hits: u64,
+    misses: u64,
+}
+
+impl State {
+    fn touch(&mut self, key: &CacheKey) {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.order.remove(&entry.tick);
+            entry.tick = self.tick;
+            self.order.insert(self.tick, key.clone());
+        }
+    }
+
+    fn remove(&mut self, key: &CacheKey) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.order.remove(&entry.tick);
+        }
+    }
+}
+
+/// In-memory cache of translations, evicting the least recently used
+/// entries beyond its capacity and entries older than its TTL.
+#[derive(Debug)]
+pub struct TranslationCache {
+    state: Mutex<State>,
+    capacity: usize,
+    ttl: Option<Duration>,
+    clock: Arc<dyn Clock>,
+}
+
+impl TranslationCache {
+    /// Creates a cache holding up to `capacity` translations.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            state: Mutex::default(),
+            capacity: capacity.max(1),
+            ttl: None,
+            clock: Arc::new(TokioClock),
+        }
+    }
+
+    /// Expires entries `ttl` after they were stored.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Returns the cached translation for `key`, marked as coming from the
+    /// cache, and counts the hit or miss.
+    pub fn get(&self, key: &CacheKey) -> Option<Translation> {
+        let now = self.clock.now();
+        let mut state = self.lock();
+
+        let expired = match state.entries.get(key) {
+            Some(entry) => self
+                .ttl
+                .is_some_and(|ttl| now.saturating_duration_since(entry.inserted) >= ttl),
+            None => {
+                state.misses += 1;
+                return None;
+            }
+        };
+        if expired {
+            state.remove(key);
+            state.misses += 1;
+            return None;
+        }
+
+        state.hits += 1;
+        state.touch(key);
+        let mut translation = state.entries[key].translation.clone();
+        translation.from_cache = true;
+        Some(translation)
+    }
+
+    pub fn insert(&self, key: CacheKey, translation: Translation) {
+        let inserted = self.clock.now();
+        let mut state = self.lock();
+
+        state.remove(&key);
+        state.tick += 1;
+        let tick = state.tick;
+        state.order.insert(tick, key.clone());
+        state.entries.insert(
+            key,
+            Entry {
+                translation,
+                inserted,
+                tick,
+            },
+        );
+
+        while state.entries.len() > self.capacity {
+            let Some((_, oldest)) = state.order.pop_first() else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+    }
+
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.order.clear();
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        let state = self.lock();
+        CacheStats {
+            hits: state.hits,
+            misses: state.misses,
+            entries: state.entries.len(),
+        }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[async_trait]
+impl Cache for TranslationCache {
+    async fn get(&self, key: &CacheKey) -> Option<Translation> {
+        TranslationCache::get(self, key)
+    }
+
+    async fn insert(&self, key: CacheKey, translation: Translation) {
+        TranslationCache::insert(self, key, translation)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::FakeClock;
+
+    fn key(text: &str) -> CacheKey {
+        CacheKey::new("echo", "", "en", "fr", text)
+    }
+
+    #[test]
+    fn test_lru_eviction_and_stats() {
+        let cache = TranslationCache::new(2);
+        cache.insert(key("a"), Translation::new("a", "A"));
+        cache.insert(key("b"), Translation::new("b", "B"));
+
+        let hit = cache.get(&key(" a ")).unwrap();
+        assert_eq!(hit.text, "A");
+        assert!(hit.from_cache);
+
+        cache.insert(key("c"), Translation::new("c", "C"));
+        assert!(cache.get(&key("b")).is_none());
+        assert!(cache.get(&key("a")).is_some());
+        assert!(cache.get(&key("c")).is_some());
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 3,
+                misses: 1,
+                entries: 2
+            }
+        );
+    }
+
+    #[test]
+    fn test_key_collapses_spaces() {
+        assert_eq!(key(" hello \t  world ").text, "hello world");
+        assert_eq!(key("hello  \n  world").text, "hello\nworld");
+        assert_eq!(key("hello \r\n world\r\n").text, "hello\nworld");
+        assert_ne!(key("hello world"), key("hello\nworld"));
+    }
+
+    #[tokio::test]
+    async fn test_ttl() {
+        let clock = FakeClock::new();
+        let cache = TranslationCache::new(10)
+            .with_ttl(Duration::from_secs(60))
+            .with_clock(clock.clone());
+        cache.insert(key("a"), Translation::new("a", "A"));
+
+        clock.sleep(Duration::from_secs(59)).await;
+        assert!(cache.get(&key("a")).is_some());
+        clock.sleep(Duration::from_secs(1)).await;
+        assert!(cache.get(&key("a")).is_none());
+        assert_eq!(cache.stats().entries, 0);
+    }
+}
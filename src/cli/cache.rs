@@ -0,0 +1,286 @@
+use super::{
+    usage, write_help, Arg, CliError, CliResult, Command, Invocation, Matches, Opt, APP, HELP,
+};
+use crate::{CacheEntry, DiskCache};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{self, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const FILE: Arg = Arg {
+    usage: "<FILE>",
+    choices: &[],
+    help: "Export or cache file to read",
+};
+
+pub(super) const CACHE: Command = Command {
+    name: "cache",
+    about: "Inspect and maintain the translation cache",
+    args: &[],
+    options: &[
+        Opt {
+            long: "path",
+            short: Some('p'),
+            value: Some("FILE"),
+            choices: &[],
+            help: "Cache file [default: $RUSTRANSLATE_CACHE or the user's cache directory]",
+        },
+        Opt {
+            long: "ttl",
+            short: None,
+            value: Some("DURATION"),
+            choices: &[],
+            help: "Treat entries older than DURATION as expired, e.g. 90, 30m or 7d",
+        },
+        HELP,
+    ],
+    subcommands: &[
+        Command {
+            name: "inspect",
+            about: "Show the size and contents of the cache",
+            args: &[],
+            options: &[HELP],
+            subcommands: &[],
+        },
+        Command {
+            name: "export",
+            about: "Write the cache to FILE, or to stdout",
+            args: &[Arg {
+                usage: "[FILE]",
+                choices: &[],
+                help: "File to write",
+            }],
+            options: &[HELP],
+            subcommands: &[],
+        },
+        Command {
+            name: "import",
+            about: "Add the entries in FILE, replacing existing ones",
+            args: &[FILE],
+            options: &[HELP],
+            subcommands: &[],
+        },
+        Command {
+            name: "merge",
+            about: "Add the entries in FILE that are newer than the cached ones",
+            args: &[FILE],
+            options: &[HELP],
+            subcommands: &[],
+        },
+        Command {
+            name: "purge",
+            about: "Remove matching entries, or all entries without filters",
+            args: &[],
+            options: &[
+                Opt {
+                    long: "expired",
+                    short: None,
+                    value: None,
+                    choices: &[],
+                    help: "Only remove expired entries",
+                },
+                Opt {
+                    long: "backend",
+                    short: Some('b'),
+                    value: Some("NAME"),
+                    choices: super::BACKENDS,
+                    help: "Only remove entries of this backend",
+                },
+                Opt {
+                    long: "older-than",
+                    short: None,
+                    value: Some("DURATION"),
+                    choices: &[],
+                    help: "Only remove entries created longer ago than DURATION",
+                },
+                HELP,
+            ],
+            subcommands: &[],
+        },
+    ],
+};
+
+/// Runs `rustranslate cache`, given its options and subcommand.
+pub(super) fn run(
+    matches: &Matches,
+    subcommand: Option<Invocation>,
+    out: &mut dyn Write,
+) -> CliResult {
+    let path = format!("{} {}", APP.name, CACHE.name);
+    let Some((command, args)) = subcommand else {
+        return write_help(&CACHE, &path, out);
+    };
+    let path = format!("{} {}", path, command.name);
+    let Some((options, _)) = Matches::parse(command, &path, args)? else {
+        return write_help(command, &path, out);
+    };
+
+    let file = file(matches.value("path"))
+        .ok_or_else(|| CliError::Usage("No cache directory found, pass --path".to_owned()))?;
+    let mut cache = DiskCache::open(file)?;
+    if let Some(ttl) = matches.value("ttl") {
+        cache = cache.with_ttl(parse_duration(ttl)?);
+    }
+
+    match (command.name, options.args.as_slice()) {
+        ("inspect", []) => inspect(&cache, out),
+        ("export", []) => {
+            cache.export(out)?;
+            Ok(())
+        }
+        ("export", [file]) => {
+            let count = cache.export(File::create(file)?)?;
+            writeln!(out, "Exported {} entries to {}", count, file)?;
+            Ok(())
+        }
+        ("import" | "merge", [file]) => {
+            let entries = read_entries(Path::new(file))?;
+            let total = entries.len();
+            let count = if command.name == "import" {
+                cache.import(entries)?
+            } else {
+                cache.merge(entries)?
+            };
+            writeln!(out, "Stored {} of {} entries from {}", count, total, file)?;
+            Ok(())
+        }
+        ("purge", []) => purge(&cache, &options, out),
+        _ => Err(usage("Unexpected arguments".to_owned(), command, &path)),
+    }
+}
+
+/// The cache file: `path` if given, else `$RUSTRANSLATE_CACHE` or the
+/// default file in the user's cache directory.
+pub(super) fn file(path: Option<&str>) -> Option<PathBuf> {
+    path.map(PathBuf::from)
+        .or_else(|| std::env::var_os("RUSTRANSLATE_CACHE").map(PathBuf::from))
+        .or_else(DiskCache::default_path)
+}
+
+fn inspect(cache: &DiskCache, out: &mut dyn Write) -> CliResult {
+    let info = cache.info()?;
+    writeln!(out, "Path:    {}", info.path.display())?;
+    writeln!(out, "Schema:  {}", info.schema)?;
+    writeln!(out, "Size:    {} bytes", info.size)?;
+    writeln!(out, "Entries: {} ({} expired)", info.entries, info.expired)?;
+
+    let mut counts = BTreeMap::new();
+    for entry in cache.entries()? {
+        let key = entry.key;
+        *counts
+            .entry((key.backend, key.source_lang, key.target_lang))
+            .or_insert(0) += 1;
+    }
+    for ((backend, source, target), count) in counts {
+        writeln!(out, "  {} {}->{}: {}", backend, source, target, count)?;
+    }
+    Ok(())
+}
+
+fn purge(cache: &DiskCache, options: &Matches, out: &mut dyn Write) -> CliResult {
+    let expired_only = options.flag("expired");
+    let backend = options.value("backend");
+    let older_than = options
+        .value("older-than")
+        .map(parse_duration)
+        .transpose()?;
+
+    let now = SystemTime::now();
+    let removed = cache.purge(|entry: &CacheEntry| {
+        if expired_only {
+            return false;
+        }
+        backend.is_none_or(|backend| entry.key.backend == backend)
+            && older_than.is_none_or(|age| {
+                now.duration_since(entry.created)
+                    .is_ok_and(|elapsed| elapsed >= age)
+            })
+    })?;
+    writeln!(out, "Removed {} entries", removed)?;
+    Ok(())
+}
+
+fn read_entries(path: &Path) -> Result<Vec<CacheEntry>, CliError> {
+    let file = File::open(path).map_err(|error| {
+        CliError::Io(io::Error::new(
+            error.kind(),
+            format!("{}: {}", path.display(), error),
+        ))
+    })?;
+    Ok(DiskCache::read_export(BufReader::new(file), path)?)
+}
+
+/// Parses durations such as `90`, `30s`, `15m`, `12h` or `7d`.
+fn parse_duration(value: &str) -> Result<Duration, CliError> {
+    let invalid = || CliError::Usage(format!("Invalid duration: {}", value));
+    let (number, unit) = match value.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => value.split_at(index),
+        None => (value, "s"),
+    };
+    let number: u64 = number.parse().map_err(|_| invalid())?;
+    let seconds = match unit {
+        "s" => 1,
+        "m" => 60,
+        "h" => 60 * 60,
+        "d" => 24 * 60 * 60,
+        _ => return Err(invalid()),
+    };
+    let seconds = number.checked_mul(seconds).ok_or_else(invalid)?;
+    Ok(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::run as run_cli;
+    use crate::test_support::TempDir;
+    use crate::{CacheKey, Translation};
+
+    async fn run(path: &str, args: &[&str]) -> String {
+        let args: Vec<_> = ["cache", "--path", path]
+            .iter()
+            .chain(args)
+            .map(|arg| arg.to_string())
+            .collect();
+        let mut out = Vec::new();
+        run_cli(&args, &mut "".as_bytes(), &mut out).await.unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_cache_commands() {
+        let dir = TempDir::new();
+        let path = dir.join("translations.jsonl");
+        let path = path.to_str().unwrap();
+        let export = dir.join("export.jsonl");
+        let export = export.to_str().unwrap();
+
+        let cache = DiskCache::open(path).unwrap();
+        for (backend, text) in [("google", "a"), ("google", "b"), ("deepl", "c")] {
+            let key = CacheKey::new(backend, "", "en", "fr", text);
+            cache.store(key, Translation::new(text, text)).unwrap();
+        }
+
+        let inspect = run(path, &["inspect"]).await;
+        assert!(inspect.contains("Entries: 3 (0 expired)"), "{}", inspect);
+        assert!(inspect.contains("  google en->fr: 2"), "{}", inspect);
+
+        run(path, &["export", export]).await;
+        let output = run(path, &["purge", "--backend", "google"]).await;
+        assert_eq!(output, "Removed 2 entries\n");
+        let output = run(path, &["merge", export]).await;
+        assert_eq!(output, format!("Stored 2 of 3 entries from {}\n", export));
+        let output = run(path, &["purge"]).await;
+        assert_eq!(output, "Removed 3 entries\n");
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("7d").unwrap(), Duration::from_secs(604_800));
+        assert!(parse_duration("7w").is_err());
+        assert!(parse_duration("h").is_err());
+        assert!(parse_duration("999999999999999999d").is_err());
+    }
+}
@@ -0,0 +1,320 @@
+//! Shell completions and the man page, generated from the command
+//! descriptions.
+
+use super::{CliError, CliResult, Command, Opt, ENVIRONMENT, EXIT_STATUSES};
+use std::io::Write;
+
+pub(super) const SHELLS: &[&str] = &["bash", "zsh", "fish"];
+
+pub(super) fn write(shell: &str, app: &Command, out: &mut dyn Write) -> CliResult {
+    let script = match shell {
+        "bash" => bash(app),
+        "zsh" => zsh(app),
+        "fish" => fish(app),
+        _ => {
+            return Err(CliError::Usage(format!(
+                "Unknown shell: {} (expected one of {})",
+                shell,
+                SHELLS.join(", ")
+            )))
+        }
+    };
+    write!(out, "{}", script)?;
+    Ok(())
+}
+
+/// Every command below `app` with the names leading to it, `app` first.
+fn commands(app: &Command) -> Vec<(Vec<&str>, &Command)> {
+    let mut commands = vec![(Vec::new(), app)];
+    let mut index = 0;
+    while index < commands.len() {
+        let (path, command) = commands[index].clone();
+        for subcommand in command.subcommands {
+            let mut path = path.clone();
+            path.push(subcommand.name);
+            commands.push((path, subcommand));
+        }
+        index += 1;
+    }
+    commands
+}
+
+fn takes_files(placeholder: &str) -> bool {
+    placeholder.trim_matches(['<', '>', '[', ']', '.']) == "FILE"
+}
+
+fn bash(app: &Command) -> String {
+    let state = |path: &[&str]| {
+        std::iter::once(app.name)
+            .chain(path.iter().copied())
+            .collect::<Vec<_>>()
+            .join("__")
+    };
+    let mut transitions = String::new();
+    let mut values = String::new();
+    let mut words = String::new();
+
+    for (path, command) in commands(app) {
+        let current = state(&path);
+        for subcommand in command.subcommands {
+            let mut next = path.clone();
+            next.push(subcommand.name);
+            transitions.push_str(&format!(
+                "            {}:{}) state=\"{}\" ;;\n",
+                current,
+                subcommand.name,
+                state(&next)
+            ));
+        }
+
+        for opt in command.options {
+            let Some(placeholder) = opt.value else {
+                continue;
+            };
+            let mut patterns = vec![format!("{}:--{}", current, opt.long)];
+            if let Some(short) = opt.short {
+                patterns.push(format!("{}:-{}", current, short));
+            }
+            let reply = if !opt.choices.is_empty() {
+                format!(
+                    "COMPREPLY=($(compgen -W \"{}\" -- \"${{cur}}\"))\n                ",
+                    opt.choices.join(" ")
+                )
+            } else if takes_files(placeholder) {
+                "COMPREPLY=($(compgen -f -- \"${cur}\"))\n                ".to_owned()
+            } else {
+                String::new()
+            };
+            values.push_str(&format!(
+                "            {})\n                {}return ;;\n",
+                patterns.join("|"),
+                reply
+            ));
+        }
+
+        let mut candidates: Vec<String> = command
+            .subcommands
+            .iter()
+            .map(|subcommand| subcommand.name.to_owned())
+            .collect();
+        for opt in command.options {
+            candidates.extend(opt.short.map(|short| format!("-{}", short)));
+            candidates.push(format!("--{}", opt.long));
+        }
+        for arg in command.args {
+            candidates.extend(arg.choices.iter().map(|choice| choice.to_string()));
+        }
+        let mut reply = format!(
+            "COMPREPLY=($(compgen -W \"{}\" -- \"${{cur}}\")",
+            candidates.join(" ")
+        );
+        if command.args.iter().any(|arg| takes_files(arg.usage)) {
+            reply.push_str(" $(compgen -f -- \"${cur}\")");
+        }
+        words.push_str(&format!(
+            "        {})\n            {}) ;;\n",
+            current, reply
+        ));
+    }
+
+    format!(
+        r#"_{name}() {{
+    local cur prev state i
+    cur="${{COMP_WORDS[COMP_CWORD]}}"
+    prev="${{COMP_WORDS[COMP_CWORD-1]}}"
+    state="{name}"
+    for ((i = 1; i < COMP_CWORD; i++)); do
+        case "${{state}}:${{COMP_WORDS[i]}}" in
+{transitions}        esac
+    done
+
+    case "${{state}}:${{prev}}" in
+{values}    esac
+
+    case "${{state}}" in
+{words}    esac
+}}
+
+complete -F _{name} {name}
+"#,
+        name = app.name,
+        transitions = transitions,
+        values = values,
+        words = words,
+    )
+}
+
+/// zsh runs the bash script through its `bashcompinit` compatibility layer.
+fn zsh(app: &Command) -> String {
+    format!(
+        "#compdef {}\n\nautoload -U +X bashcompinit && bashcompinit\n\n{}",
+        app.name,
+        bash(app)
+    )
+}
+
+fn fish(app: &Command) -> String {
+    let quote = |text: &str| format!("'{}'", text.replace('\\', "\\\\").replace('\'', "\\'"));
+    let mut script = String::new();
+
+    for (path, command) in commands(app) {
+        let mut conditions: Vec<_> = path
+            .iter()
+            .map(|name| format!("__fish_seen_subcommand_from {}", name))
+            .collect();
+        if !command.subcommands.is_empty() {
+            let names: Vec<_> = command.subcommands.iter().map(|sub| sub.name).collect();
+            conditions.push(format!(
+                "not __fish_seen_subcommand_from {}",
+                names.join(" ")
+            ));
+        }
+        let condition = match conditions.is_empty() {
+            true => "true".to_owned(),
+            false => conditions.join("; and "),
+        };
+        let complete = format!("complete -c {} -n {}", app.name, quote(&condition));
+
+        for subcommand in command.subcommands {
+            script.push_str(&format!(
+                "{} -f -a {} -d {}\n",
+                complete,
+                subcommand.name,
+                quote(subcommand.about)
+            ));
+        }
+        for opt in command.options {
+            script.push_str(&format!("{}{}\n", complete, fish_option(opt, quote)));
+        }
+        for arg in command.args {
+            if !arg.choices.is_empty() {
+                script.push_str(&format!(
+                    "{} -f -a {}\n",
+                    complete,
+                    quote(&arg.choices.join(" "))
+                ));
+            }
+        }
+    }
+    script
+}
+
+fn fish_option(opt: &Opt, quote: impl Fn(&str) -> String) -> String {
+    let mut line = String::new();
+    if let Some(short) = opt.short {
+        line.push_str(&format!(" -s {}", short));
+    }
+    line.push_str(&format!(" -l {}", opt.long));
+    match opt.value {
+        Some(_) if !opt.choices.is_empty() => {
+            line.push_str(&format!(" -x -a {}", quote(&opt.choices.join(" "))));
+        }
+        Some(placeholder) if takes_files(placeholder) => line.push_str(" -r -F"),
+        Some(_) => line.push_str(" -x"),
+        None => {}
+    }
+    line.push_str(&format!(" -d {}", quote(opt.help)));
+    line
+}
+
+pub(super) fn write_man_page(app: &Command, out: &mut dyn Write) -> CliResult {
+    let version = env!("CARGO_PKG_VERSION");
+    let mut page = format!(
+        ".TH {} 1 \"\" \"{} {}\" \"User Commands\"\n",
+        app.name.to_uppercase(),
+        app.name,
+        version
+    );
+    page.push_str(&format!(".SH NAME\n{} \\- {}\n", app.name, roff(app.about)));
+    page.push_str(&format!(".SH SYNOPSIS\n{}\n", roff(&app.usage(app.name))));
+    page.push_str(".SH OPTIONS\n");
+    man_options(app, &mut page);
+
+    page.push_str(".SH COMMANDS\n");
+    for (path, command) in commands(app).into_iter().skip(1) {
+        let path = format!("{} {}", app.name, path.join(" "));
+        page.push_str(&format!(".SS \"{}\"\n{}\n", path, roff(command.about)));
+        page.push_str(&format!(".PP\n{}\n", roff(&command.usage(&path))));
+        for arg in command.args {
+            page.push_str(&format!(".TP\n{}\n{}\n", roff(arg.usage), roff(arg.help)));
+        }
+        man_options(command, &mut page);
+    }
+
+    page.push_str(".SH ENVIRONMENT\n");
+    for (name, help) in ENVIRONMENT {
+        page.push_str(&format!(".TP\n.B {}\n{}\n", name, roff(help)));
+    }
+    page.push_str(".SH \"EXIT STATUS\"\n");
+    for (status, help) in EXIT_STATUSES {
+        page.push_str(&format!(".TP\n.B {}\n{}\n", status, roff(help)));
+    }
+
+    write!(out, "{}", page)?;
+    Ok(())
+}
+
+fn man_options(command: &Command, page: &mut String) {
+    for opt in command.options {
+        page.push_str(&format!(
+            ".TP\n{}\n{}\n",
+            roff(opt.signature().trim_start()),
+            roff(opt.help)
+        ));
+    }
+}
+
+/// Escapes `text` for a roff text line.
+fn roff(text: &str) -> String {
+    let text = text.replace('\\', "\\e").replace('-', "\\-");
+    if text.starts_with(['.', '\'']) {
+        format!("\\&{}", text)
+    } else {
+        text
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::APP;
+    use super::*;
+
+    fn generate(shell: &str) -> String {
+        let mut out = Vec::new();
+        write(shell, &APP, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_completions_cover_nested_commands() {
+        let bash = generate("bash");
+        assert!(bash.contains("rustranslate__cache:purge) state=\"rustranslate__cache__purge\""));
+        assert!(bash.contains("rustranslate__translate:--backend|rustranslate__translate:-b)"));
+        assert!(bash.contains("complete -F _rustranslate rustranslate"));
+
+        assert!(generate("zsh").starts_with("#compdef rustranslate\n"));
+
+        let fish = generate("fish");
+        assert!(fish.contains(
+            "complete -c rustranslate -n '__fish_seen_subcommand_from cache; and \
+             __fish_seen_subcommand_from purge' -l expired -d 'Only remove expired entries'"
+        ));
+        assert!(fish.contains("-s b -l backend -x -a 'google deepl libretranslate llm offline'"));
+
+        assert!(matches!(
+            write("powershell", &APP, &mut Vec::new()),
+            Err(CliError::Usage(_))
+        ));
+    }
+
+    #[test]
+    fn test_man_page() {
+        let mut out = Vec::new();
+        write_man_page(&APP, &mut out).unwrap();
+        let page = String::from_utf8(out).unwrap();
+        assert!(page.starts_with(".TH RUSTRANSLATE 1"));
+        assert!(page.contains(".SS \"rustranslate cache purge\""));
+        assert!(page.contains("\\-t, \\-\\-target <LANG>"));
+        assert!(page.contains(".TP\n.B 77\n"));
+    }
+}
@@ -0,0 +1,356 @@
+//! A small line editor with persistent history for the REPL.
+
+use std::fs::{self, OpenOptions};
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+/// Most lines kept in the history file.
+const MAX_HISTORY: usize = 1000;
+
+/// Reads lines, remembering them across sessions in a history file.
+#[derive(Debug, Default)]
+pub(super) struct LineEditor {
+    history: Vec<String>,
+    path: Option<PathBuf>,
+}
+
+impl LineEditor {
+    /// Loads the history stored at `path`, if any.
+    pub(super) fn new(path: Option<PathBuf>) -> Self {
+        let mut history: Vec<String> = path
+            .as_ref()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| text.lines().map(str::to_owned).collect())
+            .unwrap_or_default();
+
+        if history.len() > MAX_HISTORY {
+            history.drain(..history.len() - MAX_HISTORY);
+            if let Some(path) = &path {
+                let _ = fs::write(path, history.join("\n") + "\n");
+            }
+        }
+        Self { history, path }
+    }
+
+    /// The history file used when none is configured: `history` in the
+    /// `rustranslate` directory of the user's state directory.
+    pub(super) fn default_path() -> Option<PathBuf> {
+        let var = |name| std::env::var_os(name).filter(|value| !value.is_empty());
+        let dir = var("XDG_STATE_HOME")
+            .map(PathBuf::from)
+            .or_else(|| var("LOCALAPPDATA").map(PathBuf::from))
+            .or_else(|| var("HOME").map(|home| PathBuf::from(home).join(".local/state")))?;
+        Some(dir.join("rustranslate").join("history"))
+    }
+
+    pub(super) fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    /// Records `line`, unless it is blank or repeats the previous one.
+    pub(super) fn add(&mut self, line: &str) {
+        let line = line.trim();
+        if line.is_empty() || self.history.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.history.push(line.to_owned());
+
+        // History is a convenience: failing to save it is not worth an error.
+        if let Some(path) = &self.path {
+            if let Some(parent) = path.parent() {
+                let _ = fs::create_dir_all(parent);
+            }
+            let file = OpenOptions::new().create(true).append(true).open(path);
+            if let Ok(mut file) = file {
+                let _ = writeln!(file, "{}", line);
+            }
+        }
+    }
+
+    /// Reads a line from `input` without editing, as used when input is
+    /// piped. Returns `None` at the end of input.
+    pub(super) fn read_plain(&mut self, input: &mut dyn BufRead) -> io::Result<Option<String>> {
+        let mut line = String::new();
+        if input.read_line(&mut line)? == 0 {
+            return Ok(None);
+        }
+        Ok(Some(line.trim_end_matches(['\n', '\r']).to_owned()))
+    }
+
+    /// Reads a line from the terminal after printing `prompt`, with cursor
+    /// movement, Emacs-style shortcuts and history navigation. Returns
+    /// `None` when the user presses Ctrl-D on an empty line.
+    #[cfg(unix)]
+    pub(super) fn read_line(&mut self, prompt: &str) -> io::Result<Option<String>> {
+        let _raw = terminal::RawMode::enable()?;
+        let mut stdin = io::stdin().lock();
+        let mut out = io::stdout().lock();
+        let mut line = Line::default();
+        let mut position = self.history.len();
+        let mut draft = Vec::new();
+
+        line.redraw(&mut out, prompt)?;
+        loop {
+            let key = match read_key(&mut stdin)? {
+                Some(key) => key,
+                None => return Ok(None),
+            };
+            match key {
+                Key::Enter => {
+                    write!(out, "\r\n")?;
+                    out.flush()?;
+                    return Ok(Some(line.text()));
+                }
+                Key::Interrupt => {
+                    write!(out, "^C\r\n")?;
+                    out.flush()?;
+                    return Ok(Some(String::new()));
+                }
+                Key::Eof if line.chars.is_empty() => {
+                    write!(out, "\r\n")?;
+                    out.flush()?;
+                    return Ok(None);
+                }
+                Key::Up | Key::Down => {
+                    if position == self.history.len() {
+                        draft = line.chars.clone();
+                    }
+                    position = match key {
+                        Key::Up => position.saturating_sub(1),
+                        _ => (position + 1).min(self.history.len()),
+                    };
+                    let chars = match self.history.get(position) {
+                        Some(entry) => entry.chars().collect(),
+                        None => draft.clone(),
+                    };
+                    line.cursor = chars.len();
+                    line.chars = chars;
+                }
+                key => line.edit(key),
+            }
+            line.redraw(&mut out, prompt)?;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Key {
+    Char(char),
+    Enter,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    /// Ctrl-U: delete up to the cursor.
+    KillStart,
+    /// Ctrl-K: delete from the cursor.
+    KillEnd,
+    /// Ctrl-W: delete the word before the cursor.
+    KillWord,
+    Interrupt,
+    /// Ctrl-D: end of input on an empty line, delete otherwise.
+    Eof,
+    Ignored,
+}
+
+/// The line being edited.
+#[derive(Debug, Default)]
+struct Line {
+    chars: Vec<char>,
+    cursor: usize,
+}
+
+impl Line {
+    fn text(&self) -> String {
+        self.chars.iter().collect()
+    }
+
+    fn edit(&mut self, key: Key) {
+        match key {
+            Key::Char(c) => {
+                self.chars.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            Key::Backspace if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.chars.remove(self.cursor);
+            }
+            Key::Delete | Key::Eof if self.cursor < self.chars.len() => {
+                self.chars.remove(self.cursor);
+            }
+            Key::Left => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right => self.cursor = (self.cursor + 1).min(self.chars.len()),
+            Key::Home => self.cursor = 0,
+            Key::End => self.cursor = self.chars.len(),
+            Key::KillStart => {
+                self.chars.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::KillEnd => self.chars.truncate(self.cursor),
+            Key::KillWord => {
+                let mut start = self.cursor;
+                while start > 0 && self.chars[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                while start > 0 && !self.chars[start - 1].is_whitespace() {
+                    start -= 1;
+                }
+                self.chars.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            _ => {}
+        }
+    }
+
+    fn redraw(&self, out: &mut impl Write, prompt: &str) -> io::Result<()> {
+        write!(out, "\r\x1b[K{}{}", prompt, self.text())?;
+        let back = self.chars.len() - self.cursor;
+        if back > 0 {
+            write!(out, "\x1b[{}D", back)?;
+        }
+        out.flush()
+    }
+}
+
+/// Reads one key press, decoding UTF-8 and ANSI escape sequences. Returns
+/// `None` at the end of input.
+fn read_key(input: &mut impl io::Read) -> io::Result<Option<Key>> {
+    let mut byte = [0];
+    let mut next = |input: &mut dyn io::Read| -> io::Result<Option<u8>> {
+        match input.read(&mut byte)? {
+            0 => Ok(None),
+            _ => Ok(Some(byte[0])),
+        }
+    };
+
+    let Some(first) = next(input)? else {
+        return Ok(None);
+    };
+    let key = match first {
+        b'\r' | b'\n' => Key::Enter,
+        0x7f | 0x08 => Key::Backspace,
+        0x01 => Key::Home,
+        0x03 => Key::Interrupt,
+        0x04 => Key::Eof,
+        0x05 => Key::End,
+        0x0b => Key::KillEnd,
+        0x15 => Key::KillStart,
+        0x17 => Key::KillWord,
+        0x1b => {
+            if next(input)? != Some(b'[') {
+                return Ok(Some(Key::Ignored));
+            }
+            match next(input)? {
+                Some(b'A') => Key::Up,
+                Some(b'B') => Key::Down,
+                Some(b'C') => Key::Right,
+                Some(b'D') => Key::Left,
+                Some(b'H') => Key::Home,
+                Some(b'F') => Key::End,
+                Some(b'3') if next(input)? == Some(b'~') => Key::Delete,
+                _ => Key::Ignored,
+            }
+        }
+        byte if byte < 0x20 => Key::Ignored,
+        byte => {
+            let len = match byte {
+                0xf0.. => 4,
+                0xe0.. => 3,
+                0xc0.. => 2,
+                _ => 1,
+            };
+            let mut bytes = vec![byte];
+            for _ in 1..len {
+                bytes.extend(next(input)?);
+            }
+            match std::str::from_utf8(&bytes)
+                .ok()
+                .and_then(|s| s.chars().next())
+            {
+                Some(c) => Key::Char(c),
+                None => Key::Ignored,
+            }
+        }
+    };
+    Ok(Some(key))
+}
+
+#[cfg(unix)]
+mod terminal {
+    use std::io;
+
+    /// Puts the terminal into non-canonical mode without echo, restoring
+    /// the previous settings when dropped.
+    pub(super) struct RawMode {
+        original: libc::termios,
+    }
+
+    impl RawMode {
+        pub(super) fn enable() -> io::Result<Self> {
+            // SAFETY: `termios` is plain data that tcgetattr fills in.
+            let mut original = unsafe { std::mem::zeroed::<libc::termios>() };
+            if unsafe { libc::tcgetattr(libc::STDIN_FILENO, &mut original) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+
+            let mut raw = original;
+            raw.c_lflag &= !(libc::ICANON | libc::ECHO | libc::ISIG | libc::IEXTEN);
+            raw.c_iflag &= !(libc::IXON | libc::ICRNL);
+            raw.c_cc[libc::VMIN] = 1;
+            raw.c_cc[libc::VTIME] = 0;
+            if unsafe { libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &raw) } != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            Ok(Self { original })
+        }
+    }
+
+    impl Drop for RawMode {
+        fn drop(&mut self) {
+            unsafe {
+                libc::tcsetattr(libc::STDIN_FILENO, libc::TCSAFLUSH, &self.original);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::TempDir;
+
+    #[test]
+    fn test_history_persists() {
+        let dir = TempDir::new();
+        let path = dir.join("state/history");
+
+        let mut editor = LineEditor::new(Some(path.clone()));
+        for line in ["hello", "hello", " ", ":to ja"] {
+            editor.add(line);
+        }
+        let editor = LineEditor::new(Some(path));
+        assert_eq!(editor.history(), ["hello", ":to ja"]);
+    }
+
+    #[test]
+    fn test_keys_edit_the_line() {
+        let mut input = "ab\x1b[D\x1b[Dx\x1b[3~é\x05 cd\x17z\x15".as_bytes();
+        let mut line = Line::default();
+        let mut texts = Vec::new();
+        while let Some(key) = read_key(&mut input).unwrap() {
+            line.edit(key);
+            texts.push(line.text());
+        }
+        assert_eq!(
+            texts,
+            [
+                "a", "ab", "ab", "ab", "xab", "xb", "xéb", "xéb", "xéb ", "xéb c", "xéb cd",
+                "xéb ", "xéb z", ""
+            ]
+        );
+    }
+}
@@ -0,0 +1,403 @@
+use super::{
+    translator, usage, Arg, CliError, CliResult, Command, Matches, Opt, BACKEND, CACHE_FILE,
+    DICTIONARY, HELP, NO_CACHE, SOURCE, TARGET,
+};
+use crate::formats::fluent::Resource;
+use crate::formats::gettext::Catalog;
+use crate::formats::json::JsonDocument;
+use crate::formats::toml::TomlDocument;
+use crate::formats::xliff::XliffDocument;
+use crate::formats::yaml::YamlDocument;
+use crate::formats::{KeyFilter, SyntaxError};
+use crate::AUTO_DETECT;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// File formats the command understands, named by their usual extension.
+const FORMATS: &[&str] = &["ftl", "json", "po", "toml", "xliff", "yaml"];
+
+pub(super) const FILE: Command = Command {
+    name: "file",
+    about: "Translate a localization file, leaving the rest of it as it was",
+    args: &[Arg {
+        usage: "<FILE>",
+        choices: &[],
+        help: "JSON, YAML or TOML locale file, Fluent .ftl resource, gettext .po/.pot catalog or \
+               XLIFF file to translate",
+    }],
+    options: &[
+        SOURCE,
+        Opt {
+            help: "Language to translate to [default: the target language of catalogs and XLIFF \
+                   files]",
+            ..TARGET
+        },
+        BACKEND,
+        DICTIONARY,
+        CACHE_FILE,
+        NO_CACHE,
+        Opt {
+            long: "format",
+            short: Some('f'),
+            value: Some("FORMAT"),
+            choices: FORMATS,
+            help: "Format of the file [default: from its extension]",
+        },
+        Opt {
+            long: "output",
+            short: Some('o'),
+            value: Some("FILE"),
+            choices: &[],
+            help: "File to write the translation to; JSON strings it already has are kept \
+                   [default: stdout]",
+        },
+        Opt {
+            long: "include",
+            short: Some('i'),
+            value: Some("GLOB"),
+            choices: &[],
+            help: "Only translate keys matching GLOB, e.g. 'home.*' or '**.label'",
+        },
+        Opt {
+            long: "exclude",
+            short: Some('x'),
+            value: Some("GLOB"),
+            choices: &[],
+            help: "Do not translate keys matching GLOB",
+        },
+        HELP,
+    ],
+    subcommands: &[],
+};
+
+pub(super) async fn run(matches: &Matches, path: &str, out: &mut dyn Write) -> CliResult {
+    let [file] = matches.args.as_slice() else {
+        return Err(usage("Expected one FILE".to_owned(), &FILE, path));
+    };
+    let format = match matches.value("format") {
+        Some(format) => format,
+        None => match Path::new(file).extension().and_then(|e| e.to_str()) {
+            Some("ftl") => "ftl",
+            Some("json") => "json",
+            Some("po" | "pot") => "po",
+            Some("toml") => "toml",
+            Some("xlf" | "xliff") => "xliff",
+            Some("yaml" | "yml") => "yaml",
+            _ => {
+                return Err(usage(
+                    format!("Cannot tell the format of {}; use --format", file),
+                    &FILE,
+                    path,
+                ))
+            }
+        },
+    };
+    let text = read(file)?;
+    let source = matches.value("source").unwrap_or(AUTO_DETECT);
+    let output = matches.value("output");
+    let keys = matches
+        .values("include")
+        .fold(KeyFilter::new(), KeyFilter::with_include);
+    let keys = matches
+        .values("exclude")
+        .fold(keys, KeyFilter::with_exclude);
+    let target = || {
+        matches
+            .value("target")
+            .ok_or_else(|| usage("Missing --target".to_owned(), &FILE, path))
+    };
+
+    if matches!(format, "ftl" | "po" | "xliff")
+        && (matches.flag("include") || matches.flag("exclude"))
+    {
+        return Err(usage(
+            "--include and --exclude only apply to JSON, YAML and TOML files".to_owned(),
+            &FILE,
+            path,
+        ));
+    }
+
+    let (translated, count) = match format {
+        "ftl" => {
+            let mut resource = parse(file, &text, Resource::parse)?;
+            let count = resource
+                .translate(&translator(matches, source, target()?)?)
+                .await?;
+            (resource.to_string(), count)
+        }
+        "json" => {
+            let mut document = parse(file, &text, JsonDocument::parse)?;
+            let existing = match output.filter(|output| Path::new(output).exists()) {
+                Some(output) => Some(parse(output, &read(output)?, JsonDocument::parse)?),
+                None => None,
+            };
+            let translator = translator(matches, source, target()?)?;
+            let count = match &existing {
+                Some(existing) => document.update(&translator, &keys, existing).await?,
+                None => document.translate(&translator, &keys).await?,
+            };
+            (document.to_string(), count)
+        }
+        "yaml" => {
+            let mut document = parse(file, &text, YamlDocument::parse)?;
+            let translator = translator(matches, source, target()?)?;
+            let count = document.translate(&translator, &keys).await?;
+            (document.to_string(), count)
+        }
+        "toml" => {
+            let mut document = parse(file, &text, TomlDocument::parse)?;
+            let translator = translator(matches, source, target()?)?;
+            let count = document.translate(&translator, &keys).await?;
+            (document.to_string(), count)
+        }
+        "xliff" => {
+            let mut document = parse(file, &text, XliffDocument::parse)?;
+            let source = matches
+                .value("source")
+                .or(document.source_language())
+                .unwrap_or(AUTO_DETECT)
+                .to_owned();
+            let target = match matches.value("target") {
+                Some(target) => target.to_owned(),
+                None => match document.target_language() {
+                    Some(language) => language.to_owned(),
+                    None => target()?.to_owned(),
+                },
+            };
+            let count = document
+                .translate(&translator(matches, &source, &target)?)
+                .await?;
+            (document.to_string(), count)
+        }
+        "po" => {
+            let mut catalog = parse(file, &text, Catalog::parse)?;
+            let target = match matches.value("target") {
+                Some(target) => target.to_owned(),
+                None => match catalog.header_field("Language") {
+                    Some(language) if !language.is_empty() => language.to_owned(),
+                    _ => target()?.to_owned(),
+                },
+            };
+            let count = catalog
+                .translate(&translator(matches, source, &target)?)
+                .await?;
+            (catalog.to_string(), count)
+        }
+        _ => {
+            return Err(usage(
+                format!(
+                    "Unknown format: {} (expected one of {})",
+                    format,
+                    FORMATS.join(", ")
+                ),
+                &FILE,
+                path,
+            ))
+        }
+    };
+
+    match output {
+        Some(output) => {
+            fs::write(output, translated).map_err(|error| file_error(output, error))?;
+            writeln!(out, "Translated {} entries into {}", count, output)?;
+        }
+        None => write!(out, "{}", translated)?,
+    }
+    Ok(())
+}
+
+fn read(path: &str) -> Result<String, CliError> {
+    fs::read_to_string(path).map_err(|error| file_error(path, error))
+}
+
+/// Parses the contents of the file at `path` with `parse`.
+fn parse<T>(
+    path: &str,
+    text: &str,
+    parse: fn(&str) -> Result<T, SyntaxError>,
+) -> Result<T, CliError> {
+    parse(text).map_err(|error| CliError::Syntax(path.to_owned(), error))
+}
+
+/// An I/O error naming the file it happened on.
+fn file_error(path: &str, error: io::Error) -> CliError {
+    CliError::Io(io::Error::new(error.kind(), format!("{}: {}", path, error)))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::{run, CliError};
+    use crate::test_support::TempDir;
+    use std::fs;
+    use std::path::PathBuf;
+
+    async fn run_file(args: &[&str]) -> Result<String, CliError> {
+        let args: Vec<_> = ["file"]
+            .iter()
+            .chain(args)
+            .map(|arg| arg.to_string())
+            .collect();
+        let mut out = Vec::new();
+        run(&args, &mut "".as_bytes(), &mut out).await?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    fn dictionary(dir: &TempDir) -> PathBuf {
+        let path = dir.join("en-fr.tsv");
+        fs::write(
+            &path,
+            "hello\tbonjour\nworld\tmonde\nfile\tfichier\nfiles\tfichiers\n",
+        )
+        .unwrap();
+        path
+    }
+
+    /// Runs `file` with `args` and the offline backend, using the
+    /// [`dictionary`] written to `dir`.
+    async fn run_offline(dir: &TempDir, args: &[&str]) -> Result<String, CliError> {
+        let dictionary = dictionary(dir);
+        let options = ["-b", "offline", "-d", dictionary.to_str().unwrap()];
+        run_file(&[&options[..], args].concat()).await
+    }
+
+    #[tokio::test]
+    async fn test_translate_po_file() {
+        let dir = TempDir::new();
+        let catalog = dir.join("fr.po");
+        fs::write(
+            &catalog,
+            "msgid \"\"\nmsgstr \"\"\n\"Language: fr\\n\"\n\n\
+             #: src/main.rs:1\nmsgid \"hello\"\nmsgstr \"\"\n\n\
+             msgid \"file\"\nmsgid_plural \"files\"\nmsgstr[0] \"\"\nmsgstr[1] \"\"\n",
+        )
+        .unwrap();
+        let output = dir.join("out.po");
+
+        let out = run_offline(
+            &dir,
+            &[
+                "-s",
+                "en",
+                catalog.to_str().unwrap(),
+                "-o",
+                output.to_str().unwrap(),
+            ],
+        )
+        .await
+        .unwrap();
+
+        let output = fs::read_to_string(&output).unwrap();
+        assert!(
+            output.contains("#: src/main.rs:1\n#, fuzzy\nmsgid \"hello\"\nmsgstr \"bonjour\"\n")
+        );
+        assert!(output.contains("msgstr[0] \"fichier\"\nmsgstr[1] \"fichiers\"\n"));
+        assert!(out.starts_with("Translated 2 entries"));
+
+        fs::write(&catalog, "msgid \"hello\"\n").unwrap();
+        let error = run_file(&[catalog.to_str().unwrap()]).await.unwrap_err();
+        assert_eq!(error.exit_code(), 65, "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_translate_json_file() {
+        let dir = TempDir::new();
+        let source = dir.join("en.json");
+        fs::write(
+            &source,
+            "{\n  \"greeting\": \"hello\",\n  \"nested\": {\"word\": \"world\", \"count\": 2},\n  \"brand\": \"world\"\n}\n",
+        )
+        .unwrap();
+        let target = dir.join("fr.json");
+        fs::write(&target, r#"{"greeting": "salut"}"#).unwrap();
+
+        let args = [
+            "-s",
+            "en",
+            "-t",
+            "fr",
+            "--exclude=brand",
+            source.to_str().unwrap(),
+        ];
+        let output = run_offline(
+            &dir,
+            &[&args[..], &["-o", target.to_str().unwrap()]].concat(),
+        )
+        .await
+        .unwrap();
+        assert!(output.starts_with("Translated 1 entries"), "{}", output);
+        assert_eq!(
+            fs::read_to_string(&target).unwrap(),
+            "{\n  \"greeting\": \"salut\",\n  \"nested\": {\"word\": \"monde\", \"count\": 2},\n  \"brand\": \"world\"\n}\n"
+        );
+
+        let output = run_offline(&dir, &args).await.unwrap();
+        assert!(output.contains("\"greeting\": \"bonjour\""), "{}", output);
+    }
+
+    #[tokio::test]
+    async fn test_translate_yaml_file() {
+        let dir = TempDir::new();
+        let source = dir.join("en.yml");
+        fs::write(
+            &source,
+            "en:\n  # Greeting\n  hello: 'hello'\n  other: world\n",
+        )
+        .unwrap();
+
+        let args = [
+            "-s",
+            "en",
+            "-t",
+            "fr",
+            "-i",
+            "**.hello",
+            source.to_str().unwrap(),
+        ];
+        let output = run_offline(&dir, &args).await.unwrap();
+        assert_eq!(
+            output,
+            "en:\n  # Greeting\n  hello: 'bonjour'\n  other: world\n"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_translate_ftl_file() {
+        let dir = TempDir::new();
+        let source = dir.join("en.ftl");
+        fs::write(&source, "# Greeting\nhello = hello\nworld = world\n").unwrap();
+
+        let args = ["-s", "en", "-t", "fr", source.to_str().unwrap()];
+        let output = run_offline(&dir, &args).await.unwrap();
+        assert_eq!(output, "# Greeting\nhello = bonjour\nworld = monde\n");
+
+        let error = run_file(&["-t", "fr", "-x", "hello", source.to_str().unwrap()])
+            .await
+            .unwrap_err();
+        assert!(error.to_string().contains("--include"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_translate_xliff_file() {
+        let dir = TempDir::new();
+        let source = dir.join("messages.xlf");
+        fs::write(
+            &source,
+            "<xliff version=\"1.2\">\n<file source-language=\"en\" target-language=\"fr\">\n\
+             <trans-unit id=\"a\"><source>hello</source></trans-unit>\n</file>\n</xliff>\n",
+        )
+        .unwrap();
+
+        // The languages come from the file.
+        let output = run_offline(&dir, &[source.to_str().unwrap()])
+            .await
+            .unwrap();
+        assert!(
+            output.contains(
+                "<source>hello</source><target state=\"needs-review-translation\">bonjour</target>"
+            ),
+            "{}",
+            output
+        );
+    }
+}
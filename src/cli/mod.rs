@@ -0,0 +1,1028 @@
+//! The `rustranslate` command line.
+
+mod cache;
+mod completions;
+mod editor;
+mod file;
+mod repl;
+
+use crate::backend::{
+    DeepLBackend, Dictionary, GoogleGtxBackend, LibreTranslateBackend, LlmBackend, OfflineBackend,
+};
+use crate::formats::SyntaxError;
+use crate::{CacheError, DiskCache, Language, TranslationError, Translator, AUTO_DETECT};
+use serde_json::{json, Value};
+use std::error::Error;
+use std::fmt;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// An option of a command.
+#[derive(Debug)]
+struct Opt {
+    long: &'static str,
+    short: Option<char>,
+    /// Placeholder of the option's value, `None` for flags.
+    value: Option<&'static str>,
+    /// Values offered by shell completions.
+    choices: &'static [&'static str],
+    help: &'static str,
+}
+
+/// A positional argument of a command.
+#[derive(Debug)]
+struct Arg {
+    usage: &'static str,
+    choices: &'static [&'static str],
+    help: &'static str,
+}
+
+/// A command, described once for parsing, help, completions and the man
+/// page.
+#[derive(Debug)]
+struct Command {
+    name: &'static str,
+    about: &'static str,
+    args: &'static [Arg],
+    options: &'static [Opt],
+    subcommands: &'static [Command],
+}
+
+impl Command {
+    fn subcommand(&self, name: &str) -> Option<&'static Command> {
+        self.subcommands.iter().find(|command| command.name == name)
+    }
+
+    fn usage(&self, path: &str) -> String {
+        let mut usage = path.to_owned();
+        if !self.options.is_empty() {
+            usage.push_str(" [OPTIONS]");
+        }
+        for arg in self.args {
+            usage = format!("{} {}", usage, arg.usage);
+        }
+        if !self.subcommands.is_empty() {
+            usage.push_str(" <COMMAND>");
+        }
+        usage
+    }
+
+    fn help(&self, path: &str) -> String {
+        let mut help = format!("{}\n\nUsage: {}\n", self.about, self.usage(path));
+        let mut section = |title: &str, rows: Vec<(String, &str)>| {
+            if rows.is_empty() {
+                return;
+            }
+            let width = rows.iter().map(|(name, _)| name.len()).max().unwrap_or(0);
+            help.push_str(&format!("\n{}:\n", title));
+            for (name, text) in rows {
+                help.push_str(&format!("  {:width$}  {}\n", name, text, width = width));
+            }
+        };
+
+        section(
+            "Commands",
+            self.subcommands
+                .iter()
+                .map(|command| (command.name.to_owned(), command.about))
+                .collect(),
+        );
+        section(
+            "Arguments",
+            self.args
+                .iter()
+                .map(|arg| (arg.usage.to_owned(), arg.help))
+                .collect(),
+        );
+        section(
+            "Options",
+            self.options
+                .iter()
+                .map(|opt| (opt.signature(), opt.help))
+                .collect(),
+        );
+        help
+    }
+}
+
+impl Opt {
+    /// `-s, --source <LANG>`
+    fn signature(&self) -> String {
+        let mut signature = match self.short {
+            Some(short) => format!("-{}, --{}", short, self.long),
+            None => format!("    --{}", self.long),
+        };
+        if let Some(value) = self.value {
+            signature = format!("{} <{}>", signature, value);
+        }
+        signature
+    }
+}
+
+const BACKENDS: &[&str] = &["google", "deepl", "libretranslate", "llm", "offline"];
+
+const HELP: Opt = Opt {
+    long: "help",
+    short: Some('h'),
+    value: None,
+    choices: &[],
+    help: "Print help",
+};
+
+const BACKEND: Opt = Opt {
+    long: "backend",
+    short: Some('b'),
+    value: Some("NAME"),
+    choices: BACKENDS,
+    help: "Translation service to use [default: google]",
+};
+
+const DICTIONARY: Opt = Opt {
+    long: "dictionary",
+    short: Some('d'),
+    value: Some("FILE"),
+    choices: &[],
+    help: "TSV, StarDict .ifo or FreeDict .tei file for the offline backend, e.g. en-fr.tsv",
+};
+
+const JSON: Opt = Opt {
+    long: "json",
+    short: Some('j'),
+    value: None,
+    choices: &[],
+    help: "Print the result as JSON",
+};
+
+const CACHE_FILE: Opt = Opt {
+    long: "cache",
+    short: None,
+    value: Some("FILE"),
+    choices: &[],
+    help: "Translation cache file [default: $RUSTRANSLATE_CACHE or the user's cache directory]",
+};
+
+const NO_CACHE: Opt = Opt {
+    long: "no-cache",
+    short: None,
+    value: None,
+    choices: &[],
+    help: "Ask the backend again instead of reusing cached translations",
+};
+
+const TEXT: Arg = Arg {
+    usage: "[TEXT]...",
+    choices: &[],
+    help: "Text to process; read from stdin when omitted",
+};
+
+const SOURCE: Opt = Opt {
+    long: "source",
+    short: Some('s'),
+    value: Some("LANG"),
+    choices: &[],
+    help: "Language of the text [default: auto]",
+};
+
+const TARGET: Opt = Opt {
+    long: "target",
+    short: Some('t'),
+    value: Some("LANG"),
+    choices: &[],
+    help: "Language to translate to",
+};
+
+const TRANSLATE: Command = Command {
+    name: "translate",
+    about: "Translate text",
+    args: &[TEXT],
+    options: &[
+        SOURCE,
+        TARGET,
+        BACKEND,
+        DICTIONARY,
+        CACHE_FILE,
+        NO_CACHE,
+        JSON,
+        Opt {
+            long: "alternatives",
+            short: Some('a'),
+            value: None,
+            choices: &[],
+            help: "Add the backend's alternatives to the JSON output, with one more request",
+        },
+        HELP,
+    ],
+    subcommands: &[],
+};
+
+const REPL: Command = Command {
+    name: "repl",
+    about: "Translate lines typed at a prompt; :help lists commands",
+    args: &[],
+    options: &[
+        SOURCE,
+        Opt {
+            help: "Language to translate to [default: en]",
+            ..TARGET
+        },
+        BACKEND,
+        DICTIONARY,
+        CACHE_FILE,
+        NO_CACHE,
+        HELP,
+    ],
+    subcommands: &[],
+};
+
+const DETECT: Command = Command {
+    name: "detect",
+    about: "Detect the language of text",
+    args: &[TEXT],
+    options: &[BACKEND, DICTIONARY, JSON, HELP],
+    subcommands: &[],
+};
+
+const LANGUAGES: Command = Command {
+    name: "languages",
+    about: "List the languages a backend supports",
+    args: &[],
+    options: &[BACKEND, DICTIONARY, JSON, HELP],
+    subcommands: &[],
+};
+
+const COMPLETIONS: Command = Command {
+    name: "completions",
+    about: "Print a shell completion script",
+    args: &[Arg {
+        usage: "<SHELL>",
+        choices: completions::SHELLS,
+        help: "bash, zsh or fish",
+    }],
+    options: &[HELP],
+    subcommands: &[],
+};
+
+const MAN: Command = Command {
+    name: "man",
+    about: "Print the man page",
+    args: &[],
+    options: &[HELP],
+    subcommands: &[],
+};
+
+const APP: Command = Command {
+    name: "rustranslate",
+    about: "Translate text with Google, DeepL, LibreTranslate, an LLM or offline dictionaries",
+    args: &[],
+    options: &[
+        HELP,
+        Opt {
+            long: "version",
+            short: Some('V'),
+            value: None,
+            choices: &[],
+            help: "Print version",
+        },
+    ],
+    subcommands: &[
+        TRANSLATE,
+        DETECT,
+        LANGUAGES,
+        REPL,
+        file::FILE,
+        cache::CACHE,
+        COMPLETIONS,
+        MAN,
+    ],
+};
+
+/// Environment variables read by the backends, with their descriptions.
+const ENVIRONMENT: &[(&str, &str)] = &[
+    ("DEEPL_AUTH_KEY", "API key of the deepl backend"),
+    (
+        "LIBRETRANSLATE_URL",
+        "Server of the libretranslate backend [default: http://localhost:5000]",
+    ),
+    (
+        "LIBRETRANSLATE_API_KEY",
+        "API key of the libretranslate backend",
+    ),
+    (
+        "LLM_BASE_URL",
+        "OpenAI-compatible API of the llm backend [default: http://localhost:11434/v1]",
+    ),
+    ("LLM_MODEL", "Model used by the llm backend"),
+    ("LLM_API_KEY", "API key of the llm backend"),
+    (
+        "RUSTRANSLATE_CACHE",
+        "Translation cache file [default: the user's cache directory]",
+    ),
+];
+
+/// Exit statuses, following the BSD `sysexits.h` conventions.
+const EXIT_STATUSES: &[(i32, &str)] = &[
+    (0, "Success"),
+    (1, "No translation was found"),
+    (
+        64,
+        "Invalid command line or settings, or an operation the backend does not support",
+    ),
+    (
+        65,
+        "Empty input, an unknown or unsupported language, or a malformed file",
+    ),
+    (69, "The service could not be reached or failed"),
+    (74, "A file could not be read or written"),
+    (75, "Rate limited or out of quota; try again later"),
+    (76, "The service sent an unexpected response"),
+    (77, "The service rejected the credentials"),
+];
+
+/// Why a command failed.
+#[derive(Debug)]
+pub enum CliError {
+    /// The command line is invalid.
+    Usage(String),
+    Translation(TranslationError),
+    Cache(CacheError),
+    Io(io::Error),
+    /// A file is not valid in its format; the first field names the file.
+    Syntax(String, SyntaxError),
+}
+
+impl CliError {
+    /// The process exit status for this error, see `EXIT_STATUSES`.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Usage(_) => 64,
+            CliError::Syntax(_, _) => 65,
+            CliError::Cache(_) | CliError::Io(_) => 74,
+            CliError::Translation(error) => match error {
+                TranslationError::NoTranslationFound(_) => 1,
+                TranslationError::UnsupportedOperation(_)
+                | TranslationError::ClientSetupFailed(_) => 64,
+                TranslationError::EmptyInput
+                | TranslationError::InvalidLanguage(_)
+                | TranslationError::UnsupportedLanguagePair(_, _) => 65,
+                TranslationError::Timeout(_)
+                | TranslationError::Connect(_)
+                | TranslationError::RequestFailed(_)
+                | TranslationError::ServerError(_) => 69,
+                TranslationError::RateLimited(_)
+                | TranslationError::QuotaExceeded(_)
+                | TranslationError::Captcha(_) => 75,
+                TranslationError::Http(_) | TranslationError::ResponseParsingFailed(_) => 76,
+                TranslationError::AuthorizationFailed(_) => 77,
+            },
+        }
+    }
+}
+
+impl From<TranslationError> for CliError {
+    fn from(error: TranslationError) -> Self {
+        CliError::Translation(error)
+    }
+}
+
+impl From<CacheError> for CliError {
+    fn from(error: CacheError) -> Self {
+        CliError::Cache(error)
+    }
+}
+
+impl From<io::Error> for CliError {
+    fn from(error: io::Error) -> Self {
+        CliError::Io(error)
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::Usage(message) => write!(f, "{}", message),
+            CliError::Translation(error) => write!(f, "{}", error),
+            CliError::Cache(error) => write!(f, "{}", error),
+            CliError::Io(error) => write!(f, "{}", error),
+            CliError::Syntax(path, error) => write!(f, "{}: {}", path, error),
+        }
+    }
+}
+
+impl Error for CliError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CliError::Usage(_) => None,
+            CliError::Translation(error) => Some(error),
+            CliError::Cache(error) => Some(error),
+            CliError::Io(error) => Some(error),
+            CliError::Syntax(_, error) => Some(error),
+        }
+    }
+}
+
+/// The outcome of a command, whose output has been written.
+pub type CliResult = Result<(), CliError>;
+
+/// What commands read text from when none is given as arguments.
+pub trait Input: Read {
+    /// Whether someone types the input at a terminal, in which case `repl`
+    /// reads it with line editing.
+    fn is_terminal(&self) -> bool {
+        false
+    }
+}
+
+impl Input for io::Stdin {
+    fn is_terminal(&self) -> bool {
+        IsTerminal::is_terminal(self)
+    }
+}
+
+impl Input for &[u8] {}
+
+/// A subcommand with the arguments following its name.
+type Invocation<'a> = (&'static Command, &'a [String]);
+
+/// The options and arguments given to a command.
+#[derive(Debug, Default)]
+struct Matches {
+    options: Vec<(&'static str, Option<String>)>,
+    args: Vec<String>,
+}
+
+impl Matches {
+    /// Parses `args` for `command`, stopping at the first argument naming
+    /// one of its subcommands, which is returned with the remaining
+    /// arguments. Returns `Ok(None)` when help was requested.
+    fn parse<'a>(
+        command: &Command,
+        path: &str,
+        args: &'a [String],
+    ) -> Result<Option<(Self, Option<Invocation<'a>>)>, CliError> {
+        let mut matches = Self::default();
+        let mut index = 0;
+        let mut only_args = false;
+
+        while index < args.len() {
+            let arg = &args[index];
+            index += 1;
+
+            if only_args || arg == "-" || !arg.starts_with('-') {
+                if let Some(subcommand) = command.subcommand(arg).filter(|_| !only_args) {
+                    return Ok(Some((matches, Some((subcommand, &args[index..])))));
+                }
+                if !command.subcommands.is_empty() {
+                    return Err(usage(format!("Unknown command: {}", arg), command, path));
+                }
+                matches.args.push(arg.clone());
+                continue;
+            }
+            if arg == "--" {
+                only_args = true;
+                continue;
+            }
+
+            let (opt, attached) = match arg.strip_prefix("--") {
+                Some(long) => {
+                    let (name, value) = match long.split_once('=') {
+                        Some((name, value)) => (name, Some(value.to_owned())),
+                        None => (long, None),
+                    };
+                    let opt = command.options.iter().find(|opt| opt.long == name);
+                    (opt, value)
+                }
+                None => {
+                    let mut chars = arg[1..].chars();
+                    let short = chars.next();
+                    let rest = chars.as_str();
+                    let opt = command.options.iter().find(|opt| opt.short == short);
+                    (opt, Some(rest.to_owned()).filter(|rest| !rest.is_empty()))
+                }
+            };
+            let opt =
+                opt.ok_or_else(|| usage(format!("Unknown option: {}", arg), command, path))?;
+
+            if opt.long == "help" {
+                return Ok(None);
+            }
+            let value = match (opt.value, attached) {
+                (None, None) => None,
+                (None, Some(_)) => {
+                    return Err(usage(
+                        format!("--{} takes no value", opt.long),
+                        command,
+                        path,
+                    ))
+                }
+                (Some(_), Some(value)) => Some(value),
+                (Some(placeholder), None) => {
+                    let value = args.get(index).ok_or_else(|| {
+                        usage(
+                            format!("Missing {} for --{}", placeholder, opt.long),
+                            command,
+                            path,
+                        )
+                    })?;
+                    index += 1;
+                    Some(value.clone())
+                }
+            };
+            matches.options.push((opt.long, value));
+        }
+
+        Ok(Some((matches, None)))
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(long, _)| *long == name)
+    }
+
+    /// The last value given for the option `name`.
+    fn value(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(long, _)| *long == name)
+            .and_then(|(_, value)| value.as_deref())
+    }
+
+    fn values<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a str> {
+        self.options
+            .iter()
+            .filter(move |(long, _)| *long == name)
+            .filter_map(|(_, value)| value.as_deref())
+    }
+}
+
+fn usage(message: String, command: &Command, path: &str) -> CliError {
+    CliError::Usage(format!(
+        "{}\n\nUsage: {}\nFor more information, try '--help'.",
+        message,
+        command.usage(path)
+    ))
+}
+
+/// Runs the command line `args` (without the program name), reading text
+/// from `input` when none is given and writing results to `out`.
+pub async fn run(args: &[String], input: &mut dyn Input, out: &mut dyn Write) -> CliResult {
+    let (matches, subcommand) = match Matches::parse(&APP, APP.name, args)? {
+        Some(parsed) => parsed,
+        None => return write_help(&APP, APP.name, out),
+    };
+    if matches.flag("version") {
+        writeln!(out, "{} {}", APP.name, env!("CARGO_PKG_VERSION"))?;
+        return Ok(());
+    }
+    let Some((command, args)) = subcommand else {
+        return write_help(&APP, APP.name, out);
+    };
+
+    let path = format!("{} {}", APP.name, command.name);
+    let (matches, subcommand) = match Matches::parse(command, &path, args)? {
+        Some(parsed) => parsed,
+        None => return write_help(command, &path, out),
+    };
+
+    match command.name {
+        "cache" => cache::run(&matches, subcommand, out),
+        "translate" => translate(&matches, &path, input, out).await,
+        "repl" => repl::run(&matches, input, out).await,
+        "file" => file::run(&matches, &path, out).await,
+        "detect" => detect(&matches, input, out).await,
+        "languages" => languages(&matches, out).await,
+        "completions" => match matches.args.as_slice() {
+            [shell] => completions::write(shell, &APP, out),
+            _ => Err(usage("Expected one SHELL".to_owned(), command, &path)),
+        },
+        "man" => completions::write_man_page(&APP, out),
+        _ => unreachable!("every subcommand is handled"),
+    }
+}
+
+fn write_help(command: &Command, path: &str, out: &mut dyn Write) -> CliResult {
+    write!(out, "{}", command.help(path))?;
+    if command.name == APP.name {
+        writeln!(out, "\nEnvironment:")?;
+        for (name, help) in ENVIRONMENT {
+            writeln!(out, "  {}  {}", name, help)?;
+        }
+    }
+    Ok(())
+}
+
+async fn translate(
+    matches: &Matches,
+    path: &str,
+    input: &mut dyn Input,
+    out: &mut dyn Write,
+) -> CliResult {
+    let source = matches.value("source").unwrap_or(AUTO_DETECT);
+    let target = matches
+        .value("target")
+        .ok_or_else(|| usage("Missing --target".to_owned(), &TRANSLATE, path))?;
+    if matches.flag("alternatives") && !matches.flag("json") {
+        return Err(usage(
+            "--alternatives needs --json".to_owned(),
+            &TRANSLATE,
+            path,
+        ));
+    }
+    let translator = translator(matches, source, target)?;
+    let text = text(matches, input)?;
+    let translation = translator.translate(&text).await?;
+
+    if !matches.flag("json") {
+        write!(out, "{}", translation.text)?;
+        if !translation.text.ends_with('\n') {
+            writeln!(out)?;
+        }
+        return Ok(());
+    }
+
+    let source_lang = translation
+        .detected_source_lang
+        .clone()
+        .or_else(|| translator.source_lang().map(Language::to_string));
+    let mut json = json!({
+        "text": translation.text,
+        "source_text": translation.source_text,
+        "source_lang": source_lang,
+        "target_lang": translator.target_lang().to_string(),
+        "confidence": translation.confidence,
+    });
+    if matches.flag("alternatives") {
+        // Backends without lookups simply have no alternatives.
+        let alternatives = match translator.lookup(text.trim()).await {
+            Ok(lookup) => lookup
+                .alternatives
+                .iter()
+                .map(|alternatives| {
+                    let candidates: Vec<_> = alternatives
+                        .candidates
+                        .iter()
+                        .map(
+                            |candidate| json!({ "text": candidate.text, "score": candidate.score }),
+                        )
+                        .collect();
+                    json!({ "source": alternatives.source, "candidates": candidates })
+                })
+                .collect(),
+            Err(TranslationError::UnsupportedOperation(_)) => Vec::new(),
+            Err(error) => return Err(error.into()),
+        };
+        json["alternatives"] = Value::Array(alternatives);
+    }
+    writeln!(out, "{}", json)?;
+    Ok(())
+}
+
+async fn detect(matches: &Matches, input: &mut dyn Input, out: &mut dyn Write) -> CliResult {
+    let translator = detector(matches)?;
+    let detection = translator.detect(&text(matches, input)?).await?;
+    let name = Language::parse(&detection.language)
+        .ok()
+        .map(|language| language.name());
+
+    if matches.flag("json") {
+        let json = json!({
+            "language": detection.language,
+            "name": name,
+            "confidence": detection.confidence,
+            "offline": detection.offline,
+        });
+        writeln!(out, "{}", json)?;
+        return Ok(());
+    }
+
+    write!(out, "{}", detection.language)?;
+    if let Some(name) = name {
+        write!(out, "\t{}", name)?;
+    }
+    if let Some(confidence) = detection.confidence {
+        write!(out, "\t{:.2}", confidence)?;
+    }
+    writeln!(out)?;
+    Ok(())
+}
+
+async fn languages(matches: &Matches, out: &mut dyn Write) -> CliResult {
+    let translator = detector(matches)?;
+    let codes = translator.supported_languages().await?;
+    let languages = codes.iter().map(|code| {
+        let name = Language::parse(code).ok().map(|language| language.name());
+        (code, name)
+    });
+
+    if matches.flag("json") {
+        let json: Vec<Value> = languages
+            .map(|(code, name)| json!({ "code": code, "name": name }))
+            .collect();
+        writeln!(out, "{}", Value::Array(json))?;
+        return Ok(());
+    }
+    for (code, name) in languages {
+        writeln!(out, "{}\t{}", code, name.unwrap_or_default())?;
+    }
+    Ok(())
+}
+
+/// The text given as arguments, joined by spaces, or else read from `input`.
+fn text(matches: &Matches, input: &mut dyn Input) -> Result<String, CliError> {
+    if !matches.args.is_empty() {
+        return Ok(matches.args.join(" "));
+    }
+    let mut text = String::new();
+    input.read_to_string(&mut text)?;
+    Ok(text)
+}
+
+/// Creates a translator for the backend chosen on the command line.
+fn translator(matches: &Matches, source: &str, target: &str) -> Result<Translator, CliError> {
+    BackendConfig::from_matches(matches).translator(source, target)
+}
+
+/// Creates a translator for the commands that do not translate, which
+/// have no use for the cache and take no `--source`. The languages of
+/// offline dictionaries come from their file names instead.
+fn detector(matches: &Matches) -> Result<Translator, CliError> {
+    let backend = BackendConfig {
+        cache: None,
+        ..BackendConfig::from_matches(matches)
+    };
+    if backend.name != "offline" {
+        return backend.translator(AUTO_DETECT, "en");
+    }
+    let mut offline = OfflineBackend::new();
+    for path in &backend.dictionaries {
+        let path = Path::new(path);
+        let (source, target) = dictionary_languages(path)?;
+        offline = offline.with_dictionary(dictionary(path, source, target)?);
+    }
+    Ok(Translator::with_backend(AUTO_DETECT, "en", offline)?)
+}
+
+/// The backend chosen on the command line.
+#[derive(Debug, Clone)]
+struct BackendConfig {
+    name: String,
+    /// Dictionary files for the offline backend.
+    dictionaries: Vec<String>,
+    /// The translation cache file, unless caching is turned off.
+    cache: Option<PathBuf>,
+}
+
+impl BackendConfig {
+    fn from_matches(matches: &Matches) -> Self {
+        Self {
+            name: matches.value("backend").unwrap_or("google").to_owned(),
+            dictionaries: matches.values("dictionary").map(str::to_owned).collect(),
+            cache: if matches.flag("no-cache") {
+                None
+            } else {
+                cache::file(matches.value("cache"))
+            },
+        }
+    }
+
+    /// Creates a translator for this backend, configured from the
+    /// environment. Translations of the online backends are cached;
+    /// dictionary lookups are fast, and cache keys do not name the
+    /// dictionaries, so those of the offline backend are not.
+    fn translator(&self, source: &str, target: &str) -> Result<Translator, CliError> {
+        let env = |name| std::env::var(name).ok().filter(|value| !value.is_empty());
+        let backend = self.name.as_str();
+
+        let translator = match backend {
+            "google" => Translator::with_backend(source, target, GoogleGtxBackend::new()),
+            "deepl" => {
+                let auth_key = env("DEEPL_AUTH_KEY")
+                    .ok_or_else(|| CliError::Usage("Set DEEPL_AUTH_KEY to use DeepL".to_owned()))?;
+                Translator::with_backend(source, target, DeepLBackend::new(auth_key))
+            }
+            "libretranslate" => {
+                let base_url =
+                    env("LIBRETRANSLATE_URL").unwrap_or_else(|| "http://localhost:5000".to_owned());
+                let mut backend = LibreTranslateBackend::new(base_url);
+                if let Some(api_key) = env("LIBRETRANSLATE_API_KEY") {
+                    backend = backend.with_api_key(api_key);
+                }
+                Translator::with_backend(source, target, backend)
+            }
+            "llm" => {
+                let base_url =
+                    env("LLM_BASE_URL").unwrap_or_else(|| "http://localhost:11434/v1".to_owned());
+                let model = env("LLM_MODEL")
+                    .ok_or_else(|| CliError::Usage("Set LLM_MODEL to use an LLM".to_owned()))?;
+                let mut backend = LlmBackend::new(base_url, model);
+                if let Some(api_key) = env("LLM_API_KEY") {
+                    backend = backend.with_api_key(api_key);
+                }
+                Translator::with_backend(source, target, backend)
+            }
+            "offline" => {
+                if source == AUTO_DETECT {
+                    return Err(CliError::Usage(
+                        "The offline backend needs --source".to_owned(),
+                    ));
+                }
+                let (source_code, target_code) = (
+                    Language::parse(source)?.code(),
+                    Language::parse(target)?.code(),
+                );
+                let mut backend = OfflineBackend::new();
+                for path in &self.dictionaries {
+                    backend = backend.with_dictionary(dictionary(
+                        Path::new(path),
+                        source_code,
+                        target_code,
+                    )?);
+                }
+                Translator::with_backend(source, target, backend)
+            }
+            _ => {
+                return Err(CliError::Usage(format!(
+                    "Unknown backend: {} (expected one of {})",
+                    backend,
+                    BACKENDS.join(", ")
+                )))
+            }
+        };
+        let mut translator = translator?;
+        if let Some(path) = self.cache.as_ref().filter(|_| backend != "offline") {
+            translator = translator.with_cache(Arc::new(DiskCache::open(path)?));
+        }
+        Ok(translator)
+    }
+}
+
+/// The source and target languages a dictionary file is named after, as in
+/// `en-fr.tsv` or `eng-fra.tei`.
+fn dictionary_languages(path: &Path) -> Result<(&'static str, &'static str), CliError> {
+    let name = path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .unwrap_or("");
+    let stem = name.split('.').next().unwrap_or("");
+    let languages = stem.split_once('-').and_then(|(source, target)| {
+        let source = Language::parse(source).ok()?;
+        let target = Language::parse(target).ok()?;
+        Some((source.code(), target.code()))
+    });
+    languages.ok_or_else(|| {
+        CliError::Usage(format!(
+            "Cannot tell the languages of {}; name it after them, e.g. en-fr.tsv",
+            path.display()
+        ))
+    })
+}
+
+/// Loads a dictionary, choosing the format from the file extension.
+fn dictionary(path: &Path, source: &str, target: &str) -> Result<Dictionary, CliError> {
+    let extension = path.extension().and_then(|extension| extension.to_str());
+    let dictionary = match extension {
+        Some("ifo") => Dictionary::from_stardict(path, source, target),
+        Some("tei" | "xml") => Dictionary::from_freedict(path, source, target),
+        _ => Dictionary::from_tsv(path, source, target),
+    };
+    dictionary.map_err(|error| {
+        CliError::Io(io::Error::new(
+            error.kind(),
+            format!("{}: {}", path.display(), error),
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::{MockResponse, MockServer, TempDir};
+
+    async fn run_cli(args: &[&str], input: &str) -> Result<String, CliError> {
+        let args: Vec<_> = args.iter().map(|arg| arg.to_string()).collect();
+        let mut out = Vec::new();
+        run(&args, &mut input.as_bytes(), &mut out).await?;
+        Ok(String::from_utf8(out).unwrap())
+    }
+
+    #[tokio::test]
+    async fn test_translate_with_offline_dictionary() {
+        let dir = TempDir::new();
+        let path = dir.join("en-fr.tsv");
+        std::fs::write(&path, "hello\tbonjour\nworld\tmonde\n").unwrap();
+        let path = path.to_str().unwrap();
+
+        let args = [
+            "translate",
+            "-s",
+            "en",
+            "-tfr",
+            "--backend=offline",
+            "-d",
+            path,
+        ];
+        let output = run_cli(&args, "hello\n").await.unwrap();
+        assert_eq!(output, "bonjour\n");
+
+        let output = run_cli(&[&args[..], &["--json", "--", "world"]].concat(), "")
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json["text"], "monde");
+        assert_eq!(json["source_lang"], "en");
+        assert_eq!(json.get("alternatives"), None);
+
+        let output = run_cli(&[&args[..], &["--json", "-a", "world"]].concat(), "")
+            .await
+            .unwrap();
+        let json: Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json["alternatives"], json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_detect_and_languages_with_offline_dictionaries() {
+        let dir = TempDir::new();
+        let english = dir.join("en-fr.tsv");
+        std::fs::write(&english, "hello\tbonjour\nworld\tmonde\n").unwrap();
+        let german = dir.join("deu-eng.tsv");
+        std::fs::write(&german, "hallo\thello\nwelt\tworld\n").unwrap();
+        let dictionaries = [
+            "-b",
+            "offline",
+            "-d",
+            english.to_str().unwrap(),
+            "-d",
+            german.to_str().unwrap(),
+        ];
+
+        let args = [&["detect"], &dictionaries[..], &["hallo", "welt"]].concat();
+        assert_eq!(run_cli(&args, "").await.unwrap(), "de\tGerman\t1.00\n");
+
+        let args = [&["languages"], &dictionaries[..]].concat();
+        let output = run_cli(&args, "").await.unwrap();
+        assert_eq!(output, "de\tGerman\nen\tEnglish\nfr\tFrench\n");
+
+        let unnamed = dir.join("words.tsv");
+        std::fs::write(&unnamed, "hello\tbonjour\n").unwrap();
+        let args = ["detect", "-b", "offline", "-d", unnamed.to_str().unwrap()];
+        let error = run_cli(&args, "hello").await.unwrap_err();
+        assert!(error.to_string().starts_with("Cannot tell the languages"));
+        assert_eq!(error.exit_code(), 64);
+    }
+
+    #[tokio::test]
+    async fn test_translate_reuses_cached_translations() {
+        let server =
+            MockServer::start(|_| MockResponse::json(200, json!({ "translatedText": "Bonjour" })))
+                .await;
+        std::env::set_var("LIBRETRANSLATE_URL", server.url());
+        let dir = TempDir::new();
+        let cache = dir.join("translations.jsonl");
+        let cache = cache.to_str().unwrap();
+
+        let args = ["translate", "-b", "libretranslate", "-s", "en", "-t", "fr"];
+        let cached = [&args[..], &["--cache", cache, "hello"]].concat();
+        assert_eq!(run_cli(&cached, "").await.unwrap(), "Bonjour\n");
+        assert_eq!(run_cli(&cached, "").await.unwrap(), "Bonjour\n");
+        assert_eq!(server.requests().len(), 1);
+
+        let uncached = [&args[..], &["--no-cache", "hello"]].concat();
+        assert_eq!(run_cli(&uncached, "").await.unwrap(), "Bonjour\n");
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_usage_errors_and_exit_codes() {
+        let error = run_cli(&["translate", "hello"], "").await.unwrap_err();
+        assert!(error.to_string().starts_with("Missing --target"));
+        assert_eq!(error.exit_code(), 64);
+
+        let error = run_cli(&["frobnicate"], "").await.unwrap_err();
+        assert_eq!(error.exit_code(), 64);
+
+        let args = ["translate", "-t", "xx", "-b", "offline", "-s", "en"];
+        let error = run_cli(&args, "hello").await.unwrap_err();
+        assert_eq!(error.exit_code(), 65);
+
+        let args = ["translate", "-t", "fr", "-b", "offline", "-s", "en"];
+        let error = run_cli(&args, "hello").await.unwrap_err();
+        assert_eq!(error.exit_code(), 65, "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_help_and_version() {
+        let help = run_cli(&["translate", "--help"], "").await.unwrap();
+        assert!(help.contains("Usage: rustranslate translate [OPTIONS] [TEXT]..."));
+        assert!(help.contains("  -t, --target <LANG>"));
+
+        let help = run_cli(&[], "").await.unwrap();
+        assert!(help.contains("  languages    List the languages a backend supports"));
+        assert!(help.contains("DEEPL_AUTH_KEY"));
+
+        let version = run_cli(&["-V"], "").await.unwrap();
+        assert_eq!(
+            version,
+            format!("rustranslate {}\n", env!("CARGO_PKG_VERSION"))
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use super::editor::LineEditor;
+use super::{BackendConfig, CliError, CliResult, Input, Matches};
+use crate::{Translation, Translator, AUTO_DETECT};
+use std::io::{BufRead, BufReader, Write};
+
+const REPL_HELP: &str = "\
+Each line is translated with the current settings. Commands:
+  :from LANG      Translate from LANG, or detect it with :from auto
+  :to LANG        Translate to LANG
+  :swap           Swap the source and target languages
+  :backend NAME   Switch to another backend
+  :alt            Show alternatives for the last translation
+  :copy FILE      Write the last translation to FILE
+  :history        Show previous lines
+  :help           Show this help
+  :quit           Leave (or press Ctrl-D)";
+
+/// The state of an interactive session.
+#[derive(Debug)]
+struct Session {
+    source: String,
+    target: String,
+    backend: BackendConfig,
+    translator: Translator,
+    last: Option<Translation>,
+}
+
+impl Session {
+    fn prompt(&self) -> String {
+        format!("{}->{}> ", self.source, self.target)
+    }
+
+    /// Switches the settings, keeping the current ones if the translator
+    /// cannot be created with the new ones.
+    fn reconfigure(
+        &mut self,
+        source: &str,
+        target: &str,
+        backend: BackendConfig,
+    ) -> Result<(), CliError> {
+        self.translator = backend.translator(source, target)?;
+        self.source = source.to_owned();
+        self.target = target.to_owned();
+        self.backend = backend;
+        Ok(())
+    }
+
+    /// Handles one line of input, returning `false` when the session ends.
+    async fn handle(
+        &mut self,
+        line: &str,
+        editor: &LineEditor,
+        out: &mut dyn Write,
+    ) -> Result<bool, CliError> {
+        let line = line.trim();
+        let Some(command) = line.strip_prefix(':') else {
+            if !line.is_empty() {
+                let translation = self.translator.translate(line).await?;
+                writeln!(out, "{}", translation.text)?;
+                self.last = Some(translation);
+            }
+            return Ok(true);
+        };
+
+        let (command, argument) = match command.split_once(char::is_whitespace) {
+            Some((command, argument)) => (command, Some(argument.trim())),
+            None => (command, None),
+        };
+        let missing = |what: &str| CliError::Usage(format!("Usage: :{} {}", command, what));
+        let last = || {
+            self.last
+                .as_ref()
+                .ok_or_else(|| CliError::Usage("Nothing translated yet".to_owned()))
+        };
+
+        match command {
+            "from" => {
+                let source = argument.ok_or_else(|| missing("LANG"))?;
+                self.reconfigure(source, &self.target.clone(), self.backend.clone())?;
+            }
+            "to" => {
+                let target = argument.ok_or_else(|| missing("LANG"))?;
+                self.reconfigure(&self.source.clone(), target, self.backend.clone())?;
+            }
+            "swap" => {
+                if self.source == AUTO_DETECT {
+                    return Err(CliError::Usage(
+                        "Set a source language with :from before swapping".to_owned(),
+                    ));
+                }
+                let (source, target) = (self.target.clone(), self.source.clone());
+                self.reconfigure(&source, &target, self.backend.clone())?;
+            }
+            "backend" => {
+                let backend = BackendConfig {
+                    name: argument.ok_or_else(|| missing("NAME"))?.to_owned(),
+                    ..self.backend.clone()
+                };
+                let (source, target) = (self.source.clone(), self.target.clone());
+                self.reconfigure(&source, &target, backend)?;
+            }
+            "alt" => {
+                let lookup = self.translator.lookup(last()?.source_text.trim()).await?;
+                for alternatives in &lookup.alternatives {
+                    let candidates: Vec<_> = alternatives
+                        .candidates
+                        .iter()
+                        .map(|candidate| candidate.text.as_str())
+                        .collect();
+                    writeln!(out, "{}: {}", alternatives.source, candidates.join(", "))?;
+                }
+                if lookup.alternatives.is_empty() {
+                    writeln!(out, "No alternatives")?;
+                }
+            }
+            "copy" => {
+                let file = argument.ok_or_else(|| missing("FILE"))?;
+                std::fs::write(file, format!("{}\n", last()?.text))?;
+                writeln!(out, "Copied to {}", file)?;
+            }
+            "history" => {
+                for (index, line) in editor.history().iter().enumerate() {
+                    writeln!(out, "{:5}  {}", index + 1, line)?;
+                }
+            }
+            "help" | "h" | "?" => writeln!(out, "{}", REPL_HELP)?,
+            "quit" | "q" | "exit" => return Ok(false),
+            _ => {
+                return Err(CliError::Usage(format!(
+                    "Unknown command :{}, try :help",
+                    command
+                )))
+            }
+        }
+        Ok(true)
+    }
+}
+
+/// Runs `rustranslate repl`. Reads from the terminal with line editing when
+/// `input` is one, and line by line otherwise.
+pub(super) async fn run(
+    matches: &Matches,
+    input: &mut dyn Input,
+    out: &mut dyn Write,
+) -> CliResult {
+    let source = matches.value("source").unwrap_or(AUTO_DETECT).to_owned();
+    let target = matches.value("target").unwrap_or("en").to_owned();
+    let backend = BackendConfig::from_matches(matches);
+    let mut session = Session {
+        translator: backend.translator(&source, &target)?,
+        source,
+        target,
+        backend,
+        last: None,
+    };
+
+    let interactive = input.is_terminal();
+    let mut editor = LineEditor::new(if interactive {
+        LineEditor::default_path()
+    } else {
+        None
+    });
+    let mut input = BufReader::new(input);
+    if interactive {
+        writeln!(out, "Type :help for commands.")?;
+    }
+
+    loop {
+        let line = if interactive {
+            read_line(&mut editor, &session.prompt(), &mut input, out)?
+        } else {
+            editor.read_plain(&mut input)?
+        };
+        let Some(line) = line else {
+            return Ok(());
+        };
+        editor.add(&line);
+
+        match session.handle(&line, &editor, out).await {
+            Ok(true) => {}
+            Ok(false) => return Ok(()),
+            Err(error) => writeln!(out, "error: {}", error)?,
+        }
+    }
+}
+
+/// Reads a line typed at the terminal that `input` is.
+#[cfg(unix)]
+fn read_line(
+    editor: &mut LineEditor,
+    prompt: &str,
+    _input: &mut dyn BufRead,
+    _out: &mut dyn Write,
+) -> std::io::Result<Option<String>> {
+    editor.read_line(prompt)
+}
+
+#[cfg(not(unix))]
+fn read_line(
+    editor: &mut LineEditor,
+    prompt: &str,
+    input: &mut dyn BufRead,
+    out: &mut dyn Write,
+) -> std::io::Result<Option<String>> {
+    write!(out, "{}", prompt)?;
+    out.flush()?;
+    editor.read_plain(input)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::cli::run;
+    use crate::test_support::TempDir;
+
+    #[tokio::test]
+    async fn test_repl_session() {
+        let dir = TempDir::new();
+        let dictionary = dir.join("dictionary.tsv");
+        std::fs::write(&dictionary, "hello\tbonjour\nbonjour\thello\n").unwrap();
+        let copy = dir.join("copy.txt");
+
+        let args: Vec<_> = [
+            "repl",
+            "-s",
+            "en",
+            "-t",
+            "fr",
+            "-b",
+            "offline",
+            "-d",
+            dictionary.to_str().unwrap(),
+        ]
+        .map(str::to_owned)
+        .to_vec();
+        let input = format!(
+            "hello\n:copy {}\n:swap\nbonjour\n:from auto\n:to xx\n:alt\n:frobnicate\n:q\nhello\n",
+            copy.display()
+        );
+        let mut out = Vec::new();
+        run(&args, &mut input.as_bytes(), &mut out).await.unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<_> = out.lines().collect();
+        assert_eq!(
+            lines[..3],
+            ["bonjour", &format!("Copied to {}", copy.display()), "hello"]
+        );
+        assert_eq!(lines[3], "error: The offline backend needs --source");
+        assert_eq!(lines[4], "error: Unknown language tag: xx");
+        assert!(lines[5].starts_with("error: The backend does not support"));
+        assert_eq!(lines[6], "error: Unknown command :frobnicate, try :help");
+        assert_eq!(lines.len(), 7);
+        assert_eq!(std::fs::read_to_string(copy).unwrap(), "bonjour\n");
+    }
+}
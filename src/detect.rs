@@ -0,0 +1,277 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::OnceLock;
+
+/// The language a text was detected to be written in.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Detection {
+    pub language: String,
+    /// Confidence in the detection, from 0 to 1, if known.
+    pub confidence: Option<f64>,
+    /// Whether the local detector was used instead of the backend.
+    pub offline: bool,
+}
+
+impl Detection {
+    pub fn new(language: impl Into<String>, confidence: Option<f64>) -> Self {
+        Self {
+            language: language.into(),
+            confidence,
+            offline: false,
+        }
+    }
+}
+
+/// Sample text per Latin-script language, used to build trigram and
+/// common-word profiles.
+const LATIN_SAMPLES: &[(&str, &str)] = &[
+    (
+        "en",
+        "the and that have for not with you this but his from they say her she will one all \
+         would there their what out about who get which when make can like time just him know \
+         take people into year your good some could them see other than then now look only come \
+         its over think also back after use two how our work first well way even new want because \
+         any these give day most us is are was were has been the weather is nice today where are you",
+    ),
+    (
+        "fr",
+        "le la les de des du un une et est que qui dans pour pas sur au avec ce il elle ne se \
+         plus par son sont mais nous vous comme ou tout faire leur bien aussi cette été avoir \
+         être très quand même sans peut deux où après ces entre pourquoi je suis bonjour merci \
+         aujourd'hui il fait beau temps comment allez-vous c'est une belle journée",
+    ),
+    (
+        "de",
+        "der die das und ist nicht ein eine zu den von mit sich des auf für im dem auch es an \
+         werden aus er hat dass sie nach wird bei einer um am sind noch wie einem über einen so \
+         zum war haben nur oder aber vor zur bis mehr durch man sein wurde sei ich bin guten \
+         morgen danke heute ist schönes wetter wie geht es dir wo ist der bahnhof",
+    ),
+    (
+        "es",
+        "el la de que y en los se del las un por con no una su para es al lo como más pero sus \
+         le ya o fue este ha sí porque esta son entre cuando muy sin sobre también me hasta hay \
+         donde quien desde todo nos durante todos uno les ni contra otros ese eso ante ellos \
+         hola gracias buenos días hoy hace buen tiempo cómo estás dónde está la estación",
+    ),
+    (
+        "it",
+        "il di che è e la per un in non una sono mi ho lo ma ti le si ha con cosa se io come da \
+         questo qui bene hai sei anche mio solo era più lui gli fatto tutto della nel alla \
+         degli delle perché questa quando ciao grazie buongiorno oggi fa bel tempo come stai \
+         dove si trova la stazione",
+    ),
+    (
+        "pt",
+        "o a de que e do da em um para é com não uma os no se na por mais as dos como mas foi \
+         ao ele das tem à seu sua ou ser quando muito há nos já está eu também só pelo pela até \
+         isso ela entre era depois sem mesmo aos ter seus quem nas me esse eles você olá \
+         obrigado bom dia hoje está um dia bonito como você está onde fica a estação",
+    ),
+    (
+        "nl",
+        "de het een en van ik te dat die in is niet je hij zijn op aan met voor er om als maar \
+         dan ze wat mijn heb hebben bij nog ook wel naar kan zo kunnen geen uit waar goed moet \
+         hier worden door over goedemorgen dank je wel vandaag is het mooi weer hoe gaat het \
+         met jou waar is het station",
+    ),
+    (
+        "sv",
+        "och att det i som en på är av för med till den har de inte om ett han men var jag sig \
+         från vi så kan man när år säger hon under också efter eller nu sin där vid mot ska \
+         skulle kommer ut får finns vara hade alla andra mycket god morgon tack idag är det \
+         fint väder hur mår du var ligger stationen",
+    ),
+    (
+        "pl",
+        "nie to się na i w z jest że do co jak tak ale po od za o mnie mi już tylko czy tu ja \
+         jego być przez jej wszystko był może dla gdzie teraz kiedy ich bardzo jestem dzień \
+         dobry dziękuję dzisiaj jest ładna pogoda jak się masz gdzie jest dworzec",
+    ),
+    (
+        "tr",
+        "bir ve bu da de için ne ile çok ben sen o var daha gibi olarak kadar sonra en her ama \
+         değil mi ya şey olan diye bana ise nasıl neden ki çünkü hiç şimdi merhaba teşekkür \
+         ederim günaydın bugün hava çok güzel nasılsın istasyon nerede",
+    ),
+];
+
+/// Detects the language of `text` locally from its script and, for Latin
+/// script, from character trigrams and common words. Returns `None` if nothing matches.
+pub fn detect_offline(text: &str) -> Option<Detection> {
+    let language = detect_script(text).or_else(|| detect_latin(text));
+    language.map(|(language, confidence)| Detection {
+        language: language.to_owned(),
+        confidence: Some(confidence),
+        offline: true,
+    })
+}
+
+/// Picks a language from the dominant non-Latin script, if any.
+fn detect_script(text: &str) -> Option<(&'static str, f64)> {
+    let mut counts: HashMap<&'static str, usize> = HashMap::new();
+    let mut letters = 0;
+
+    for c in text.chars().filter(|c| c.is_alphabetic()) {
+        letters += 1;
+        let script = match c as u32 {
+            0x3040..=0x30FF => "ja",
+            0xAC00..=0xD7AF | 0x1100..=0x11FF | 0x3130..=0x318F => "ko",
+            0x4E00..=0x9FFF | 0x3400..=0x4DBF => "zh",
+            0x0400..=0x04FF => "ru",
+            0x0370..=0x03FF => "el",
+            0x0590..=0x05FF => "he",
+            0x0600..=0x06FF => "ar",
+            0x0E00..=0x0E7F => "th",
+            0x0900..=0x097F => "hi",
+            0x0980..=0x09FF => "bn",
+            0x0B80..=0x0BFF => "ta",
+            0x10A0..=0x10FF => "ka",
+            0x0530..=0x058F => "hy",
+            _ => continue,
+        };
+        *counts.entry(script).or_default() += 1;
+    }
+
+    // Japanese mixes kana with Han characters.
+    if counts.contains_key("ja") {
+        let japanese = counts.remove("ja").unwrap_or(0) + counts.remove("zh").unwrap_or(0);
+        counts.insert("ja", japanese);
+    }
+
+    let (script, count) = counts.into_iter().max_by_key(|(_, count)| *count)?;
+    if count * 2 < letters {
+        return None;
+    }
+
+    let language = match script {
+        "ru" if text.chars().any(|c| "іїєґ".contains(c)) => "uk",
+        "ar" if text.chars().any(|c| "پچژگ".contains(c)) => "fa",
+        script => script,
+    };
+    Some((language, count as f64 / letters as f64))
+}
+
+fn detect_latin(text: &str) -> Option<(&'static str, f64)> {
+    let trigrams = trigrams(text);
+    let words: Vec<String> = words(text).map(str::to_lowercase).collect();
+    if words.is_empty() {
+        return None;
+    }
+
+    let mut scores: Vec<(&'static str, f64)> = profiles()
+        .iter()
+        .map(|profile| {
+            let known = words
+                .iter()
+                .filter(|word| profile.words.contains(word.as_str()))
+                .count();
+            let word_score = known as f64 / words.len() as f64;
+            let score = (cosine(&trigrams, &profile.trigrams) + word_score) / 2.0;
+            (profile.language, score)
+        })
+        .collect();
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let (language, best) = scores[0];
+    if best <= 0.0 {
+        return None;
+    }
+    let runner_up = scores.get(1).map_or(0.0, |(_, score)| *score);
+    Some((language, best / (best + runner_up)))
+}
+
+struct Profile {
+    language: &'static str,
+    trigrams: HashMap<String, f64>,
+    words: HashSet<&'static str>,
+}
+
+fn profiles() -> &'static [Profile] {
+    static PROFILES: OnceLock<Vec<Profile>> = OnceLock::new();
+    PROFILES.get_or_init(|| {
+        LATIN_SAMPLES
+            .iter()
+            .map(|(language, sample)| Profile {
+                language,
+                trigrams: trigrams(sample),
+                words: words(sample).collect(),
+            })
+            .collect()
+    })
+}
+
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphabetic() && c != '\'')
+        .filter(|word| !word.is_empty())
+}
+
+/// Counts character trigrams of each word padded with spaces.
+fn trigrams(text: &str) -> HashMap<String, f64> {
+    let mut counts = HashMap::new();
+    for word in words(text) {
+        let chars: Vec<char> = format!(" {} ", word.to_lowercase()).chars().collect();
+        for window in chars.windows(3) {
+            *counts.entry(window.iter().collect()).or_insert(0.0) += 1.0;
+        }
+    }
+    counts
+}
+
+fn cosine(a: &HashMap<String, f64>, b: &HashMap<String, f64>) -> f64 {
+    let dot: f64 = a
+        .iter()
+        .filter_map(|(trigram, count)| b.get(trigram).map(|other| count * other))
+        .sum();
+    let norm = |counts: &HashMap<String, f64>| counts.values().map(|c| c * c).sum::<f64>().sqrt();
+    let norms = norm(a) * norm(b);
+    if norms == 0.0 {
+        0.0
+    } else {
+        dot / norms
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn language(text: &str) -> String {
+        detect_offline(text).unwrap().language
+    }
+
+    #[test]
+    fn test_detect_script() {
+        assert_eq!(language("こんにちは世界"), "ja");
+        assert_eq!(language("你好世界"), "zh");
+        assert_eq!(language("안녕하세요"), "ko");
+        assert_eq!(language("Привет, как дела?"), "ru");
+        assert_eq!(language("Привіт, як справи?"), "uk");
+        assert_eq!(language("Γειά σου κόσμε"), "el");
+    }
+
+    #[test]
+    fn test_detect_latin() {
+        // None of these sentences appear in the samples.
+        assert_eq!(
+            language("They would like to know what you think about it."),
+            "en"
+        );
+        assert_eq!(
+            language("Nous pensons qu'elle est très contente de son travail."),
+            "fr"
+        );
+        assert_eq!(
+            language("Ich habe nicht gewusst, dass sie heute nach Hause kommt."),
+            "de"
+        );
+        assert_eq!(
+            language("Creo que ellos no saben lo que pasó con su hermano."),
+            "es"
+        );
+        assert_eq!(
+            language("Non so se lui ha già mangiato qualcosa questa sera."),
+            "it"
+        );
+        assert!(detect_offline("1234 !!").is_none());
+    }
+}
@@ -0,0 +1,369 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+// Responses are only interpreted by the HTTP backends.
+#[cfg(any(
+    feature = "google",
+    feature = "deepl",
+    feature = "libretranslate",
+    feature = "llm"
+))]
+use {
+    reqwest::header::{HeaderMap, RETRY_AFTER},
+    reqwest::StatusCode,
+    std::time::SystemTime,
+};
+
+/// Longest response body kept on an error, in characters.
+#[cfg(any(
+    feature = "google",
+    feature = "deepl",
+    feature = "libretranslate",
+    feature = "llm"
+))]
+const SNIPPET_LEN: usize = 200;
+
+/// Why a translation failed.
+///
+/// The errors of the HTTP client, a `reqwest::Error` with the `http`
+/// feature, are kept as boxed sources so that the type is the same
+/// whichever backends are enabled.
+#[derive(Debug)]
+pub enum TranslationError {
+    /// The request timed out before a response arrived, or the response
+    /// stalled for longer than the read timeout.
+    Timeout(Option<Box<dyn Error + Send + Sync>>),
+    /// The host could not be resolved or connected to.
+    Connect(Box<dyn Error + Send + Sync>),
+    /// The request failed for another reason before a response arrived.
+    RequestFailed(Option<Box<dyn Error + Send + Sync>>),
+    /// The HTTP client could not be built, e.g. from an invalid
+    /// certificate.
+    ClientSetupFailed(Box<dyn Error + Send + Sync>),
+    /// The server rejected the credentials (401 or 403).
+    AuthorizationFailed(HttpError),
+    /// The account's translation quota is used up.
+    QuotaExceeded(HttpError),
+    /// The server is rate limiting requests (429).
+    RateLimited(HttpError),
+    /// The server failed to handle the request (5xx).
+    ServerError(HttpError),
+    /// The server answered with a captcha or "unusual traffic" page
+    /// instead of a translation.
+    Captcha(HttpError),
+    /// The server answered with another error status.
+    Http(HttpError),
+    /// The response body is not in the expected format.
+    ResponseParsingFailed(ParseError),
+    NoTranslationFound(String),
+    /// There was no text to translate.
+    EmptyInput,
+    InvalidLanguage(String),
+    UnsupportedLanguagePair(String, String),
+    UnsupportedOperation(&'static str),
+}
+
+impl TranslationError {
+    /// Builds the error for a response with an unsuccessful `status`.
+    #[cfg(any(
+        feature = "google",
+        feature = "deepl",
+        feature = "libretranslate",
+        feature = "llm"
+    ))]
+    pub(crate) fn from_status(status: StatusCode, headers: &HeaderMap, body: &str) -> Self {
+        let error = HttpError {
+            status: status.as_u16(),
+            body: snippet(body),
+            retry_after: retry_after(headers),
+        };
+
+        match status {
+            _ if is_captcha(body) => TranslationError::Captcha(error),
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => {
+                TranslationError::AuthorizationFailed(error)
+            }
+            StatusCode::TOO_MANY_REQUESTS => TranslationError::RateLimited(error),
+            status if status.is_server_error() => TranslationError::ServerError(error),
+            _ => TranslationError::Http(error),
+        }
+    }
+
+    /// Builds the error for a successful response whose `body` could not be
+    /// used, optionally caused by a JSON syntax error.
+    #[cfg(any(
+        feature = "google",
+        feature = "deepl",
+        feature = "libretranslate",
+        feature = "llm"
+    ))]
+    pub(crate) fn invalid_response(body: &str, source: Option<serde_json::Error>) -> Self {
+        TranslationError::ResponseParsingFailed(ParseError {
+            body: snippet(body),
+            source,
+        })
+    }
+
+    /// Whether sending the same request again may succeed: timeouts,
+    /// connection failures, rate limiting and server errors.
+    pub fn is_retryable(&self) -> bool {
+        matches!(
+            self,
+            TranslationError::Timeout(_)
+                | TranslationError::Connect(_)
+                | TranslationError::RequestFailed(_)
+                | TranslationError::RateLimited(_)
+                | TranslationError::ServerError(_)
+        )
+    }
+
+    /// The HTTP error response, if the server answered with one.
+    pub fn http(&self) -> Option<&HttpError> {
+        match self {
+            TranslationError::AuthorizationFailed(error)
+            | TranslationError::QuotaExceeded(error)
+            | TranslationError::RateLimited(error)
+            | TranslationError::ServerError(error)
+            | TranslationError::Captcha(error)
+            | TranslationError::Http(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// The HTTP status code of the error response.
+    pub fn status(&self) -> Option<u16> {
+        self.http().map(|error| error.status)
+    }
+
+    /// How long the server asked to wait before retrying.
+    pub fn retry_after(&self) -> Option<Duration> {
+        self.http().and_then(|error| error.retry_after)
+    }
+}
+
+#[cfg(feature = "http")]
+impl From<reqwest::Error> for TranslationError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            TranslationError::Timeout(Some(error.into()))
+        } else if error.is_connect() {
+            TranslationError::Connect(error.into())
+        } else {
+            TranslationError::RequestFailed(Some(error.into()))
+        }
+    }
+}
+
+impl fmt::Display for TranslationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TranslationError::Timeout(_) => write!(f, "Translation request timed out"),
+            TranslationError::Connect(_) => {
+                write!(f, "Failed to connect to the translation service")
+            }
+            TranslationError::RequestFailed(_) => write!(f, "Failed to send translation request"),
+            TranslationError::ClientSetupFailed(_) => write!(f, "Failed to set up the HTTP client"),
+            TranslationError::AuthorizationFailed(error) => {
+                write!(f, "Authorization failed, check the API key ({})", error)
+            }
+            TranslationError::QuotaExceeded(error) => {
+                write!(f, "Translation quota exceeded ({})", error)
+            }
+            TranslationError::RateLimited(error) => {
+                write!(f, "Too many requests, try again later ({})", error)
+            }
+            TranslationError::ServerError(error) => {
+                write!(f, "The translation service failed ({})", error)
+            }
+            TranslationError::Captcha(error) => write!(
+                f,
+                "The translation service asked for a captcha after unusual traffic ({})",
+                error
+            ),
+            TranslationError::Http(error) => write!(f, "Translation request rejected ({})", error),
+            TranslationError::ResponseParsingFailed(error) => {
+                write!(f, "Failed to parse response body: {}", error)
+            }
+            TranslationError::NoTranslationFound(word) => {
+                write!(f, "No translation found for: {}", word)
+            }
+            TranslationError::EmptyInput => write!(f, "Nothing to translate"),
+            TranslationError::InvalidLanguage(tag) => write!(f, "Unknown language tag: {}", tag),
+            TranslationError::UnsupportedLanguagePair(source, target) => {
+                write!(f, "Unsupported language pair: {} -> {}", source, target)
+            }
+            TranslationError::UnsupportedOperation(operation) => {
+                write!(f, "The backend does not support {}", operation)
+            }
+        }
+    }
+}
+
+impl Error for TranslationError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            TranslationError::Timeout(Some(error))
+            | TranslationError::Connect(error)
+            | TranslationError::RequestFailed(Some(error))
+            | TranslationError::ClientSetupFailed(error) => Some(error.as_ref()),
+            TranslationError::ResponseParsingFailed(error) => error.source(),
+            _ => None,
+        }
+    }
+}
+
+/// An unsuccessful HTTP response.
+#[derive(Debug, Clone)]
+pub struct HttpError {
+    /// The status code, e.g. 429.
+    pub status: u16,
+    /// The start of the response body.
+    pub body: String,
+    /// The `Retry-After` delay sent with the response.
+    pub retry_after: Option<Duration>,
+}
+
+impl fmt::Display for HttpError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "HTTP {}", self.status)?;
+        if let Some(retry_after) = self.retry_after {
+            write!(f, ", retry after {}s", retry_after.as_secs())?;
+        }
+        Ok(())
+    }
+}
+
+/// A response body that could not be parsed.
+#[derive(Debug)]
+pub struct ParseError {
+    /// The start of the response body.
+    pub body: String,
+    source: Option<serde_json::Error>,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.source {
+            Some(source) => write!(f, "{}", source),
+            None => write!(f, "unexpected response {:?}", self.body),
+        }
+    }
+}
+
+impl Error for ParseError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        self.source
+            .as_ref()
+            .map(|error| error as &(dyn Error + 'static))
+    }
+}
+
+#[cfg(any(
+    feature = "google",
+    feature = "deepl",
+    feature = "libretranslate",
+    feature = "llm"
+))]
+fn snippet(body: &str) -> String {
+    match body.char_indices().nth(SNIPPET_LEN) {
+        Some((end, _)) => format!("{}...", &body[..end]),
+        None => body.to_owned(),
+    }
+}
+
+/// Parses a `Retry-After` header given either in seconds or as an HTTP date.
+#[cfg(any(
+    feature = "google",
+    feature = "deepl",
+    feature = "libretranslate",
+    feature = "llm"
+))]
+fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get(RETRY_AFTER)?.to_str().ok()?.trim();
+    if let Ok(seconds) = value.parse() {
+        return Some(Duration::from_secs(seconds));
+    }
+
+    let date = httpdate::parse_http_date(value).ok()?;
+    Some(
+        date.duration_since(SystemTime::now())
+            .unwrap_or(Duration::ZERO),
+    )
+}
+
+/// Whether `body` is an HTML page blocking automated traffic, as Google
+/// serves once it flags a client.
+#[cfg(any(
+    feature = "google",
+    feature = "deepl",
+    feature = "libretranslate",
+    feature = "llm"
+))]
+pub(crate) fn is_captcha(body: &str) -> bool {
+    let start = body.trim_start().get(..15).unwrap_or_default();
+    if !start.eq_ignore_ascii_case("<!doctype html>") && !start.starts_with("<html") {
+        return false;
+    }
+
+    let body = body.to_lowercase();
+    body.contains("unusual traffic") || body.contains("captcha")
+}
+
+#[cfg(test)]
+#[cfg(any(
+    feature = "google",
+    feature = "deepl",
+    feature = "libretranslate",
+    feature = "llm"
+))]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    #[test]
+    fn test_from_status() {
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_static("30"));
+
+        let error = TranslationError::from_status(StatusCode::TOO_MANY_REQUESTS, &headers, "");
+        assert!(matches!(error, TranslationError::RateLimited(_)));
+        assert!(error.is_retryable());
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(30)));
+
+        let error = TranslationError::from_status(StatusCode::BAD_GATEWAY, &HeaderMap::new(), "");
+        assert!(matches!(error, TranslationError::ServerError(_)));
+        assert_eq!(error.status(), Some(502));
+
+        let page = "<html><body>Our systems have detected unusual traffic</body></html>";
+        let error = TranslationError::from_status(StatusCode::TOO_MANY_REQUESTS, &headers, page);
+        assert!(matches!(error, TranslationError::Captcha(_)));
+        assert!(!error.is_retryable());
+
+        let error =
+            TranslationError::from_status(StatusCode::NOT_FOUND, &headers, &"x".repeat(500));
+        assert_eq!(error.http().unwrap().body.len(), SNIPPET_LEN + 3);
+    }
+
+    #[test]
+    fn test_retry_after_date() {
+        let date = httpdate::fmt_http_date(SystemTime::now() + Duration::from_secs(120));
+        let mut headers = HeaderMap::new();
+        headers.insert(RETRY_AFTER, HeaderValue::from_str(&date).unwrap());
+
+        let delay = retry_after(&headers).unwrap();
+        assert!(delay > Duration::from_secs(100) && delay <= Duration::from_secs(120));
+    }
+
+    #[test]
+    fn test_parse_error_source() {
+        let source = serde_json::from_str::<serde_json::Value>("{").unwrap_err();
+        let error = TranslationError::invalid_response("{", Some(source));
+        assert!(error.source().is_some());
+        assert!(error
+            .to_string()
+            .starts_with("Failed to parse response body: EOF"));
+
+        let error = TranslationError::NoTranslationFound("hello".to_owned());
+        assert_eq!(error.to_string(), "No translation found for: hello");
+    }
+}
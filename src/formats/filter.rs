@@ -0,0 +1,91 @@
+//! Choosing keys of structured files by glob patterns.
+
+/// Selects keys, given as their path of names from the root, with
+/// include and exclude globs. Without include globs every key that is not
+/// excluded is selected.
+///
+/// Globs are written with `.` between names, as in `home.title`. Within a
+/// name, `*` matches any characters and `?` one character; a `**` name
+/// matches any number of names, so `**.label` selects every `label` key.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct KeyFilter {
+    include: Vec<String>,
+    exclude: Vec<String>,
+}
+
+impl KeyFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Selects only keys matching `glob` or another include glob.
+    pub fn with_include(mut self, glob: impl Into<String>) -> Self {
+        self.include.push(glob.into());
+        self
+    }
+
+    /// Leaves out keys matching `glob`, even when they are included.
+    pub fn with_exclude(mut self, glob: impl Into<String>) -> Self {
+        self.exclude.push(glob.into());
+        self
+    }
+
+    pub fn matches<S: AsRef<str>>(&self, path: &[S]) -> bool {
+        let path: Vec<&str> = path.iter().map(AsRef::as_ref).collect();
+        let matching = |glob: &String| {
+            let glob: Vec<&str> = glob.split('.').collect();
+            matches_path(&glob, &path)
+        };
+        (self.include.is_empty() || self.include.iter().any(matching))
+            && !self.exclude.iter().any(matching)
+    }
+}
+
+fn matches_path(glob: &[&str], path: &[&str]) -> bool {
+    match (glob.split_first(), path.split_first()) {
+        (None, _) => path.is_empty(),
+        (Some((&"**", rest)), _) => (0..=path.len()).any(|skip| matches_path(rest, &path[skip..])),
+        (Some(_), None) => false,
+        (Some((pattern, glob)), Some((name, path))) => {
+            let pattern: Vec<char> = pattern.chars().collect();
+            let name: Vec<char> = name.chars().collect();
+            matches_name(&pattern, &name) && matches_path(glob, path)
+        }
+    }
+}
+
+fn matches_name(pattern: &[char], name: &[char]) -> bool {
+    match pattern.split_first() {
+        None => name.is_empty(),
+        Some(('*', rest)) => (0..=name.len()).any(|skip| matches_name(rest, &name[skip..])),
+        Some(('?', rest)) => !name.is_empty() && matches_name(rest, &name[1..]),
+        Some((c, rest)) => name.first() == Some(c) && matches_name(rest, &name[1..]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_key_filter() {
+        let all = KeyFilter::new();
+        assert!(all.matches(&["home", "title"]));
+
+        let filter = KeyFilter::new()
+            .with_include("home.*")
+            .with_include("**.label")
+            .with_exclude("home.url?");
+        assert!(filter.matches(&["home", "title"]));
+        assert!(!filter.matches(&["home", "urls"]));
+        assert!(filter.matches(&["home", "url"]));
+        assert!(!filter.matches(&["home", "hero", "title"]));
+        assert!(filter.matches(&["label"]));
+        assert!(filter.matches(&["form", "name", "label"]));
+        assert!(!filter.matches(&["form", "name", "labels"]));
+
+        let filter = KeyFilter::new().with_exclude("**.é?");
+        assert!(!filter.matches(&["a", "éü"]));
+        assert!(filter.matches(&["a", "é"]));
+    }
+}
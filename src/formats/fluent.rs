@@ -0,0 +1,793 @@
+//! Project Fluent resources: `.ftl` files.
+//!
+//! [`Resource::parse`] reads messages and terms with their attributes, and
+//! [`Resource`]'s `Display` writes the entries it did not change, and every
+//! comment, exactly as they were read. Changed entries are written in the
+//! layout of Fluent's own serializer.
+
+use super::{
+    keep_spacing, line_ending, mask, plural_categories, translate_texts, unmask, Piece, SyntaxError,
+};
+use crate::{TranslationError, Translator};
+use std::fmt;
+
+/// CLDR plural categories, which select expressions over numbers use as
+/// variant keys.
+const CATEGORIES: &[&str] = &["zero", "one", "two", "few", "many", "other"];
+
+/// A message, or a term when its id starts with `-`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    /// The id, without the `-` of terms.
+    pub id: String,
+    pub term: bool,
+    pub value: Option<Pattern>,
+    pub attributes: Vec<Attribute>,
+}
+
+impl Message {
+    /// The patterns a translation changes: the value, and the attributes of
+    /// messages. Term attributes are only seen by selectors and stay as
+    /// they are.
+    fn patterns_mut(&mut self) -> impl Iterator<Item = &mut Pattern> {
+        let attributes = match self.term {
+            true => &mut [],
+            false => self.attributes.as_mut_slice(),
+        };
+        self.value
+            .iter_mut()
+            .chain(attributes.iter_mut().map(|attribute| &mut attribute.value))
+    }
+
+    fn write(&self, out: &mut String) {
+        if self.term {
+            out.push('-');
+        }
+        out.push_str(&self.id);
+        out.push_str(" =");
+        if let Some(value) = &self.value {
+            value.write(out);
+        }
+        for attribute in &self.attributes {
+            out.push_str(&format!("\n    .{} =", attribute.id));
+            attribute.value.write(out);
+        }
+        out.push('\n');
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Attribute {
+    pub id: String,
+    pub value: Pattern,
+}
+
+/// The text of a message, term, attribute or variant.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Pattern {
+    pub elements: Vec<PatternElement>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternElement {
+    Text(String),
+    Placeable(Placeable),
+}
+
+/// What appears between `{` and `}` in a pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Placeable {
+    /// An expression such as `{ $user }` or `{ -brand-name }`, kept as
+    /// written, braces included.
+    Expression(String),
+    /// A select expression, with its selector as written, e.g. `$count`.
+    Select {
+        selector: String,
+        variants: Vec<Variant>,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub key: String,
+    /// Whether this is the `*` default variant.
+    pub default: bool,
+    pub value: Pattern,
+}
+
+impl Pattern {
+    /// Calls `f` with this pattern and then with the patterns of its
+    /// variants, depth first.
+    fn visit_mut(&mut self, f: &mut dyn FnMut(&mut Pattern)) {
+        f(self);
+        for element in &mut self.elements {
+            if let PatternElement::Placeable(Placeable::Select { variants, .. }) = element {
+                for variant in variants {
+                    variant.value.visit_mut(f);
+                }
+            }
+        }
+    }
+
+    fn has_text(&self) -> bool {
+        self.elements.iter().any(|element| match element {
+            PatternElement::Text(text) => !text.trim().is_empty(),
+            PatternElement::Placeable(_) => false,
+        })
+    }
+
+    /// The text runs and placeables, for [`mask`] and [`unmask`].
+    fn pieces(&self) -> Vec<Piece<&Placeable>> {
+        self.elements
+            .iter()
+            .map(|element| match element {
+                PatternElement::Text(text) => Piece::Text(text.clone()),
+                PatternElement::Placeable(placeable) => Piece::Kept(placeable),
+            })
+            .collect()
+    }
+
+    /// Replaces the text with the translation of its masked text, putting
+    /// the placeables back where their markers went. Leaves the pattern as
+    /// it is and returns `false` unless every marker was kept exactly once.
+    fn unmask(&mut self, translation: &str) -> bool {
+        let Some(pieces) = unmask(&self.pieces(), translation, |_| true) else {
+            return false;
+        };
+        let elements = pieces
+            .into_iter()
+            .map(|piece| match piece {
+                Piece::Text(text) => PatternElement::Text(text),
+                Piece::Kept(placeable) => PatternElement::Placeable(placeable.clone()),
+            })
+            .collect();
+        self.elements = elements;
+        true
+    }
+
+    /// Adds a variant for each of `categories` that select expressions
+    /// over numbers lack, copying the default variant.
+    fn add_plural_variants(&mut self, categories: &[&str]) {
+        for element in &mut self.elements {
+            let PatternElement::Placeable(Placeable::Select { selector, variants }) = element
+            else {
+                continue;
+            };
+            let numeric = selector.starts_with('$') || selector.starts_with("NUMBER(");
+            let keys_are_plural = variants.iter().all(|variant| {
+                CATEGORIES.contains(&variant.key.as_str()) || variant.key.parse::<f64>().is_ok()
+            });
+            if !numeric || !keys_are_plural {
+                continue;
+            }
+            let Some(default) = variants.iter().find(|variant| variant.default).cloned() else {
+                continue;
+            };
+            let rank = |key: &str| CATEGORIES.iter().position(|category| *category == key);
+            for category in categories {
+                if variants.iter().any(|variant| variant.key == *category) {
+                    continue;
+                }
+                // Before the first category that CLDR lists later.
+                let position = variants
+                    .iter()
+                    .position(|variant| rank(&variant.key) > rank(category))
+                    .unwrap_or(variants.len());
+                let variant = Variant {
+                    key: (*category).to_owned(),
+                    default: false,
+                    value: default.value.clone(),
+                };
+                variants.insert(position, variant);
+            }
+        }
+    }
+
+    /// Writes ` value`, or the value indented on the following lines when
+    /// it spans several.
+    fn write(&self, out: &mut String) {
+        let mut content = String::new();
+        let mut multiline = false;
+        for element in &self.elements {
+            match element {
+                PatternElement::Text(text) => {
+                    multiline |= text.contains('\n');
+                    write_text(text, &mut content);
+                }
+                PatternElement::Placeable(Placeable::Expression(expression)) => {
+                    content.push_str(expression);
+                }
+                PatternElement::Placeable(Placeable::Select { selector, variants }) => {
+                    multiline = true;
+                    content.push_str(&format!("{{ {} ->", selector));
+                    for variant in variants {
+                        let marker = if variant.default { "   *" } else { "    " };
+                        let mut value = String::new();
+                        variant.value.write(&mut value);
+                        content.push_str(&format!(
+                            "\n{}[{}]{}",
+                            marker,
+                            variant.key,
+                            indent(&value)
+                        ));
+                    }
+                    content.push_str("\n}");
+                }
+            }
+        }
+        match multiline {
+            true => out.push_str(&format!("\n    {}", indent(&content))),
+            false => out.push_str(&format!(" {}", content)),
+        }
+    }
+}
+
+/// Writes text, quoting the characters that would otherwise start a
+/// placeable, or a variant or attribute at the start of a line.
+fn write_text(text: &str, out: &mut String) {
+    let mut line_start = out.is_empty();
+    for c in text.chars() {
+        match c {
+            '{' | '}' => out.push_str(&format!("{{ \"{}\" }}", c)),
+            '[' | '*' | '.' if line_start => out.push_str(&format!("{{ \"{}\" }}", c)),
+            c => out.push(c),
+        }
+        line_start = c == '\n';
+    }
+}
+
+/// Indents every line but the first by four spaces, leaving blank lines
+/// empty.
+fn indent(text: &str) -> String {
+    text.split('\n')
+        .enumerate()
+        .map(|(index, line)| match (index, line.is_empty()) {
+            (0, _) | (_, true) => line.to_owned(),
+            _ => format!("    {}", line),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// A run of lines of a resource: an entry, comments or blank lines.
+#[derive(Debug, Clone)]
+struct Block {
+    raw: String,
+    original: Option<Message>,
+    message: Option<Message>,
+}
+
+/// A parsed `.ftl` file.
+#[derive(Debug, Clone)]
+pub struct Resource {
+    blocks: Vec<Block>,
+    /// The line ending used by the file.
+    newline: &'static str,
+}
+
+impl Resource {
+    pub fn parse(text: &str) -> Result<Self, SyntaxError> {
+        let newline = line_ending(text);
+
+        // Byte ranges of the lines of each block, with whether it is an entry.
+        let mut spans: Vec<(usize, usize, usize, bool)> = Vec::new();
+        let mut offset = 0;
+        let mut blank_start = None;
+        for (index, line) in text.split_inclusive('\n').enumerate() {
+            let content = line.trim_end_matches(['\n', '\r']);
+            let start = offset;
+            offset += line.len();
+            if content.trim().is_empty() {
+                blank_start.get_or_insert((start, index));
+                continue;
+            }
+            let continues = content.starts_with([' ', '[', '*', '.', '}']);
+            match spans.last_mut() {
+                // Blank lines within an entry belong to it.
+                Some((_, end, _, true)) if continues => *end = offset,
+                Some((_, end, _, false)) if content.starts_with('#') && blank_start.is_none() => {
+                    *end = offset
+                }
+                _ => {
+                    if let Some((blank, line)) = blank_start {
+                        spans.push((blank, start, line, false));
+                    }
+                    spans.push((start, offset, index, !content.starts_with('#')));
+                }
+            }
+            blank_start = None;
+        }
+        if let Some((blank, line)) = blank_start {
+            spans.push((blank, text.len(), line, false));
+        }
+
+        let mut blocks = Vec::with_capacity(spans.len());
+        for (start, end, line, is_entry) in spans {
+            let raw = &text[start..end];
+            let message = match is_entry {
+                true => {
+                    let text = raw.replace("\r\n", "\n");
+                    let mut parser = EntryParser {
+                        text: &text,
+                        position: 0,
+                        first_line: line + 1,
+                    };
+                    Some(parser.message()?)
+                }
+                false => None,
+            };
+            blocks.push(Block {
+                raw: raw.to_owned(),
+                original: message.clone(),
+                message,
+            });
+        }
+        Ok(Self { blocks, newline })
+    }
+
+    /// The messages and terms, in the order they appear.
+    pub fn messages(&self) -> impl Iterator<Item = &Message> {
+        self.blocks
+            .iter()
+            .filter_map(|block| block.message.as_ref())
+    }
+
+    pub fn messages_mut(&mut self) -> impl Iterator<Item = &mut Message> {
+        self.blocks
+            .iter_mut()
+            .filter_map(|block| block.message.as_mut())
+    }
+
+    /// The message, or the term when `id` starts with `-`, named `id`.
+    pub fn get(&self, id: &str) -> Option<&Message> {
+        let (term, id) = match id.strip_prefix('-') {
+            Some(id) => (true, id),
+            None => (false, id),
+        };
+        self.messages()
+            .find(|message| message.term == term && message.id == id)
+    }
+
+    /// Translates the text of every message and term with `translator`,
+    /// keeping placeables in place, and adds the variants that the plural
+    /// categories of the target language need to select expressions over
+    /// numbers. Returns the number of messages and terms translated.
+    ///
+    /// Each pattern is translated as a whole, with its placeables masked;
+    /// when the backend loses one, its text elements are translated one by
+    /// one instead.
+    pub async fn translate(&mut self, translator: &Translator) -> Result<usize, TranslationError> {
+        let mut texts = Vec::new();
+        let mut count = 0;
+        for message in self.messages_mut() {
+            let before = texts.len();
+            for pattern in message.patterns_mut() {
+                pattern.visit_mut(&mut |pattern| {
+                    if pattern.has_text() {
+                        texts.push((mask(&pattern.pieces()), None));
+                    }
+                });
+            }
+            count += usize::from(texts.len() > before);
+        }
+        let mut translations = translate_texts(translator, &texts).await?.into_iter();
+
+        // The patterns whose placeables were lost, by the order they are
+        // visited in, and their text elements.
+        let mut failed = Vec::new();
+        let mut fallback = Vec::new();
+        self.visit_mut(&mut |pattern| {
+            let lost = pattern.has_text()
+                && !pattern.unmask(&translations.next().expect("one translation per text"));
+            if lost {
+                for element in &pattern.elements {
+                    if let PatternElement::Text(text) = element {
+                        fallback.push((text.clone(), None));
+                    }
+                }
+            }
+            failed.push(lost);
+        });
+        if !fallback.is_empty() {
+            let mut translated = translate_texts(translator, &fallback).await?.into_iter();
+            let mut failed = failed.into_iter();
+            self.visit_mut(&mut |pattern| {
+                if !failed.next().unwrap_or(false) {
+                    return;
+                }
+                for element in &mut pattern.elements {
+                    if let PatternElement::Text(text) = element {
+                        let translation = translated.next().expect("one translation per text");
+                        *text = keep_spacing(text, &translation);
+                    }
+                }
+            });
+        }
+
+        let categories = plural_categories(translator.target_lang());
+        self.visit_mut(&mut |pattern| pattern.add_plural_variants(categories));
+        Ok(count)
+    }
+
+    fn visit_mut(&mut self, f: &mut dyn FnMut(&mut Pattern)) {
+        for message in self.messages_mut() {
+            for pattern in message.patterns_mut() {
+                pattern.visit_mut(f);
+            }
+        }
+    }
+}
+
+impl fmt::Display for Resource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for block in &self.blocks {
+            match &block.message {
+                Some(message) if block.original.as_ref() != Some(message) => {
+                    let mut text = String::new();
+                    message.write(&mut text);
+                    // Keep a missing newline at the end of the file missing.
+                    if !block.raw.ends_with('\n') {
+                        text.pop();
+                    }
+                    f.write_str(&text.replace('\n', self.newline))?;
+                }
+                _ => f.write_str(&block.raw)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Parses the lines of one entry.
+struct EntryParser<'a> {
+    text: &'a str,
+    position: usize,
+    first_line: usize,
+}
+
+impl EntryParser<'_> {
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn error(&self, message: &str) -> SyntaxError {
+        let line = self.text[..self.position].matches('\n').count();
+        SyntaxError::new(self.first_line + line, message)
+    }
+
+    fn skip_blank(&mut self, newlines: bool) {
+        while let Some(c) = self.peek() {
+            if c != ' ' && !(newlines && c == '\n') {
+                return;
+            }
+            self.position += 1;
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<(), SyntaxError> {
+        if self.peek() != Some(c) {
+            return Err(self.error(&format!("expected `{}`", c)));
+        }
+        self.position += 1;
+        Ok(())
+    }
+
+    fn identifier(&mut self) -> Result<String, SyntaxError> {
+        let rest = &self.text[self.position..];
+        if !rest.starts_with(|c: char| c.is_ascii_alphabetic()) {
+            return Err(self.error("expected an identifier"));
+        }
+        let length = rest
+            .find(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '-')
+            .unwrap_or(rest.len());
+        self.position += length;
+        Ok(rest[..length].to_owned())
+    }
+
+    fn message(&mut self) -> Result<Message, SyntaxError> {
+        let term = self.peek() == Some('-');
+        if term {
+            self.position += 1;
+        }
+        let id = self.identifier()?;
+        self.skip_blank(false);
+        self.expect('=')?;
+        let value = self.pattern(false)?;
+
+        let mut attributes = Vec::new();
+        loop {
+            self.skip_blank(true);
+            match self.peek() {
+                None => break,
+                Some('.') => {
+                    self.position += 1;
+                    let id = self.identifier()?;
+                    self.skip_blank(false);
+                    self.expect('=')?;
+                    let value = self
+                        .pattern(false)?
+                        .ok_or_else(|| self.error("attribute without a value"))?;
+                    attributes.push(Attribute { id, value });
+                }
+                Some(_) => return Err(self.error("expected an attribute")),
+            }
+        }
+        if value.is_none() && (term || attributes.is_empty()) {
+            return Err(self.error("entry without a value"));
+        }
+        Ok(Message {
+            id,
+            term,
+            value,
+            attributes,
+        })
+    }
+
+    /// Reads a pattern up to an attribute, or in a variant up to the next
+    /// variant or the end of the select expression.
+    fn pattern(&mut self, variant: bool) -> Result<Option<Pattern>, SyntaxError> {
+        let mut elements = Vec::new();
+        let mut text = String::new();
+        loop {
+            match self.peek() {
+                None => break,
+                Some('\n') => {
+                    let rest = &self.text[self.position..];
+                    let next = rest.trim_start_matches([' ', '\n']);
+                    let ends = next.is_empty()
+                        || next.starts_with('.')
+                        || (variant && next.starts_with(['[', '*', '}']));
+                    if ends {
+                        break;
+                    }
+                    text.push('\n');
+                    self.position += 1;
+                }
+                Some('{') => {
+                    if !text.is_empty() {
+                        elements.push(PatternElement::Text(std::mem::take(&mut text)));
+                    }
+                    elements.push(PatternElement::Placeable(self.placeable()?));
+                }
+                Some('}') if variant => break,
+                Some('}') => return Err(self.error("unbalanced `}`")),
+                Some(c) => {
+                    text.push(c);
+                    self.position += c.len_utf8();
+                }
+            }
+        }
+        if !text.is_empty() {
+            elements.push(PatternElement::Text(text));
+        }
+        dedent(&mut elements);
+        Ok(Some(Pattern { elements }).filter(|pattern| !pattern.elements.is_empty()))
+    }
+
+    fn placeable(&mut self) -> Result<Placeable, SyntaxError> {
+        let start = self.position;
+        let mut depth = 0;
+        let mut chars = self.text[start + 1..].char_indices().peekable();
+        let arrow = loop {
+            let Some((offset, c)) = chars.next() else {
+                return Err(self.error("unclosed placeable"));
+            };
+            match c {
+                '"' => {
+                    while let Some((_, c)) = chars.next() {
+                        match c {
+                            '\\' => {
+                                chars.next();
+                            }
+                            '"' => break,
+                            _ => {}
+                        }
+                    }
+                }
+                '{' | '(' => depth += 1,
+                '}' if depth == 0 => {
+                    self.position = start + 1 + offset + 1;
+                    let expression = self.text[start..self.position].to_owned();
+                    return Ok(Placeable::Expression(expression));
+                }
+                '}' | ')' => depth -= 1,
+                '-' if depth == 0 && chars.peek().is_some_and(|(_, c)| *c == '>') => {
+                    break start + 1 + offset;
+                }
+                _ => {}
+            }
+        };
+
+        let selector = self.text[start + 1..arrow].trim().to_owned();
+        self.position = arrow + 2;
+        let mut variants = Vec::new();
+        loop {
+            self.skip_blank(true);
+            let default = self.peek() == Some('*');
+            match self.peek() {
+                Some('}') => {
+                    self.position += 1;
+                    break;
+                }
+                Some('*' | '[') => self.position += usize::from(default),
+                None => return Err(self.error("unclosed select expression")),
+                Some(_) => return Err(self.error("expected a variant")),
+            }
+            self.expect('[')?;
+            let rest = &self.text[self.position..];
+            let end = rest
+                .find([']', '\n'])
+                .filter(|&end| rest[end..].starts_with(']'))
+                .ok_or_else(|| self.error("unclosed variant key"))?;
+            let key = rest[..end].trim().to_owned();
+            self.position += end + 1;
+            let value = self
+                .pattern(true)?
+                .ok_or_else(|| self.error("variant without a value"))?;
+            variants.push(Variant {
+                key,
+                default,
+                value,
+            });
+        }
+        if variants.iter().filter(|variant| variant.default).count() != 1 {
+            return Err(self.error("a select expression needs one default variant"));
+        }
+        Ok(Placeable::Select { selector, variants })
+    }
+}
+
+/// Removes the indentation the lines of a pattern share, the blank space
+/// before it starts and after it ends.
+fn dedent(elements: &mut Vec<PatternElement>) {
+    let count = elements.len();
+    let mut common: Option<usize> = None;
+    for (index, element) in elements.iter().enumerate() {
+        let PatternElement::Text(text) = element else {
+            continue;
+        };
+        let lines: Vec<&str> = text.split('\n').skip(1).collect();
+        for (number, line) in lines.iter().enumerate() {
+            let content = line.trim_start_matches(' ');
+            // A blank line counts when a placeable follows it.
+            let before_placeable = number + 1 == lines.len() && index + 1 < count;
+            if !content.is_empty() || before_placeable {
+                let indent = line.len() - content.len();
+                common = Some(common.map_or(indent, |common| common.min(indent)));
+            }
+        }
+    }
+    let common = common.unwrap_or(0);
+
+    for element in elements.iter_mut() {
+        if let PatternElement::Text(text) = element {
+            let lines: Vec<&str> = text
+                .split('\n')
+                .enumerate()
+                .map(|(index, line)| match index {
+                    0 => line,
+                    _ => &line[common.min(line.len() - line.trim_start_matches(' ').len())..],
+                })
+                .collect();
+            *text = lines.join("\n");
+        }
+    }
+    if let Some(PatternElement::Text(text)) = elements.first_mut() {
+        *text = text.trim_start().to_owned();
+    }
+    if let Some(PatternElement::Text(text)) = elements.last_mut() {
+        *text = text.trim_end().to_owned();
+    }
+    elements.retain(|element| !matches!(element, PatternElement::Text(text) if text.is_empty()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::UppercaseBackend;
+
+    const SOURCE: &str = "\
+### Resource comment
+
+# The product name.
+-brand = Firefox
+    .gender = masculine
+
+welcome = Welcome to { -brand }, { $user }!
+    .title = Hello
+emails =
+    { $count ->
+        [one] You have one email.
+       *[other] You have { $count } emails
+            in { $folder }.
+    }
+";
+
+    fn text(value: &str) -> PatternElement {
+        PatternElement::Text(value.to_owned())
+    }
+
+    fn expression(value: &str) -> PatternElement {
+        PatternElement::Placeable(Placeable::Expression(value.to_owned()))
+    }
+
+    #[test]
+    fn test_resource_round_trips() {
+        let resource = Resource::parse(SOURCE).unwrap();
+        assert_eq!(resource.to_string(), SOURCE);
+
+        let brand = resource.get("-brand").unwrap();
+        assert_eq!(brand.value.as_ref().unwrap().elements, [text("Firefox")]);
+        assert_eq!(brand.attributes[0].id, "gender");
+
+        let welcome = resource.get("welcome").unwrap();
+        assert_eq!(
+            welcome.value.as_ref().unwrap().elements,
+            [
+                text("Welcome to "),
+                expression("{ -brand }"),
+                text(", "),
+                expression("{ $user }"),
+                text("!"),
+            ]
+        );
+
+        let emails = resource.get("emails").unwrap();
+        let Some(PatternElement::Placeable(Placeable::Select { selector, variants })) =
+            emails.value.as_ref().unwrap().elements.first()
+        else {
+            panic!("expected a select expression");
+        };
+        assert_eq!(selector, "$count");
+        assert_eq!(variants[1].key, "other");
+        assert!(variants[1].default);
+        assert_eq!(
+            variants[1].value.elements,
+            [
+                text("You have "),
+                expression("{ $count }"),
+                text(" emails\nin "),
+                expression("{ $folder }"),
+                text("."),
+            ]
+        );
+
+        let error = Resource::parse("a = { $b\n").unwrap_err();
+        assert_eq!(error.line, 1);
+        let error = Resource::parse("a = x\nb = { $n ->\n    [one] y\n}\n").unwrap_err();
+        assert_eq!(error.line, 4);
+    }
+
+    #[tokio::test]
+    async fn test_translate_resource() {
+        let translator = Translator::with_backend("en", "pl", UppercaseBackend::default()).unwrap();
+        let mut resource = Resource::parse(SOURCE).unwrap();
+        assert_eq!(resource.translate(&translator).await.unwrap(), 3);
+        assert_eq!(
+            resource.to_string(),
+            "\
+### Resource comment
+
+# The product name.
+-brand = FIREFOX
+    .gender = masculine
+
+welcome = WELCOME TO { -brand }, { $user }!
+    .title = HELLO
+emails =
+    { $count ->
+        [one] YOU HAVE ONE EMAIL.
+        [few]
+            YOU HAVE { $count } EMAILS
+            IN { $folder }.
+        [many]
+            YOU HAVE { $count } EMAILS
+            IN { $folder }.
+       *[other]
+            YOU HAVE { $count } EMAILS
+            IN { $folder }.
+    }
+"
+        );
+    }
+}
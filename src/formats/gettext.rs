@@ -0,0 +1,647 @@
+//! gettext catalogs: `.po` files and the `.pot` templates they start from.
+//!
+//! [`Catalog::parse`] keeps the text of every entry, and [`Catalog`]'s
+//! `Display` writes untouched entries back exactly as they were read, so
+//! that translating a catalog only changes the entries it translated.
+
+use super::{line_ending, placeholders, translate_messages, PluralForms, SyntaxError};
+use crate::{TranslationError, Translator};
+use std::fmt;
+
+/// Flag marking translations that need review.
+const FUZZY: &str = "fuzzy";
+
+/// Flags of formats with printf directives, such as `%d` or `%(name)s`.
+const PRINTF_FORMATS: &[&str] = &[
+    "c-format",
+    "objc-format",
+    "python-format",
+    "php-format",
+    "perl-format",
+    "awk-format",
+];
+
+/// Column that gettext tools wrap strings at.
+const WRAP_WIDTH: usize = 79;
+
+/// One message of a catalog, with its comments.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Entry {
+    /// `#` comments written by translators.
+    pub translator_comments: Vec<String>,
+    /// `#.` comments extracted from the source code.
+    pub extracted_comments: Vec<String>,
+    /// `#:` source locations.
+    pub references: Vec<String>,
+    /// `#,` flags such as `fuzzy` or `c-format`.
+    pub flags: Vec<String>,
+    /// `#|` lines describing the message a fuzzy translation was made for.
+    pub previous: Vec<String>,
+    /// `msgctxt`, telling apart messages with the same `msgid`.
+    pub context: Option<String>,
+    pub msgid: String,
+    pub msgid_plural: Option<String>,
+    /// The translation, or one per plural form when there is a
+    /// `msgid_plural`.
+    pub msgstr: Vec<String>,
+    /// Whether the entry is obsolete (`#~`), kept only for reference.
+    pub obsolete: bool,
+}
+
+impl Entry {
+    /// Whether this is the header, whose `msgstr` holds the catalog's
+    /// metadata.
+    pub fn is_header(&self) -> bool {
+        self.msgid.is_empty() && self.context.is_none() && !self.obsolete
+    }
+
+    pub fn is_fuzzy(&self) -> bool {
+        self.flags.iter().any(|flag| flag == FUZZY)
+    }
+
+    pub fn is_translated(&self) -> bool {
+        !self.msgstr.is_empty() && self.msgstr.iter().all(|msgstr| !msgstr.is_empty())
+    }
+
+    /// Whether [`Catalog::translate`] translates this entry: it is a
+    /// current message without a reviewed translation.
+    pub fn needs_translation(&self) -> bool {
+        !self.is_header() && !self.obsolete && (!self.is_translated() || self.is_fuzzy())
+    }
+
+    /// Whether the flags say the strings are in a format with printf
+    /// directives.
+    fn is_printf_format(&self) -> bool {
+        self.flags
+            .iter()
+            .any(|flag| PRINTF_FORMATS.contains(&flag.as_str()))
+    }
+
+    /// What translators are told about this entry: its `msgctxt` and
+    /// comments.
+    fn description(&self) -> Option<String> {
+        let lines: Vec<&str> = self
+            .context
+            .iter()
+            .chain(&self.translator_comments)
+            .chain(&self.extracted_comments)
+            .map(|line| line.trim())
+            .filter(|line| !line.is_empty())
+            .collect();
+        (!lines.is_empty()).then(|| lines.join("\n"))
+    }
+
+    fn write(&self, out: &mut String, newline: &str) {
+        let mut line = |text: String| {
+            out.push_str(text.trim_end());
+            out.push_str(newline);
+        };
+        for comment in &self.translator_comments {
+            line(format!("# {}", comment));
+        }
+        for comment in &self.extracted_comments {
+            line(format!("#. {}", comment));
+        }
+        for reference in &self.references {
+            line(format!("#: {}", reference));
+        }
+        if !self.flags.is_empty() {
+            line(format!("#, {}", self.flags.join(", ")));
+        }
+        let (prefix, previous) = match self.obsolete {
+            true => ("#~ ", "#~| "),
+            false => ("", "#| "),
+        };
+        for text in &self.previous {
+            line(format!("{}{}", previous, text));
+        }
+
+        let mut field = |keyword: &str, value: &str| {
+            for text in quote(keyword, value) {
+                line(format!("{}{}", prefix, text));
+            }
+        };
+        if let Some(context) = &self.context {
+            field("msgctxt", context);
+        }
+        field("msgid", &self.msgid);
+        match &self.msgid_plural {
+            Some(plural) => {
+                field("msgid_plural", plural);
+                for (index, msgstr) in self.msgstr.iter().enumerate() {
+                    field(&format!("msgstr[{}]", index), msgstr);
+                }
+            }
+            None => field("msgstr", self.msgstr.first().map_or("", String::as_str)),
+        }
+    }
+}
+
+/// A part of the file: an entry, or comments that belong to none.
+#[derive(Debug, Clone)]
+struct Block {
+    /// The block as read, written back while the entry is unchanged.
+    raw: String,
+    /// The blank lines that follow it.
+    separator: String,
+    original: Option<Entry>,
+    entry: Option<Entry>,
+}
+
+/// A parsed `.po` or `.pot` file.
+#[derive(Debug, Clone)]
+pub struct Catalog {
+    /// Blank lines before the first entry.
+    leading: String,
+    blocks: Vec<Block>,
+    /// The line ending used by the file.
+    newline: &'static str,
+}
+
+impl Catalog {
+    pub fn parse(text: &str) -> Result<Self, SyntaxError> {
+        let newline = line_ending(text);
+
+        // Byte ranges of the blocks, with their parsed entries.
+        let mut spans: Vec<(usize, usize, Option<Entry>)> = Vec::new();
+        let mut current: Option<(usize, EntryParser)> = None;
+        let mut offset = 0;
+        for (index, line) in text.split_inclusive('\n').enumerate() {
+            let content = line.trim_end_matches(['\n', '\r']).trim();
+            let start = offset;
+            offset += line.len();
+
+            if content.is_empty() {
+                if let Some((block_start, parser)) = current.take() {
+                    spans.push((block_start, start, parser.finish()?));
+                }
+                continue;
+            }
+            if let Some((block_start, parser)) =
+                current.take_if(|(_, parser)| parser.ends_before(content))
+            {
+                spans.push((block_start, start, parser.finish()?));
+            }
+            let (_, parser) = current.get_or_insert_with(|| (start, EntryParser::default()));
+            parser.line(content, index + 1)?;
+        }
+        if let Some((block_start, parser)) = current {
+            spans.push((block_start, text.len(), parser.finish()?));
+        }
+
+        let mut blocks = Vec::with_capacity(spans.len());
+        for (index, (start, end, entry)) in spans.iter().enumerate() {
+            let next = spans
+                .get(index + 1)
+                .map_or(text.len(), |(next, _, _)| *next);
+            blocks.push(Block {
+                raw: text[*start..*end].to_owned(),
+                separator: text[*end..next].to_owned(),
+                original: entry.clone(),
+                entry: entry.clone(),
+            });
+        }
+        Ok(Self {
+            leading: text[..spans.first().map_or(text.len(), |(start, _, _)| *start)].to_owned(),
+            blocks,
+            newline,
+        })
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &Entry> {
+        self.blocks.iter().filter_map(|block| block.entry.as_ref())
+    }
+
+    pub fn entries_mut(&mut self) -> impl Iterator<Item = &mut Entry> {
+        self.blocks
+            .iter_mut()
+            .filter_map(|block| block.entry.as_mut())
+    }
+
+    pub fn header(&self) -> Option<&Entry> {
+        self.entries().find(|entry| entry.is_header())
+    }
+
+    /// The value of a header field such as `Plural-Forms`.
+    pub fn header_field(&self, name: &str) -> Option<&str> {
+        let header = self.header()?.msgstr.first()?;
+        header.lines().find_map(|line| {
+            let (key, value) = line.split_once(':')?;
+            key.trim().eq_ignore_ascii_case(name).then(|| value.trim())
+        })
+    }
+
+    /// Sets a header field, adding it when missing. Does nothing when the
+    /// catalog has no header.
+    pub fn set_header_field(&mut self, name: &str, value: &str) {
+        let Some(header) = self.entries_mut().find(|entry| entry.is_header()) else {
+            return;
+        };
+        if header.msgstr.is_empty() {
+            header.msgstr.push(String::new());
+        }
+        let msgstr = &mut header.msgstr[0];
+        let mut found = false;
+        let mut lines: Vec<String> = msgstr
+            .lines()
+            .map(|line| match line.split_once(':') {
+                Some((key, _)) if key.trim().eq_ignore_ascii_case(name) => {
+                    found = true;
+                    format!("{}: {}", key, value)
+                }
+                _ => line.to_owned(),
+            })
+            .collect();
+        if !found {
+            lines.push(format!("{}: {}", name, value));
+        }
+        *msgstr = lines.join("\n") + "\n";
+    }
+
+    /// The plural rules of the catalog's `Plural-Forms` header.
+    pub fn plural_forms(&self) -> Option<PluralForms> {
+        PluralForms::parse(self.header_field("Plural-Forms")?)
+    }
+
+    /// Translates the entries that are untranslated or fuzzy with
+    /// `translator`, filling every plural form of the target language and
+    /// marking the results fuzzy for review. Comments and `msgctxt` are
+    /// passed to the backend as context. Returns the number of entries
+    /// translated.
+    ///
+    /// Brace placeholders such as `{0}`, and printf directives such as `%d`
+    /// in entries flagged `c-format`, `python-format` and the like, are
+    /// kept as they are; an entry whose placeholders the backend loses is
+    /// translated in the parts between them instead.
+    ///
+    /// A header without plural rules or language gets those of the
+    /// translator's target language.
+    pub async fn translate(&mut self, translator: &Translator) -> Result<usize, TranslationError> {
+        let target = translator.target_lang();
+        let plural_forms = match self.plural_forms() {
+            Some(plural_forms) => plural_forms,
+            None => {
+                let plural_forms = PluralForms::for_language(target);
+                self.set_header_field("Plural-Forms", &plural_forms.to_string());
+                plural_forms
+            }
+        };
+        if self.header_field("Language").is_none_or(str::is_empty) {
+            self.set_header_field("Language", &target.to_string().replace('-', "_"));
+        }
+
+        let mut texts = Vec::new();
+        for entry in self.entries().filter(|entry| entry.needs_translation()) {
+            let context = entry.description();
+            let printf = entry.is_printf_format();
+            texts.push((placeholders(&entry.msgid, printf), context.clone()));
+            if let Some(plural) = &entry.msgid_plural {
+                texts.push((placeholders(plural, printf), context));
+            }
+        }
+        let mut translations = translate_messages(translator, &texts).await?.into_iter();
+
+        let singular_index = plural_forms.index(1);
+        let mut count = 0;
+        for entry in self.entries_mut().filter(|entry| entry.needs_translation()) {
+            let singular = translations.next().expect("one translation per text");
+            entry.msgstr = match entry.msgid_plural {
+                Some(_) => {
+                    let plural = translations.next().expect("one translation per text");
+                    (0..plural_forms.nplurals())
+                        .map(|index| match index == singular_index {
+                            true => singular.clone(),
+                            false => plural.clone(),
+                        })
+                        .collect()
+                }
+                None => vec![singular],
+            };
+            if !entry.is_fuzzy() {
+                entry.flags.insert(0, FUZZY.to_owned());
+            }
+            entry.previous.clear();
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+impl fmt::Display for Catalog {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.leading)?;
+        for block in &self.blocks {
+            match &block.entry {
+                Some(entry) if block.original.as_ref() != Some(entry) => {
+                    let mut text = String::new();
+                    entry.write(&mut text, self.newline);
+                    // Keep a missing newline at the end of the file missing.
+                    if !block.raw.ends_with('\n') {
+                        text.truncate(text.len() - self.newline.len());
+                    }
+                    f.write_str(&text)?;
+                }
+                _ => f.write_str(&block.raw)?,
+            }
+            f.write_str(&block.separator)?;
+        }
+        Ok(())
+    }
+}
+
+/// Which string of an entry continuation lines add to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Field {
+    Context,
+    Id,
+    Plural,
+    Str(usize),
+}
+
+/// Collects the lines of one block into an entry.
+#[derive(Debug, Default)]
+struct EntryParser {
+    entry: Entry,
+    has_msgid: bool,
+    field: Option<Field>,
+    first_line: usize,
+}
+
+impl EntryParser {
+    /// Whether `line` starts another entry after a complete one, as when
+    /// entries are not separated by blank lines.
+    fn ends_before(&self, line: &str) -> bool {
+        matches!(self.field, Some(Field::Str(_)))
+            && !line.starts_with('"')
+            && !line.starts_with("msgstr")
+            && !line.starts_with("#~ \"")
+            && !line.starts_with("#~ msgstr")
+    }
+
+    fn line(&mut self, line: &str, number: usize) -> Result<(), SyntaxError> {
+        if self.first_line == 0 {
+            self.first_line = number;
+        }
+        let error = |message: &str| SyntaxError::new(number, message);
+        let entry = &mut self.entry;
+
+        let line = match line.strip_prefix("#~") {
+            Some(rest) => {
+                entry.obsolete = true;
+                let rest = rest.trim_start();
+                if let Some(previous) = rest.strip_prefix('|') {
+                    entry.previous.push(previous.trim().to_owned());
+                    return Ok(());
+                }
+                rest
+            }
+            None => line,
+        };
+
+        if let Some(comment) = line.strip_prefix('#') {
+            let text = |rest: &str| rest.strip_prefix(' ').unwrap_or(rest).to_owned();
+            match comment.chars().next() {
+                Some('.') => entry.extracted_comments.push(text(&comment[1..])),
+                Some(':') => entry.references.push(text(&comment[1..])),
+                Some('|') => entry.previous.push(text(&comment[1..])),
+                Some(',') => entry.flags.extend(
+                    comment[1..]
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|flag| !flag.is_empty())
+                        .map(str::to_owned),
+                ),
+                _ => entry.translator_comments.push(text(comment)),
+            }
+            return Ok(());
+        }
+
+        if line.starts_with('"') {
+            let value = unquote(line).ok_or_else(|| error("Invalid string"))?;
+            let target = match self.field {
+                Some(Field::Context) => entry.context.get_or_insert_with(String::new),
+                Some(Field::Id) => &mut entry.msgid,
+                Some(Field::Plural) => entry.msgid_plural.get_or_insert_with(String::new),
+                Some(Field::Str(index)) => &mut entry.msgstr[index],
+                None => return Err(error("String without a keyword")),
+            };
+            target.push_str(&value);
+            return Ok(());
+        }
+
+        let (keyword, rest) = line
+            .split_once(|c: char| c.is_whitespace() || c == '"')
+            .map(|(keyword, _)| (keyword, line[keyword.len()..].trim_start()))
+            .ok_or_else(|| error("Expected a keyword and a string"))?;
+        let value = unquote(rest).ok_or_else(|| error("Invalid string"))?;
+        let field = match keyword {
+            "msgctxt" => Field::Context,
+            "msgid" => Field::Id,
+            "msgid_plural" => Field::Plural,
+            "msgstr" => Field::Str(0),
+            _ => match keyword
+                .strip_prefix("msgstr[")
+                .and_then(|rest| rest.strip_suffix(']'))
+                .and_then(|index| index.parse().ok())
+            {
+                Some(index) => Field::Str(index),
+                None => return Err(error(&format!("Unknown keyword {}", keyword))),
+            },
+        };
+
+        match field {
+            Field::Context => entry.context = Some(value),
+            Field::Id => {
+                entry.msgid = value;
+                self.has_msgid = true;
+            }
+            Field::Plural => entry.msgid_plural = Some(value),
+            Field::Str(index) => {
+                if index != entry.msgstr.len() {
+                    return Err(error(&format!("Expected msgstr[{}]", entry.msgstr.len())));
+                }
+                entry.msgstr.push(value);
+            }
+        }
+        self.field = Some(field);
+        Ok(())
+    }
+
+    /// The entry, or `None` for a block of comments only.
+    fn finish(self) -> Result<Option<Entry>, SyntaxError> {
+        if self.field.is_none() {
+            return Ok(None);
+        }
+        if !self.has_msgid {
+            return Err(SyntaxError::new(self.first_line, "Entry without msgid"));
+        }
+        if self.entry.msgstr.is_empty() {
+            return Err(SyntaxError::new(self.first_line, "Entry without msgstr"));
+        }
+        Ok(Some(self.entry))
+    }
+}
+
+/// Decodes a quoted PO string with C escapes.
+fn unquote(text: &str) -> Option<String> {
+    let inner = text.trim().strip_prefix('"')?.strip_suffix('"')?;
+    let mut value = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => value.push(match chars.next()? {
+                'n' => '\n',
+                't' => '\t',
+                'r' => '\r',
+                'a' => '\x07',
+                'b' => '\x08',
+                'f' => '\x0c',
+                'v' => '\x0b',
+                c @ ('\\' | '"' | '\'' | '?') => c,
+                _ => return None,
+            }),
+            '"' => return None,
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '"' => escaped.push_str("\\\""),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Formats `keyword "value"` the way gettext tools do: on one line when it
+/// fits, and otherwise starting with an empty string followed by one line
+/// per line of the value, wrapped at spaces.
+fn quote(keyword: &str, value: &str) -> Vec<String> {
+    let escaped = escape(value);
+    let single = format!("{} \"{}\"", keyword, escaped);
+    let inner_newline = value.find('\n').is_some_and(|end| end + 1 < value.len());
+    if !inner_newline && single.chars().count() <= WRAP_WIDTH {
+        return vec![single];
+    }
+
+    let mut lines = vec![format!("{} \"\"", keyword)];
+    for piece in escaped.split_inclusive("\\n") {
+        let mut line = String::new();
+        for word in piece.split_inclusive(' ') {
+            if !line.is_empty() && line.chars().count() + word.chars().count() + 2 > WRAP_WIDTH {
+                lines.push(format!("\"{}\"", line));
+                line.clear();
+            }
+            line.push_str(word);
+        }
+        lines.push(format!("\"{}\"", line));
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::UppercaseBackend;
+
+    const CATALOG: &str = r#"# Translations for the demo app.
+msgid ""
+msgstr ""
+"Project-Id-Version: demo 1.0\n"
+"Language: \n"
+"Content-Type: text/plain; charset=UTF-8\n"
+
+#. Shown on the start page
+#: src/main.rs:10
+msgid "Welcome"
+msgstr ""
+
+#: src/main.rs:12
+#, c-format
+msgid "%d file"
+msgid_plural "%d files"
+msgstr[0] ""
+msgstr[1] ""
+
+#  Kept as it is
+msgctxt "menu"
+msgid "Open"
+msgstr "Ouvrir"
+
+#, fuzzy
+#| msgid "Close it"
+msgctxt "menu"
+msgid "Close"
+msgstr "Fermer ça"
+
+#~ msgid "Old"
+#~ msgstr ""
+"#;
+
+    #[test]
+    fn test_catalog_round_trips() {
+        let catalog = Catalog::parse(CATALOG).unwrap();
+        assert_eq!(catalog.to_string(), CATALOG);
+        assert_eq!(catalog.entries().count(), 6);
+        assert_eq!(catalog.header_field("project-id-version"), Some("demo 1.0"));
+
+        let entries: Vec<_> = catalog.entries().collect();
+        assert_eq!(entries[2].msgid_plural.as_deref(), Some("%d files"));
+        assert_eq!(entries[3].context.as_deref(), Some("menu"));
+        assert_eq!(entries[3].translator_comments, [" Kept as it is"]);
+        assert_eq!(entries[4].previous, ["msgid \"Close it\""]);
+        assert!(entries[5].obsolete);
+        let needing: Vec<_> = catalog
+            .entries()
+            .filter(|entry| entry.needs_translation())
+            .map(|entry| entry.msgid.as_str())
+            .collect();
+        assert_eq!(needing, ["Welcome", "%d file", "Close"]);
+
+        let crlf = CATALOG.replace('\n', "\r\n");
+        assert_eq!(Catalog::parse(&crlf).unwrap().to_string(), crlf);
+
+        let error = Catalog::parse("msgid \"a\"\nmsgstr \"b\nmsgstr \"c\"\n").unwrap_err();
+        assert_eq!(error, SyntaxError::new(2, "Invalid string"));
+    }
+
+    #[tokio::test]
+    async fn test_translate_catalog() {
+        let backend = UppercaseBackend::default();
+        let translator = Translator::with_backend("en", "ru", backend.clone()).unwrap();
+        let mut catalog = Catalog::parse(CATALOG).unwrap();
+        assert_eq!(catalog.translate(&translator).await.unwrap(), 3);
+
+        let expected = CATALOG
+            .replace(
+                "\"Language: \\n\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"",
+                "\"Language: ru\\n\"\n\"Content-Type: text/plain; charset=UTF-8\\n\"\n\
+                 \"Plural-Forms: nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n%10>=2 && \"\n\
+                 \"n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);\\n\"",
+            )
+            .replace(
+                "#: src/main.rs:10\nmsgid \"Welcome\"\nmsgstr \"\"",
+                "#: src/main.rs:10\n#, fuzzy\nmsgid \"Welcome\"\nmsgstr \"WELCOME\"",
+            )
+            .replace(
+                "#, c-format\nmsgid \"%d file\"\nmsgid_plural \"%d files\"\nmsgstr[0] \"\"\nmsgstr[1] \"\"",
+                "#, fuzzy, c-format\nmsgid \"%d file\"\nmsgid_plural \"%d files\"\n\
+                 msgstr[0] \"%d FILE\"\nmsgstr[1] \"%d FILES\"\nmsgstr[2] \"%d FILES\"",
+            )
+            .replace(
+                "#| msgid \"Close it\"\nmsgctxt \"menu\"\nmsgid \"Close\"\nmsgstr \"Fermer ça\"",
+                "msgctxt \"menu\"\nmsgid \"Close\"\nmsgstr \"CLOSE\"",
+            );
+        assert_eq!(catalog.to_string(), expected);
+        assert_eq!(backend.contexts(), ["Shown on the start page", "menu"]);
+    }
+}
@@ -0,0 +1,314 @@
+//! Nested JSON locale files, as used by i18next, vue-i18n or next-intl.
+//!
+//! [`JsonDocument`] edits the string values of a file in place, so that
+//! key order, indentation and every other value stay as they were written.
+
+use super::{translate_values, KeyFilter, SyntaxError};
+use crate::{TranslationError, Translator};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+/// A string value of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonString {
+    pub value: String,
+    path: Vec<String>,
+    /// The value as read, with the byte range of its literal.
+    original: String,
+    span: Range<usize>,
+}
+
+impl JsonString {
+    /// The keys leading to the value from the root; array elements are
+    /// named by their index.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// The path joined with dots, as in `home.title`.
+    pub fn key(&self) -> String {
+        self.path.join(".")
+    }
+}
+
+/// A parsed JSON file.
+#[derive(Debug, Clone)]
+pub struct JsonDocument {
+    text: String,
+    strings: Vec<JsonString>,
+}
+
+impl JsonDocument {
+    pub fn parse(text: &str) -> Result<Self, SyntaxError> {
+        // A byte order mark is kept, but not shown to serde.
+        let start = text.len() - text.trim_start_matches('\u{feff}').len();
+        if let Err(error) = serde_json::from_str::<serde_json::Value>(&text[start..]) {
+            let message = error.to_string();
+            let message = match message.rsplit_once(" at line ") {
+                Some((message, _)) => message.to_owned(),
+                None => message,
+            };
+            return Err(SyntaxError::new(error.line(), message));
+        }
+
+        let mut scanner = Scanner {
+            text,
+            position: start,
+            strings: Vec::new(),
+        };
+        scanner.value(&mut Vec::new());
+        Ok(Self {
+            text: text.to_owned(),
+            strings: scanner.strings,
+        })
+    }
+
+    /// The string values, in the order they appear.
+    pub fn strings(&self) -> impl Iterator<Item = &JsonString> {
+        self.strings.iter()
+    }
+
+    pub fn strings_mut(&mut self) -> impl Iterator<Item = &mut JsonString> {
+        self.strings.iter_mut()
+    }
+
+    /// The string at `path`, if there is one.
+    pub fn get<S: AsRef<str>>(&self, path: &[S]) -> Option<&str> {
+        self.strings
+            .iter()
+            .find(|string| {
+                string
+                    .path
+                    .iter()
+                    .map(String::as_str)
+                    .eq(path.iter().map(AsRef::as_ref))
+            })
+            .map(|string| string.value.as_str())
+    }
+
+    /// Translates the strings whose keys `keys` selects with `translator`.
+    /// Returns the number of strings translated.
+    pub async fn translate(
+        &mut self,
+        translator: &Translator,
+        keys: &KeyFilter,
+    ) -> Result<usize, TranslationError> {
+        self.translate_missing(translator, keys, None).await
+    }
+
+    /// Turns this source document into its translation, keeping the
+    /// strings `existing` already translates and translating the others
+    /// whose keys `keys` selects. Strings only found in `existing` are
+    /// dropped. Returns the number of strings translated.
+    pub async fn update(
+        &mut self,
+        translator: &Translator,
+        keys: &KeyFilter,
+        existing: &JsonDocument,
+    ) -> Result<usize, TranslationError> {
+        self.translate_missing(translator, keys, Some(existing))
+            .await
+    }
+
+    async fn translate_missing(
+        &mut self,
+        translator: &Translator,
+        keys: &KeyFilter,
+        existing: Option<&JsonDocument>,
+    ) -> Result<usize, TranslationError> {
+        let existing: HashMap<&[String], &str> = existing
+            .iter()
+            .flat_map(|existing| existing.strings())
+            .map(|string| (string.path(), string.value.as_str()))
+            .collect();
+        let mut missing = Vec::new();
+        for string in &mut self.strings {
+            match existing.get(string.path.as_slice()) {
+                Some(translation) => string.value = (*translation).to_owned(),
+                None => missing.push((string.path.as_slice(), &mut string.value)),
+            }
+        }
+        translate_values(translator, keys, missing).await
+    }
+}
+
+impl fmt::Display for JsonDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut position = 0;
+        for string in self.strings.iter().filter(|s| s.value != s.original) {
+            f.write_str(&self.text[position..string.span.start])?;
+            let literal = serde_json::to_string(&string.value).map_err(|_| fmt::Error)?;
+            f.write_str(&literal)?;
+            position = string.span.end;
+        }
+        f.write_str(&self.text[position..])
+    }
+}
+
+/// Finds the string values of a document that is known to be valid.
+struct Scanner<'a> {
+    text: &'a str,
+    position: usize,
+    strings: Vec<JsonString>,
+}
+
+impl Scanner<'_> {
+    fn peek(&self) -> u8 {
+        self.text.as_bytes()[self.position]
+    }
+
+    fn skip_whitespace(&mut self) {
+        while self.text.as_bytes()[self.position].is_ascii_whitespace() {
+            self.position += 1;
+        }
+    }
+
+    fn value(&mut self, path: &mut Vec<String>) {
+        self.skip_whitespace();
+        match self.peek() {
+            b'{' => {
+                self.position += 1;
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        b'}' => break,
+                        b',' => self.position += 1,
+                        _ => {
+                            path.push(self.string().1);
+                            self.skip_whitespace();
+                            self.position += 1; // `:`
+                            self.value(path);
+                            path.pop();
+                        }
+                    }
+                }
+                self.position += 1;
+            }
+            b'[' => {
+                self.position += 1;
+                let mut index = 0;
+                loop {
+                    self.skip_whitespace();
+                    match self.peek() {
+                        b']' => break,
+                        b',' => self.position += 1,
+                        _ => {
+                            path.push(index.to_string());
+                            self.value(path);
+                            path.pop();
+                            index += 1;
+                        }
+                    }
+                }
+                self.position += 1;
+            }
+            b'"' => {
+                let (span, value) = self.string();
+                self.strings.push(JsonString {
+                    path: path.clone(),
+                    original: value.clone(),
+                    value,
+                    span,
+                });
+            }
+            // Numbers, booleans and null end at a delimiter or whitespace.
+            _ => {
+                while !matches!(self.peek(), b',' | b'}' | b']')
+                    && !self.peek().is_ascii_whitespace()
+                {
+                    self.position += 1;
+                    if self.position == self.text.len() {
+                        return;
+                    }
+                }
+            }
+        }
+    }
+
+    /// Reads a string literal, returning its range and value.
+    fn string(&mut self) -> (Range<usize>, String) {
+        let start = self.position;
+        self.position += 1;
+        loop {
+            match self.peek() {
+                b'\\' => self.position += 2,
+                b'"' => break,
+                _ => self.position += 1,
+            }
+        }
+        self.position += 1;
+        let value = serde_json::from_str(&self.text[start..self.position])
+            .expect("the document was validated");
+        (start..self.position, value)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::UppercaseBackend;
+
+    const SOURCE: &str = r#"{
+  "home": {
+    "title": "Welcome",
+    "subtitle":   "Hello, \"friend\"",
+    "visits": 3
+  },
+  "url": "https://example.com",
+  "items": ["one", "{count} two", null, true],
+  "empty": ""
+}
+"#;
+
+    #[test]
+    fn test_document_round_trips() {
+        let document = JsonDocument::parse(SOURCE).unwrap();
+        assert_eq!(document.to_string(), SOURCE);
+        let keys: Vec<_> = document.strings().map(JsonString::key).collect();
+        assert_eq!(
+            keys,
+            [
+                "home.title",
+                "home.subtitle",
+                "url",
+                "items.0",
+                "items.1",
+                "empty"
+            ]
+        );
+        assert_eq!(
+            document.get(&["home", "subtitle"]),
+            Some("Hello, \"friend\"")
+        );
+
+        let error = JsonDocument::parse("{\n  \"a\": 1,\n}").unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(
+            JsonDocument::parse("\u{feff}7").unwrap().to_string(),
+            "\u{feff}7"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_translate_missing_keys() {
+        let translator = Translator::with_backend("en", "fr", UppercaseBackend::default()).unwrap();
+        let existing =
+            JsonDocument::parse(r#"{"home": {"title": "Bienvenue"}, "old": "x"}"#).unwrap();
+        let mut document = JsonDocument::parse(SOURCE).unwrap();
+        let keys = KeyFilter::new().with_exclude("url");
+
+        let count = document
+            .update(&translator, &keys, &existing)
+            .await
+            .unwrap();
+        assert_eq!(count, 3);
+        assert_eq!(
+            document.to_string(),
+            SOURCE
+                .replace("Welcome", "Bienvenue")
+                .replace(r#"Hello, \"friend\""#, r#"HELLO, \"FRIEND\""#)
+                .replace(r#"["one", "{count} two""#, r#"["ONE", "{count} TWO""#)
+        );
+    }
+}
@@ -0,0 +1,397 @@
+//! Localization file formats whose texts are translated in place, leaving
+//! the rest of the file as it was.
+
+mod filter;
+pub mod fluent;
+pub mod gettext;
+pub mod json;
+mod plural;
+pub mod toml;
+pub mod xliff;
+pub mod yaml;
+
+pub use filter::KeyFilter;
+pub use plural::{plural_categories, PluralForms};
+
+use crate::{TranslationError, Translator};
+use futures_util::stream::{self, StreamExt};
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt;
+
+/// A file that is not valid in its format.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyntaxError {
+    /// The line the problem was found on, starting at 1.
+    pub line: usize,
+    pub message: String,
+}
+
+impl SyntaxError {
+    fn new(line: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for SyntaxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl Error for SyntaxError {}
+
+/// Translates `texts`, each with the context describing it, returning the
+/// translations in the same order. Texts without context share batched
+/// requests, repeated texts are translated once and blank texts are kept
+/// as they are. Fails with the first error.
+async fn translate_texts(
+    translator: &Translator,
+    texts: &[(String, Option<String>)],
+) -> Result<Vec<String>, TranslationError> {
+    let mut unique: Vec<(&str, Option<&str>)> = Vec::new();
+    let mut indices = HashMap::new();
+    for (text, context) in texts {
+        let key = (text.as_str(), context.as_deref());
+        if !text.trim().is_empty() && !indices.contains_key(&key) {
+            indices.insert(key, unique.len());
+            unique.push(key);
+        }
+    }
+
+    let plain: Vec<&str> = unique
+        .iter()
+        .filter(|(_, context)| context.is_none())
+        .map(|(text, _)| *text)
+        .collect();
+    let mut plain = translator.translate_batch(&plain).await.into_iter();
+    let mut described = stream::iter(unique.iter().filter_map(|(text, context)| {
+        let context = (*context)?;
+        Some(translator.translate_with_context(text, context))
+    }))
+    .buffered(translator.concurrency)
+    .collect::<Vec<_>>()
+    .await
+    .into_iter();
+
+    let mut translations = Vec::with_capacity(unique.len());
+    for (_, context) in &unique {
+        let result = match context {
+            Some(_) => described.next(),
+            None => plain.next(),
+        };
+        translations.push(result.expect("every text has a result")?.text);
+    }
+
+    Ok(texts
+        .iter()
+        .map(
+            |(text, context)| match indices.get(&(text.as_str(), context.as_deref())) {
+                Some(&index) => translations[index].clone(),
+                None => text.clone(),
+            },
+        )
+        .collect())
+}
+
+/// Translates `texts`, split into text runs and the placeholders
+/// [`placeholders`] finds, as [`translate_texts`] does, keeping the
+/// placeholders as they are.
+///
+/// Each text is translated as a whole, with its placeholders masked; when
+/// the backend loses one, or the text has braces that masking would
+/// confuse, its text runs are translated one by one instead.
+async fn translate_messages(
+    translator: &Translator,
+    texts: &[(Vec<Piece<String>>, Option<String>)],
+) -> Result<Vec<String>, TranslationError> {
+    let whole: Vec<_> = texts
+        .iter()
+        .filter(|(pieces, _)| !has_stray_braces(pieces))
+        .map(|(pieces, context)| (mask(pieces), context.clone()))
+        .collect();
+    let mut translations = translate_texts(translator, &whole).await?.into_iter();
+    let results: Vec<Option<Vec<Piece<String>>>> = texts
+        .iter()
+        .map(|(pieces, _)| {
+            if has_stray_braces(pieces) {
+                return None;
+            }
+            let translation = translations.next().expect("one translation per text");
+            unmask(pieces, &translation, |_| true)
+        })
+        .collect();
+
+    let runs: Vec<_> = texts
+        .iter()
+        .zip(&results)
+        .filter(|(_, result)| result.is_none())
+        .flat_map(|((pieces, context), _)| {
+            pieces.iter().filter_map(move |piece| match piece {
+                Piece::Text(text) if !text.trim().is_empty() => {
+                    Some((text.clone(), context.clone()))
+                }
+                _ => None,
+            })
+        })
+        .collect();
+    let mut run_translations = translate_texts(translator, &runs).await?.into_iter();
+    Ok(texts
+        .iter()
+        .zip(results)
+        .map(|((pieces, _), result)| {
+            let pieces = result.unwrap_or_else(|| {
+                pieces
+                    .iter()
+                    .map(|piece| match piece {
+                        Piece::Text(text) if !text.trim().is_empty() => {
+                            let translation =
+                                run_translations.next().expect("one translation per text");
+                            Piece::Text(keep_spacing(text, &translation))
+                        }
+                        piece => piece.clone(),
+                    })
+                    .collect()
+            });
+            pieces
+                .into_iter()
+                .map(|piece| match piece {
+                    Piece::Text(text) | Piece::Kept(text) => text,
+                })
+                .collect()
+        })
+        .collect())
+}
+
+/// Translates the non-blank `values` whose paths `keys` selects in place,
+/// as [`translate_messages`] does without context, so that the brace
+/// placeholders of i18next, vue-i18n, ICU and Rails messages are kept.
+/// Returns the number of values translated.
+async fn translate_values<'a>(
+    translator: &Translator,
+    keys: &KeyFilter,
+    values: impl IntoIterator<Item = (&'a [String], &'a mut String)>,
+) -> Result<usize, TranslationError> {
+    let mut selected: Vec<&mut String> = values
+        .into_iter()
+        .filter(|(path, value)| keys.matches(path) && !value.trim().is_empty())
+        .map(|(_, value)| value)
+        .collect();
+    let texts: Vec<_> = selected
+        .iter()
+        .map(|value| (placeholders(value, false), None))
+        .collect();
+    let translations = translate_messages(translator, &texts).await?;
+    for (value, translation) in selected.iter_mut().zip(translations) {
+        **value = translation;
+    }
+    Ok(selected.len())
+}
+
+/// The line ending `text` uses: that of its first line.
+fn line_ending(text: &str) -> &'static str {
+    match text.find('\n') {
+        Some(end) if text[..end].ends_with('\r') => "\r\n",
+        _ => "\n",
+    }
+}
+
+/// `translation` with the whitespace `original` started and ended with.
+fn keep_spacing(original: &str, translation: &str) -> String {
+    let trimmed = original.trim();
+    if trimmed.is_empty() {
+        return original.to_owned();
+    }
+    let start = original.find(trimmed).unwrap_or(0);
+    format!(
+        "{}{}{}",
+        &original[..start],
+        translation.trim(),
+        &original[start + trimmed.len()..]
+    )
+}
+
+/// A run of text, or a part of a text that translations keep as it is,
+/// such as a placeable or an inline tag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Piece<T> {
+    Text(String),
+    Kept(T),
+}
+
+/// The text of `pieces` with each kept piece replaced by `{index}`, so
+/// that they are translated together.
+fn mask<T>(pieces: &[Piece<T>]) -> String {
+    let mut masked = String::new();
+    let mut index = 0;
+    for piece in pieces {
+        match piece {
+            Piece::Text(text) => masked.push_str(text),
+            Piece::Kept(_) => {
+                masked.push_str(&format!("{{{}}}", index));
+                index += 1;
+            }
+        }
+    }
+    masked
+}
+
+/// Puts the kept pieces of `pieces` back into the translation of their
+/// [`mask`]ed text. Returns `None` unless every marker was kept exactly
+/// once and `valid` accepts the order the kept pieces now come in.
+fn unmask<T: Clone>(
+    pieces: &[Piece<T>],
+    translation: &str,
+    valid: impl Fn(&[&T]) -> bool,
+) -> Option<Vec<Piece<T>>> {
+    let kept: Vec<&T> = pieces
+        .iter()
+        .filter_map(|piece| match piece {
+            Piece::Kept(kept) => Some(kept),
+            Piece::Text(_) => None,
+        })
+        .collect();
+    let mut used = vec![false; kept.len()];
+    let mut order = Vec::new();
+    let mut result = Vec::new();
+    let mut rest = translation;
+    while let Some(start) = rest.find('{') {
+        let end = start + rest[start..].find('}')?;
+        let index: usize = rest[start + 1..end].parse().ok()?;
+        if used.get(index) != Some(&false) {
+            return None;
+        }
+        used[index] = true;
+        if start > 0 {
+            result.push(Piece::Text(rest[..start].to_owned()));
+        }
+        result.push(Piece::Kept(kept[index].clone()));
+        order.push(kept[index]);
+        rest = &rest[end + 1..];
+    }
+    if rest.contains('}') || used.contains(&false) || !valid(&order) {
+        return None;
+    }
+    if !rest.is_empty() {
+        result.push(Piece::Text(rest.to_owned()));
+    }
+    Some(result)
+}
+
+/// Splits `text` into text runs and the placeholders that translations
+/// keep: brace placeholders such as `{0}`, `{name}`, i18next's `{{count}}`,
+/// Ruby's `%{count}` and ICU's `{n, plural, one {# file} other {# files}}`,
+/// and with `printf` also directives such as `%d`, `%1$s` and `%(name)s`.
+fn placeholders(text: &str, printf: bool) -> Vec<Piece<String>> {
+    let mut pieces = Vec::new();
+    let mut start = 0;
+    let mut index = 0;
+    while index < text.len() {
+        let end = match text.as_bytes()[index] {
+            b'{' => braces_end(text, index),
+            b'%' if text[index + 1..].starts_with('{') => braces_end(text, index + 1),
+            b'%' if printf => directive_end(text, index),
+            _ => None,
+        };
+        let Some(end) = end else {
+            index += 1;
+            continue;
+        };
+        if start < index {
+            pieces.push(Piece::Text(text[start..index].to_owned()));
+        }
+        pieces.push(Piece::Kept(text[index..end].to_owned()));
+        (start, index) = (end, end);
+    }
+    if start < text.len() {
+        pieces.push(Piece::Text(text[start..].to_owned()));
+    }
+    pieces
+}
+
+/// The end of the braces opening at `open`, after the one closing them.
+fn braces_end(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (offset, c) in text[open..].char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            _ => continue,
+        }
+        if depth == 0 {
+            return Some(open + offset + 1);
+        }
+    }
+    None
+}
+
+/// The end of the printf directive starting with the `%` at `start`.
+fn directive_end(text: &str, start: usize) -> Option<usize> {
+    let rest = &text[start + 1..];
+    let name = match rest.strip_prefix('(') {
+        Some(name) => name.find(')')? + 2,
+        None => 0,
+    };
+    let spec = &rest[name..];
+    let flags = spec.find(|c: char| !c.is_ascii_digit() && !"$-+#0'.*hlLqjzt".contains(c))?;
+    let conversion = spec[flags..].chars().next()?;
+    "diouxXeEfFgGaAcspn%@"
+        .contains(conversion)
+        .then_some(start + 1 + name + flags + 1)
+}
+
+/// Whether the text runs of `pieces` have braces, which [`unmask`] would
+/// take for markers.
+fn has_stray_braces(pieces: &[Piece<String>]) -> bool {
+    pieces
+        .iter()
+        .any(|piece| matches!(piece, Piece::Text(text) if text.contains(['{', '}'])))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::UppercaseBackend;
+
+    fn kept(text: &str, printf: bool) -> Vec<String> {
+        placeholders(text, printf)
+            .into_iter()
+            .filter_map(|piece| match piece {
+                Piece::Kept(kept) => Some(kept),
+                Piece::Text(_) => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_placeholders() {
+        assert_eq!(
+            kept("{{count}} items for {name}", false),
+            ["{{count}}", "{name}"]
+        );
+        assert_eq!(
+            kept("{n, plural, one {# file} other {# files}} left", false),
+            ["{n, plural, one {# file} other {# files}}"]
+        );
+        assert_eq!(kept("%{count} of %d", false), ["%{count}"]);
+        assert_eq!(
+            kept("%d of %1$s, %(name)s at 100%%", true),
+            ["%d", "%1$s", "%(name)s", "%%"]
+        );
+        assert!(kept("100% sure {", true).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_translate_messages_keeps_placeholders() {
+        let translator = Translator::with_backend("en", "fr", UppercaseBackend::default()).unwrap();
+        let texts = [
+            (placeholders("%d file in {dir}", true), None),
+            (placeholders("{count} left }", false), None),
+        ];
+        let translations = translate_messages(&translator, &texts).await.unwrap();
+        assert_eq!(translations, ["%d FILE IN {dir}", "{count} LEFT }"]);
+    }
+}
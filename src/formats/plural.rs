@@ -0,0 +1,311 @@
+//! Plural rules: gettext's `Plural-Forms` header and CLDR's categories.
+
+use crate::Language;
+use std::fmt;
+
+/// How many plural forms a language has, and which one a number takes, as
+/// in `nplurals=2; plural=(n != 1);`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PluralForms {
+    nplurals: usize,
+    plural: Expr,
+    /// The `plural` expression as written.
+    source: String,
+}
+
+impl PluralForms {
+    /// Parses the value of a `Plural-Forms` header. Returns `None` for the
+    /// `nplurals=INTEGER; plural=EXPRESSION;` placeholder of templates.
+    pub fn parse(value: &str) -> Option<Self> {
+        let mut nplurals = None;
+        let mut plural = None;
+        for part in value.split(';') {
+            match part.split_once('=') {
+                Some((key, value)) if key.trim() == "nplurals" => {
+                    nplurals = value.trim().parse().ok().filter(|&n| n > 0);
+                }
+                Some((key, value)) if key.trim() == "plural" => plural = Some(value.trim()),
+                _ => {}
+            }
+        }
+
+        let source = plural?;
+        let mut parser = Parser {
+            input: source.as_bytes(),
+            position: 0,
+        };
+        let expr = parser.conditional()?;
+        parser.skip_whitespace();
+        if parser.position != source.len() {
+            return None;
+        }
+        Some(Self {
+            nplurals: nplurals?,
+            plural: expr,
+            source: source.to_owned(),
+        })
+    }
+
+    /// The rules gettext documents for `language`, defaulting to separate
+    /// forms for one and for everything else.
+    pub fn for_language(language: &Language) -> Self {
+        let rule = match (language.code(), language.region()) {
+            ("pt", Some("BR")) => "nplurals=2; plural=(n > 1);",
+            (
+                "ay" | "bo" | "dz" | "id" | "ja" | "jbo" | "ka" | "km" | "ko" | "lo" | "ms" | "my"
+                | "su" | "th" | "ug" | "vi" | "wo" | "zh",
+                _,
+            ) => "nplurals=1; plural=0;",
+            ("am" | "br" | "fil" | "fr" | "ln" | "mg" | "mi" | "oc" | "tg" | "ti" | "tl", _) => {
+                "nplurals=2; plural=(n > 1);"
+            }
+            ("is", _) => "nplurals=2; plural=(n%10!=1 || n%100==11);",
+            ("be" | "bs" | "hr" | "ru" | "sr" | "uk", _) => {
+                "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : \
+                 n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);"
+            }
+            ("cs" | "sk", _) => "nplurals=3; plural=(n==1) ? 0 : (n>=2 && n<=4) ? 1 : 2;",
+            ("pl", _) => {
+                "nplurals=3; plural=(n==1 ? 0 : \
+                 n%10>=2 && n%10<=4 && (n%100<10 || n%100>=20) ? 1 : 2);"
+            }
+            ("lt", _) => {
+                "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : \
+                 n%10>=2 && (n%100<10 || n%100>=20) ? 1 : 2);"
+            }
+            ("lv", _) => "nplurals=3; plural=(n%10==1 && n%100!=11 ? 0 : n != 0 ? 1 : 2);",
+            ("ro", _) => {
+                "nplurals=3; plural=(n==1 ? 0 : (n==0 || (n%100 > 0 && n%100 < 20)) ? 1 : 2);"
+            }
+            ("sl", _) => {
+                "nplurals=4; plural=(n%100==1 ? 0 : n%100==2 ? 1 : n%100==3 || n%100==4 ? 2 : 3);"
+            }
+            ("cy", _) => {
+                "nplurals=4; plural=(n==1) ? 0 : (n==2) ? 1 : (n != 8 && n != 11) ? 2 : 3;"
+            }
+            ("ga", _) => "nplurals=5; plural=(n==1 ? 0 : n==2 ? 1 : n<7 ? 2 : n<11 ? 3 : 4);",
+            ("ar", _) => {
+                "nplurals=6; plural=(n==0 ? 0 : n==1 ? 1 : n==2 ? 2 : \
+                 n%100>=3 && n%100<=10 ? 3 : n%100>=11 ? 4 : 5);"
+            }
+            _ => "nplurals=2; plural=(n != 1);",
+        };
+        Self::parse(rule).expect("built-in plural rules are valid")
+    }
+
+    pub fn nplurals(&self) -> usize {
+        self.nplurals
+    }
+
+    /// The index of the form used for `n`.
+    pub fn index(&self, n: u64) -> usize {
+        (self.plural.eval(n) as usize).min(self.nplurals - 1)
+    }
+}
+
+impl fmt::Display for PluralForms {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "nplurals={}; plural={};", self.nplurals, self.source)
+    }
+}
+
+/// The CLDR plural categories of cardinal numbers in `language`, in CLDR's
+/// order, defaulting to `one` and `other`.
+pub fn plural_categories(language: &Language) -> &'static [&'static str] {
+    match language.code() {
+        "bo" | "dz" | "id" | "ig" | "ja" | "jv" | "km" | "ko" | "lo" | "ms" | "my" | "sg"
+        | "su" | "th" | "to" | "vi" | "wo" | "yo" | "yue" | "zh" => &["other"],
+        "ca" | "es" | "fr" | "it" | "pt" => &["one", "many", "other"],
+        "bs" | "hr" | "ro" | "sr" => &["one", "few", "other"],
+        "be" | "cs" | "lt" | "pl" | "ru" | "sk" | "uk" => &["one", "few", "many", "other"],
+        "he" => &["one", "two", "other"],
+        "sl" => &["one", "two", "few", "other"],
+        "lv" => &["zero", "one", "other"],
+        "ga" | "mt" => &["one", "two", "few", "many", "other"],
+        "ar" | "cy" => &["zero", "one", "two", "few", "many", "other"],
+        _ => &["one", "other"],
+    }
+}
+
+/// A C expression over `n`, as allowed in `plural=`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Expr {
+    N,
+    Number(u64),
+    Not(Box<Expr>),
+    Binary(Box<Expr>, &'static str, Box<Expr>),
+    Conditional(Box<Expr>, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    fn eval(&self, n: u64) -> u64 {
+        match self {
+            Expr::N => n,
+            Expr::Number(value) => *value,
+            Expr::Not(expr) => u64::from(expr.eval(n) == 0),
+            Expr::Conditional(condition, then, otherwise) => match condition.eval(n) {
+                0 => otherwise.eval(n),
+                _ => then.eval(n),
+            },
+            Expr::Binary(left, operator, right) => {
+                let (a, b) = (left.eval(n), right.eval(n));
+                match *operator {
+                    "||" => u64::from(a != 0 || b != 0),
+                    "&&" => u64::from(a != 0 && b != 0),
+                    "==" => u64::from(a == b),
+                    "!=" => u64::from(a != b),
+                    "<" => u64::from(a < b),
+                    ">" => u64::from(a > b),
+                    "<=" => u64::from(a <= b),
+                    ">=" => u64::from(a >= b),
+                    "+" => a.wrapping_add(b),
+                    "-" => a.wrapping_sub(b),
+                    "*" => a.wrapping_mul(b),
+                    "/" => a.checked_div(b).unwrap_or(0),
+                    "%" => a.checked_rem(b).unwrap_or(0),
+                    _ => unreachable!("unknown operator {}", operator),
+                }
+            }
+        }
+    }
+}
+
+/// Binary operators from the loosest to the tightest binding.
+const PRECEDENCE: &[&[&str]] = &[
+    &["||"],
+    &["&&"],
+    &["==", "!="],
+    &["<=", ">=", "<", ">"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+/// A recursive descent parser for plural expressions.
+struct Parser<'a> {
+    input: &'a [u8],
+    position: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self
+            .input
+            .get(self.position)
+            .is_some_and(u8::is_ascii_whitespace)
+        {
+            self.position += 1;
+        }
+    }
+
+    /// Consumes `token` if it comes next.
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_whitespace();
+        let matches = self.input[self.position..].starts_with(token.as_bytes());
+        if matches {
+            self.position += token.len();
+        }
+        matches
+    }
+
+    fn conditional(&mut self) -> Option<Expr> {
+        let condition = self.binary(0)?;
+        if !self.eat("?") {
+            return Some(condition);
+        }
+        let then = self.conditional()?;
+        if !self.eat(":") {
+            return None;
+        }
+        let otherwise = self.conditional()?;
+        Some(Expr::Conditional(
+            Box::new(condition),
+            Box::new(then),
+            Box::new(otherwise),
+        ))
+    }
+
+    fn binary(&mut self, level: usize) -> Option<Expr> {
+        let Some(operators) = PRECEDENCE.get(level) else {
+            return self.unary();
+        };
+        let mut left = self.binary(level + 1)?;
+        'outer: loop {
+            for operator in *operators {
+                // `!=` must not be read as `!`, nor `<=` as `<`.
+                if self.eat(operator) {
+                    let right = self.binary(level + 1)?;
+                    left = Expr::Binary(Box::new(left), operator, Box::new(right));
+                    continue 'outer;
+                }
+            }
+            return Some(left);
+        }
+    }
+
+    fn unary(&mut self) -> Option<Expr> {
+        if self.eat("!") {
+            return Some(Expr::Not(Box::new(self.unary()?)));
+        }
+        if self.eat("(") {
+            let expr = self.conditional()?;
+            return self.eat(")").then_some(expr);
+        }
+        if self.eat("n") {
+            return Some(Expr::N);
+        }
+        let start = self.position;
+        while self
+            .input
+            .get(self.position)
+            .is_some_and(u8::is_ascii_digit)
+        {
+            self.position += 1;
+        }
+        std::str::from_utf8(&self.input[start..self.position])
+            .ok()?
+            .parse()
+            .ok()
+            .map(Expr::Number)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn indices(forms: &PluralForms, numbers: &[u64]) -> Vec<usize> {
+        numbers.iter().map(|&n| forms.index(n)).collect()
+    }
+
+    #[test]
+    fn test_plural_forms() {
+        let language = |tag| Language::parse(tag).unwrap();
+        let numbers = [0, 1, 2, 5, 11, 21, 22, 25, 101, 111];
+
+        let russian = PluralForms::for_language(&language("ru"));
+        assert_eq!(russian.nplurals(), 3);
+        assert_eq!(indices(&russian, &numbers), [2, 0, 1, 2, 2, 0, 1, 2, 0, 2]);
+        let french = PluralForms::for_language(&language("fr"));
+        assert_eq!(indices(&french, &numbers[..3]), [0, 0, 1]);
+        let english = PluralForms::for_language(&language("en"));
+        assert_eq!(english.to_string(), "nplurals=2; plural=(n != 1);");
+
+        let arabic = PluralForms::for_language(&language("ar"));
+        assert_eq!(indices(&arabic, &numbers), [0, 1, 2, 3, 4, 4, 4, 4, 5, 4]);
+
+        assert_eq!(
+            PluralForms::parse("nplurals=INTEGER; plural=EXPRESSION;"),
+            None
+        );
+        assert_eq!(PluralForms::parse("nplurals=2; plural=(n != 1"), None);
+        let custom = PluralForms::parse(" nplurals=2;plural=n>=2&&!(n%2);").unwrap();
+        assert_eq!(indices(&custom, &[1, 2, 3, 4]), [0, 1, 0, 1]);
+
+        assert_eq!(plural_categories(&language("ja")), ["other"]);
+        assert_eq!(
+            plural_categories(&language("pl")),
+            ["one", "few", "many", "other"]
+        );
+        assert_eq!(plural_categories(&language("en-GB")), ["one", "other"]);
+    }
+}
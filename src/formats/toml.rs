@@ -0,0 +1,587 @@
+//! TOML locale files.
+//!
+//! [`TomlDocument`] finds the strings of a file, in tables, arrays and
+//! inline tables, and rewrites only those it translated, in the quoting
+//! they were written in where the translation allows it. Comments, key
+//! order and every other value stay as they were.
+
+use super::{line_ending, translate_values, KeyFilter, SyntaxError};
+use crate::{TranslationError, Translator};
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+
+/// How a string is quoted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Style {
+    /// `"..."`
+    Basic,
+    /// `'...'`
+    Literal,
+    /// `"""..."""`, with whether a line break follows the opening quotes.
+    MultilineBasic(bool),
+    /// `'''...'''`, with whether a line break follows the opening quotes.
+    MultilineLiteral(bool),
+}
+
+/// A string value of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TomlString {
+    pub value: String,
+    path: Vec<String>,
+    /// The value as read, with the byte range of its literal and its style.
+    original: String,
+    span: Range<usize>,
+    style: Style,
+}
+
+impl TomlString {
+    /// The keys leading to the value from the root; array elements,
+    /// including the tables of an array of tables, are named by their index.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// The path joined with dots, as in `home.title`.
+    pub fn key(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// The value quoted as it was, or in basic quotes when that quoting
+    /// cannot hold it.
+    fn write(&self, newline: &str) -> String {
+        let value = &self.value;
+        let control = |c: char| c.is_control() && c != '\t';
+        match self.style {
+            Style::Literal if !value.contains(|c| c == '\'' || control(c)) => {
+                format!("'{}'", value)
+            }
+            Style::MultilineLiteral(leading)
+                if !value.contains("'''")
+                    && !value.ends_with('\'')
+                    && !value.contains(|c| c != '\n' && control(c)) =>
+            {
+                let leading = if leading { newline } else { "" };
+                format!("'''{}{}'''", leading, value.replace('\n', newline))
+            }
+            Style::MultilineBasic(leading) | Style::MultilineLiteral(leading) => {
+                let leading = if leading { newline } else { "" };
+                let mut escaped = String::new();
+                for c in value.chars() {
+                    match c {
+                        '\n' => escaped.push_str(newline),
+                        // Escaping every quote keeps `"""` out of the text.
+                        '"' => escaped.push_str("\\\""),
+                        c => escape(c, &mut escaped),
+                    }
+                }
+                format!("\"\"\"{}{}\"\"\"", leading, escaped)
+            }
+            _ => {
+                let mut escaped = String::new();
+                for c in value.chars() {
+                    match c {
+                        '"' => escaped.push_str("\\\""),
+                        c => escape(c, &mut escaped),
+                    }
+                }
+                format!("\"{}\"", escaped)
+            }
+        }
+    }
+}
+
+/// Writes `c` as basic strings need it, escaping backslashes and control
+/// characters.
+fn escape(c: char, escaped: &mut String) {
+    match c {
+        '\\' => escaped.push_str("\\\\"),
+        '\n' => escaped.push_str("\\n"),
+        '\r' => escaped.push_str("\\r"),
+        c if c.is_control() && c != '\t' => escaped.push_str(&format!("\\u{:04X}", c as u32)),
+        c => escaped.push(c),
+    }
+}
+
+/// A parsed TOML file.
+#[derive(Debug, Clone)]
+pub struct TomlDocument {
+    text: String,
+    strings: Vec<TomlString>,
+    /// The line ending used by the file.
+    newline: &'static str,
+}
+
+impl TomlDocument {
+    pub fn parse(text: &str) -> Result<Self, SyntaxError> {
+        let mut parser = Parser {
+            text,
+            position: 0,
+            arrays: HashMap::new(),
+            strings: Vec::new(),
+        };
+        parser.parse()?;
+        Ok(Self {
+            text: text.to_owned(),
+            strings: parser.strings,
+            newline: line_ending(text),
+        })
+    }
+
+    /// The string values, in the order they appear.
+    pub fn strings(&self) -> impl Iterator<Item = &TomlString> {
+        self.strings.iter()
+    }
+
+    pub fn strings_mut(&mut self) -> impl Iterator<Item = &mut TomlString> {
+        self.strings.iter_mut()
+    }
+
+    /// Translates the strings whose keys `keys` selects with `translator`.
+    /// Returns the number of strings translated.
+    pub async fn translate(
+        &mut self,
+        translator: &Translator,
+        keys: &KeyFilter,
+    ) -> Result<usize, TranslationError> {
+        let values = self
+            .strings
+            .iter_mut()
+            .map(|string| (string.path.as_slice(), &mut string.value));
+        translate_values(translator, keys, values).await
+    }
+}
+
+impl fmt::Display for TomlDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut position = 0;
+        for string in self.strings.iter().filter(|s| s.value != s.original) {
+            f.write_str(&self.text[position..string.span.start])?;
+            f.write_str(&string.write(self.newline))?;
+            position = string.span.end;
+        }
+        f.write_str(&self.text[position..])
+    }
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+    /// The number of tables in each array of tables seen so far.
+    arrays: HashMap<Vec<String>, usize>,
+    strings: Vec<TomlString>,
+}
+
+impl Parser<'_> {
+    fn parse(&mut self) -> Result<(), SyntaxError> {
+        let mut table = Vec::new();
+        loop {
+            self.skip_space(true);
+            match self.peek() {
+                None => return Ok(()),
+                Some('[') if self.text[self.position..].starts_with("[[") => {
+                    self.position += 2;
+                    let keys = self.keys()?;
+                    self.expect("]]")?;
+                    let (last, parents) = keys.split_last().expect("keys are never empty");
+                    let mut array = self.resolve(parents);
+                    array.push(last.clone());
+                    let count = self.arrays.entry(array.clone()).or_insert(0);
+                    array.push(count.to_string());
+                    *count += 1;
+                    table = array;
+                }
+                Some('[') => {
+                    self.position += 1;
+                    let keys = self.keys()?;
+                    self.expect("]")?;
+                    table = self.resolve(&keys);
+                }
+                Some(_) => {
+                    let mut path = table.clone();
+                    path.extend(self.keys()?);
+                    self.skip_space(false);
+                    self.expect("=")?;
+                    self.value(&mut path)?;
+                }
+            }
+            self.skip_space(false);
+            match self.peek() {
+                None | Some('\n') => {}
+                Some('\r') if self.text[self.position..].starts_with("\r\n") => {}
+                _ => return Err(self.error("expected the end of the line")),
+            }
+        }
+    }
+
+    /// The path of the table `keys` names, in which arrays of tables stand
+    /// for their last table.
+    fn resolve(&self, keys: &[String]) -> Vec<String> {
+        let mut path = Vec::new();
+        for key in keys {
+            path.push(key.clone());
+            if let Some(count) = self.arrays.get(&path) {
+                path.push((count - 1).to_string());
+            }
+        }
+        path
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn line(&self) -> usize {
+        self.text[..self.position].matches('\n').count() + 1
+    }
+
+    fn error(&self, message: &str) -> SyntaxError {
+        SyntaxError::new(self.line(), message)
+    }
+
+    fn expect(&mut self, token: &str) -> Result<(), SyntaxError> {
+        self.skip_space(false);
+        if !self.text[self.position..].starts_with(token) {
+            return Err(self.error(&format!("expected `{}`", token)));
+        }
+        self.position += token.len();
+        Ok(())
+    }
+
+    /// Skips spaces and comments, and line breaks too when `newlines` is
+    /// set.
+    fn skip_space(&mut self, newlines: bool) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t') => self.position += 1,
+                Some('\r' | '\n') if newlines => self.position += 1,
+                Some('#') => {
+                    let rest = &self.text[self.position..];
+                    let end = rest.find(['\r', '\n']).unwrap_or(rest.len());
+                    self.position += end;
+                }
+                _ => return,
+            }
+        }
+    }
+
+    /// Reads a key, which may be dotted.
+    fn keys(&mut self) -> Result<Vec<String>, SyntaxError> {
+        let mut keys = Vec::new();
+        loop {
+            self.skip_space(false);
+            let key = match self.peek() {
+                Some('"' | '\'') => self.string()?.2,
+                _ => {
+                    let rest = &self.text[self.position..];
+                    let length = rest
+                        .find(|c: char| !c.is_ascii_alphanumeric() && c != '_' && c != '-')
+                        .unwrap_or(rest.len());
+                    if length == 0 {
+                        return Err(self.error("expected a key"));
+                    }
+                    self.position += length;
+                    rest[..length].to_owned()
+                }
+            };
+            keys.push(key);
+            self.skip_space(false);
+            if self.peek() != Some('.') {
+                return Ok(keys);
+            }
+            self.position += 1;
+        }
+    }
+
+    /// Parses the value of the key at `path`.
+    fn value(&mut self, path: &mut Vec<String>) -> Result<(), SyntaxError> {
+        self.skip_space(false);
+        match self.peek() {
+            Some('"' | '\'') => {
+                let (span, style, value) = self.string()?;
+                self.strings.push(TomlString {
+                    path: path.clone(),
+                    original: value.clone(),
+                    value,
+                    span,
+                    style,
+                });
+            }
+            Some('[') => {
+                self.position += 1;
+                let mut index = 0;
+                loop {
+                    self.skip_space(true);
+                    match self.peek() {
+                        Some(']') => break,
+                        Some(',') => self.position += 1,
+                        None => return Err(self.error("unterminated array")),
+                        Some(_) => {
+                            path.push(index.to_string());
+                            self.value(path)?;
+                            path.pop();
+                            index += 1;
+                        }
+                    }
+                }
+                self.position += 1;
+            }
+            Some('{') => {
+                self.position += 1;
+                loop {
+                    self.skip_space(false);
+                    match self.peek() {
+                        Some('}') => break,
+                        Some(',') => self.position += 1,
+                        None | Some('\r' | '\n') => {
+                            return Err(self.error("unterminated inline table"))
+                        }
+                        Some(_) => {
+                            let keys = self.keys()?;
+                            self.expect("=")?;
+                            let length = path.len();
+                            path.extend(keys);
+                            self.value(path)?;
+                            path.truncate(length);
+                        }
+                    }
+                }
+                self.position += 1;
+            }
+            // Numbers, booleans and dates, which may hold a space between
+            // the date and the time.
+            _ => {
+                let rest = &self.text[self.position..];
+                let mut length = rest
+                    .find([',', ']', '}', '#', '\r', '\n', ' ', '\t'])
+                    .unwrap_or(rest.len());
+                if rest[length..].starts_with(' ')
+                    && rest[length + 1..].starts_with(|c: char| c.is_ascii_digit())
+                    && rest[..length].contains('-')
+                {
+                    length += 1 + rest[length + 1..]
+                        .find([',', ']', '}', '#', '\r', '\n', ' ', '\t'])
+                        .unwrap_or(rest.len() - length - 1);
+                }
+                if length == 0 {
+                    return Err(self.error("expected a value"));
+                }
+                self.position += length;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads a string in any of the four quotings, returning its range,
+    /// style and value.
+    fn string(&mut self) -> Result<(Range<usize>, Style, String), SyntaxError> {
+        let start = self.position;
+        let rest = &self.text[start..];
+        let (style, quotes) = if rest.starts_with("\"\"\"") {
+            (Style::MultilineBasic(false), "\"\"\"")
+        } else if rest.starts_with("'''") {
+            (Style::MultilineLiteral(false), "'''")
+        } else if rest.starts_with('"') {
+            (Style::Basic, "\"")
+        } else {
+            (Style::Literal, "'")
+        };
+        let basic = matches!(style, Style::Basic | Style::MultilineBasic(_));
+        let multiline = quotes.len() == 3;
+        self.position += quotes.len();
+
+        let body_start = self.position;
+        let body_end = loop {
+            let rest = &self.text[self.position..];
+            match rest.chars().next() {
+                None => break None,
+                Some('\\') if basic => {
+                    self.position += 1 + rest[1..].chars().next().map_or(0, char::len_utf8);
+                }
+                Some('\n') if !multiline => break None,
+                Some(_) if rest.starts_with(quotes) => {
+                    // Up to two quotes before the closing ones belong to the
+                    // string.
+                    let quote = quotes.as_bytes()[0];
+                    let mut end = self.position;
+                    while multiline
+                        && end < self.position + 2
+                        && self.text.as_bytes().get(end + 3) == Some(&quote)
+                    {
+                        end += 1;
+                    }
+                    self.position = end + quotes.len();
+                    break Some(end);
+                }
+                Some(c) => self.position += c.len_utf8(),
+            }
+        };
+        let Some(body_end) = body_end else {
+            self.position = start;
+            return Err(self.error("unterminated string"));
+        };
+
+        let mut body = &self.text[body_start..body_end];
+        let mut leading = false;
+        if multiline {
+            for newline in ["\r\n", "\n"] {
+                if let Some(rest) = body.strip_prefix(newline) {
+                    body = rest;
+                    leading = true;
+                    break;
+                }
+            }
+        }
+        let style = match style {
+            Style::MultilineBasic(_) => Style::MultilineBasic(leading),
+            Style::MultilineLiteral(_) => Style::MultilineLiteral(leading),
+            style => style,
+        };
+        let value = match basic {
+            true => unescape(body).map_err(|message| {
+                self.position = start;
+                self.error(message)
+            })?,
+            false => body.replace("\r\n", "\n"),
+        };
+        Ok((start..self.position, style, value))
+    }
+}
+
+/// Replaces the escape sequences of a basic string, and removes the line
+/// breaks and whitespace after line ending backslashes.
+fn unescape(body: &str) -> Result<String, &'static str> {
+    let mut value = String::new();
+    let mut chars = body.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => {}
+            '\r' if chars.peek() == Some(&'\n') => continue,
+            c => {
+                value.push(c);
+                continue;
+            }
+        }
+        let escaped = match chars.next().ok_or("unfinished escape sequence")? {
+            'b' => '\x08',
+            't' => '\t',
+            'n' => '\n',
+            'f' => '\x0c',
+            'r' => '\r',
+            'e' => '\x1b',
+            '"' => '"',
+            '\\' => '\\',
+            digits @ ('u' | 'U') => {
+                let length = if digits == 'u' { 4 } else { 8 };
+                let hex: String = chars.by_ref().take(length).collect();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == length)
+                    .and_then(char::from_u32)
+                    .ok_or("invalid escape sequence")?
+            }
+            c if c.is_whitespace() => {
+                while chars.next_if(|c| c.is_whitespace()).is_some() {}
+                continue;
+            }
+            _ => return Err("invalid escape sequence"),
+        };
+        value.push(escaped);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::UppercaseBackend;
+
+    const SOURCE: &str = r#"# Locale for the settings screen
+title = "Settings" # shown in the window title
+version = 2
+updated = 1979-05-27 07:32:00Z
+
+[menu]
+save = 'Save'
+"quit.label" = "Quit \"now\""
+hint = """
+Press a key
+to continue"""
+path = 'C:\Users'
+days = [
+  "Monday", # first
+  "Tuesday",
+]
+button = { label = "OK", width = 80 }
+
+[[tips]]
+text = '''It's easy'''
+
+[[tips]]
+text = "Second"
+"#;
+
+    #[test]
+    fn test_document_round_trips() {
+        let document = TomlDocument::parse(SOURCE).unwrap();
+        assert_eq!(document.to_string(), SOURCE);
+        let values: Vec<_> = document
+            .strings()
+            .map(|string| (string.key(), string.value.as_str()))
+            .collect();
+        let expected = [
+            ("title", "Settings"),
+            ("menu.save", "Save"),
+            ("menu.quit.label", "Quit \"now\""),
+            ("menu.hint", "Press a key\nto continue"),
+            ("menu.path", "C:\\Users"),
+            ("menu.days.0", "Monday"),
+            ("menu.days.1", "Tuesday"),
+            ("menu.button.label", "OK"),
+            ("tips.0.text", "It's easy"),
+            ("tips.1.text", "Second"),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(key, value)| (key.to_string(), *value))
+            .collect();
+        assert_eq!(values, expected);
+        assert_eq!(
+            document.strings().nth(2).unwrap().path(),
+            ["menu", "quit.label"]
+        );
+
+        let error = TomlDocument::parse("a = 1\nb = \"open\n").unwrap_err();
+        assert_eq!(error.line, 2);
+        let error = TomlDocument::parse("a = 1 b = 2\n").unwrap_err();
+        assert_eq!(error.line, 1);
+    }
+
+    #[tokio::test]
+    async fn test_translate_document() {
+        let translator = Translator::with_backend("en", "fr", UppercaseBackend::default()).unwrap();
+        let mut document = TomlDocument::parse(SOURCE).unwrap();
+        let keys = KeyFilter::new().with_exclude("menu.path");
+        assert_eq!(document.translate(&translator, &keys).await.unwrap(), 9);
+        let translated = SOURCE
+            .replace("\"Settings\"", "\"SETTINGS\"")
+            .replace("'Save'", "'SAVE'")
+            .replace("Quit \\\"now\\\"", "QUIT \\\"NOW\\\"")
+            .replace("Press a key\nto continue", "PRESS A KEY\nTO CONTINUE")
+            .replace("\"Monday\"", "\"MONDAY\"")
+            .replace("\"Tuesday\"", "\"TUESDAY\"")
+            .replace("'''It's easy'''", "'''IT'S EASY'''")
+            .replace("\"Second\"", "\"SECOND\"");
+        assert_eq!(document.to_string(), translated);
+
+        // Literal strings cannot hold quotes, nor basic ones raw breaks.
+        let mut document = TomlDocument::parse("a = 'x'\nb = \"y\"\n").unwrap();
+        for string in document.strings_mut() {
+            string.value = "it's\n\"done\"".to_owned();
+        }
+        assert_eq!(
+            document.to_string(),
+            "a = \"it's\\n\\\"done\\\"\"\nb = \"it's\\n\\\"done\\\"\"\n"
+        );
+    }
+}
@@ -0,0 +1,1067 @@
+//! XLIFF 1.2 and 2.x files, as exchanged with translation agencies and CAT
+//! tools.
+//!
+//! [`XliffDocument`] reads the segments of `<trans-unit>` (1.2) and
+//! `<unit>` (2.x) elements, and its `Display` writes the file as it was
+//! read apart from the targets, states, notes and target language it
+//! changed.
+
+use super::{keep_spacing, mask, translate_texts, unmask, Piece, SyntaxError};
+use crate::{TranslationError, Translator};
+use std::fmt;
+use std::ops::Range;
+
+/// Inline elements whose content is code rather than text: XLIFF 1.2's
+/// native codes. They are kept whole, like empty elements.
+const CODES: &[&str] = &["bpt", "ept", "it", "ph"];
+
+/// The state of machine translations, which XLIFF 1.2 gives targets and
+/// 2.x, which has no such state, gives segments as a sub-state.
+const NEEDS_REVIEW: &str = "needs-review-translation";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Version {
+    V1,
+    V2,
+}
+
+/// A source text and its translation.
+#[derive(Debug, Clone)]
+pub struct Segment {
+    /// The content of the `<target>` element, inline tags included, as
+    /// XML.
+    pub target: Option<String>,
+    id: String,
+    unit: usize,
+    translate: bool,
+    source: String,
+    inline: Vec<Inline>,
+    original: Option<String>,
+    /// The `<target>` element as read, with its start tag.
+    target_element: Option<(Range<usize>, Tag)>,
+    /// Where a new `<target>` goes, and the whitespace before `<source>`.
+    insert_at: usize,
+    indent: String,
+    /// The `<segment>` start tag of XLIFF 2.x, which holds the state.
+    segment_tag: Option<Tag>,
+    machine: bool,
+}
+
+impl Segment {
+    /// The `id` of the unit.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    /// The content of the `<source>` element, as XML.
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Whether the segment may be translated, which `translate="no"` on
+    /// its unit or an enclosing element forbids.
+    pub fn is_translatable(&self) -> bool {
+        self.translate
+    }
+
+    /// Whether the segment is translatable, has text and no target yet.
+    pub fn needs_translation(&self) -> bool {
+        self.translate
+            && self.inline.iter().any(|inline| match inline {
+                Piece::Text(text) => !text.trim().is_empty(),
+                Piece::Kept(_) => false,
+            })
+            && self
+                .target
+                .as_deref()
+                .is_none_or(|target| target.trim().is_empty())
+    }
+}
+
+/// Where the note of a unit's machine translations goes.
+#[derive(Debug, Clone)]
+struct Unit {
+    note_at: usize,
+    /// The whitespace before the note.
+    indent: String,
+    /// The whitespace before the `<notes>` element that 2.x units without
+    /// notes need.
+    wrap: Option<String>,
+}
+
+/// A parsed XLIFF file.
+#[derive(Debug, Clone)]
+pub struct XliffDocument {
+    text: String,
+    version: Version,
+    source_language: Option<String>,
+    target_language: Option<String>,
+    /// The start tags that lack the target language.
+    language_tags: Vec<Tag>,
+    language_added: bool,
+    segments: Vec<Segment>,
+    units: Vec<Unit>,
+    /// The text of the notes on machine translations.
+    note: String,
+}
+
+impl XliffDocument {
+    pub fn parse(text: &str) -> Result<Self, SyntaxError> {
+        let mut parser = Parser {
+            text,
+            version: None,
+            source_language: None,
+            target_language: None,
+            language_tags: Vec::new(),
+            stack: Vec::new(),
+            unit: None,
+            segment: None,
+            segments: Vec::new(),
+            units: Vec::new(),
+        };
+        let mut tokens = Tokenizer {
+            text,
+            position: 0,
+            end: text.len(),
+        };
+        // The whitespace before the current token.
+        let mut whitespace = 0..0;
+        while let Some((range, token)) = tokens.next()? {
+            match token {
+                Token::Text(_) if text[range.clone()].trim().is_empty() => {
+                    whitespace = range;
+                    continue;
+                }
+                Token::Start(tag) => {
+                    let indent = text[whitespace].to_owned();
+                    parser.start(tag, indent)?;
+                }
+                Token::End(name) => {
+                    let element = parser
+                        .stack
+                        .pop()
+                        .ok_or_else(|| error(text, range.start, "unexpected end tag"))?;
+                    if element.tag.name != name {
+                        let message = format!("expected </{}>", element.tag.name);
+                        return Err(error(text, range.start, message));
+                    }
+                    parser.end(element, range.clone(), whitespace.start)?;
+                }
+                _ => {}
+            }
+            whitespace = range.end..range.end;
+        }
+        if let Some(element) = parser.stack.last() {
+            let message = format!("unclosed <{}>", element.tag.name);
+            return Err(error(text, element.tag.range.start, message));
+        }
+        let Some(version) = parser.version else {
+            return Err(error(text, text.len(), "expected an <xliff> element"));
+        };
+        Ok(Self {
+            text: text.to_owned(),
+            version,
+            source_language: parser.source_language,
+            target_language: parser.target_language,
+            language_tags: parser.language_tags,
+            language_added: false,
+            segments: parser.segments,
+            units: parser.units,
+            note: String::new(),
+        })
+    }
+
+    /// The source language of the (first) file.
+    pub fn source_language(&self) -> Option<&str> {
+        self.source_language.as_deref()
+    }
+
+    pub fn target_language(&self) -> Option<&str> {
+        self.target_language.as_deref()
+    }
+
+    /// The segments, in the order they appear.
+    pub fn segments(&self) -> impl Iterator<Item = &Segment> {
+        self.segments.iter()
+    }
+
+    pub fn segments_mut(&mut self) -> impl Iterator<Item = &mut Segment> {
+        self.segments.iter_mut()
+    }
+
+    /// Translates the segments that need it with `translator`, keeping
+    /// their inline tags. Their state becomes `needs-review-translation`
+    /// and their units get a note saying they were machine translated.
+    /// A file without a target language gets the translator's. Returns
+    /// the number of segments translated.
+    ///
+    /// Each segment is translated as a whole, with its inline tags masked;
+    /// when the backend loses one, or the text has braces that masking
+    /// would confuse, its text runs are translated one by one instead.
+    pub async fn translate(&mut self, translator: &Translator) -> Result<usize, TranslationError> {
+        let pending: Vec<usize> = (0..self.segments.len())
+            .filter(|&index| self.segments[index].needs_translation())
+            .collect();
+        let whole: Vec<_> = pending
+            .iter()
+            .map(|&index| &self.segments[index].inline)
+            .filter(|inline| !inline.iter().any(has_braces))
+            .map(|inline| (mask(inline), None))
+            .collect();
+        let mut translations = translate_texts(translator, &whole).await?.into_iter();
+        let results: Vec<Option<Vec<Inline>>> = pending
+            .iter()
+            .map(|&index| {
+                let inline = &self.segments[index].inline;
+                if inline.iter().any(has_braces) {
+                    return None;
+                }
+                let translation = translations.next().expect("one translation per text");
+                unmask(inline, &translation, nests)
+            })
+            .collect();
+
+        let runs: Vec<_> = pending
+            .iter()
+            .zip(&results)
+            .filter(|(_, result)| result.is_none())
+            .flat_map(|(&index, _)| texts(&self.segments[index].inline))
+            .collect();
+        let mut run_translations = translate_texts(translator, &runs).await?.into_iter();
+        for (&index, result) in pending.iter().zip(results) {
+            let segment = &mut self.segments[index];
+            let inline = result.unwrap_or_else(|| {
+                segment
+                    .inline
+                    .iter()
+                    .map(|inline| match inline {
+                        Piece::Text(text) if !text.trim().is_empty() => {
+                            let translation =
+                                run_translations.next().expect("one translation per text");
+                            Piece::Text(keep_spacing(text, &translation))
+                        }
+                        inline => inline.clone(),
+                    })
+                    .collect()
+            });
+            segment.target = Some(to_xml(&inline));
+            segment.machine = true;
+        }
+        let count = pending.len();
+
+        if count > 0 {
+            self.note = format!(
+                "Machine translation ({}), to be reviewed",
+                translator.backend().name()
+            );
+        }
+        if self.target_language.is_none() {
+            self.target_language = Some(translator.target_lang().to_string());
+            self.language_added = !self.language_tags.is_empty();
+        }
+        Ok(count)
+    }
+}
+
+impl fmt::Display for XliffDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut edits: Vec<(Range<usize>, String)> = Vec::new();
+        if self.language_added {
+            let attribute = match self.version {
+                Version::V1 => "target-language",
+                Version::V2 => "trgLang",
+            };
+            let language = self.target_language.as_deref().unwrap_or_default();
+            for tag in &self.language_tags {
+                edits.push((tag.range.clone(), tag.write(&[(attribute, language)])));
+            }
+        }
+
+        for segment in &self.segments {
+            if segment.target != segment.original {
+                let state: &[(&str, &str)] = match (self.version, segment.machine) {
+                    (Version::V1, true) => &[("state", NEEDS_REVIEW)],
+                    _ => &[],
+                };
+                let new_tag;
+                let (range, tag, indent) = match &segment.target_element {
+                    Some((range, tag)) => (range.clone(), tag, ""),
+                    None => {
+                        new_tag = Tag::new("target");
+                        let at = segment.insert_at;
+                        (at..at, &new_tag, segment.indent.as_str())
+                    }
+                };
+                let element = match &segment.target {
+                    Some(target) => {
+                        format!("{}{}{}</{}>", indent, tag.write(state), target, tag.name)
+                    }
+                    None => String::new(),
+                };
+                edits.push((range, element));
+            }
+            if let (Some(tag), true) = (&segment.segment_tag, segment.machine) {
+                let sub_state = format!("rustranslate:{}", NEEDS_REVIEW);
+                let attributes = [("state", "translated"), ("subState", sub_state.as_str())];
+                edits.push((tag.range.clone(), tag.write(&attributes)));
+            }
+        }
+
+        for (index, unit) in self.units.iter().enumerate() {
+            let machine = self.segments.iter().any(|s| s.unit == index && s.machine);
+            if !machine {
+                continue;
+            }
+            let note = match self.version {
+                Version::V1 => format!(
+                    "{}<note from=\"rustranslate\" annotates=\"target\">{}</note>",
+                    unit.indent,
+                    escape(&self.note)
+                ),
+                Version::V2 => format!(
+                    "{}<note appliesTo=\"target\" category=\"machine-translation\">{}</note>",
+                    unit.indent,
+                    escape(&self.note)
+                ),
+            };
+            let note = match &unit.wrap {
+                Some(indent) => format!("{}<notes>{}{}</notes>", indent, note, indent),
+                None => note,
+            };
+            edits.push((unit.note_at..unit.note_at, note));
+        }
+
+        edits.sort_by_key(|(range, _)| range.start);
+        let mut position = 0;
+        for (range, replacement) in edits {
+            f.write_str(&self.text[position..range.start])?;
+            f.write_str(&replacement)?;
+            position = range.end;
+        }
+        f.write_str(&self.text[position..])
+    }
+}
+
+/// A piece of the content of a `<source>` or `<target>`: text, or an
+/// inline tag or code as written.
+type Inline = Piece<(String, Code)>;
+
+/// How an inline tag pairs with others; paired tags are numbered in the
+/// order they open.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Code {
+    Open(usize),
+    Close(usize),
+    Standalone,
+}
+
+fn has_braces(inline: &Inline) -> bool {
+    matches!(inline, Piece::Text(text) if text.contains(['{', '}']))
+}
+
+/// The text runs of `inline` that have text, to translate one by one.
+fn texts(inline: &[Inline]) -> Vec<(String, Option<String>)> {
+    inline
+        .iter()
+        .filter_map(|inline| match inline {
+            Piece::Text(text) if !text.trim().is_empty() => Some((text.clone(), None)),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Whether paired tags still nest in the order a translation put them.
+fn nests(tags: &[&(String, Code)]) -> bool {
+    let mut open = Vec::new();
+    tags.iter().all(|(_, code)| match code {
+        Code::Open(pair) => {
+            open.push(*pair);
+            true
+        }
+        Code::Close(pair) => open.pop() == Some(*pair),
+        Code::Standalone => true,
+    })
+}
+
+fn to_xml(inline: &[Inline]) -> String {
+    inline
+        .iter()
+        .map(|inline| match inline {
+            Piece::Text(text) => escape(text),
+            Piece::Kept((code, _)) => code.clone(),
+        })
+        .collect()
+}
+
+/// Reads the content of a `<source>` in `range` of `text`.
+fn inline(text: &str, range: Range<usize>) -> Result<Vec<Inline>, SyntaxError> {
+    let mut tokens = Tokenizer {
+        text,
+        position: range.start,
+        end: range.end,
+    };
+    let mut inline = Vec::new();
+    let mut open = Vec::new();
+    let mut pairs = 0;
+    while let Some((range, token)) = tokens.next()? {
+        let (code, kind) = match token {
+            Token::Text(content) | Token::CData(content) => {
+                match inline.last_mut() {
+                    Some(Piece::Text(text)) => text.push_str(&content),
+                    _ => inline.push(Piece::Text(content)),
+                }
+                continue;
+            }
+            Token::Start(tag) if tag.empty => (range, Code::Standalone),
+            Token::Start(tag)
+                if CODES.contains(&local(&tag.name))
+                    || tag.get("translate").as_deref() == Some("no") =>
+            {
+                let end = tokens.skip_element()?;
+                (range.start..end, Code::Standalone)
+            }
+            Token::Start(_) => {
+                open.push(pairs);
+                pairs += 1;
+                (range, Code::Open(pairs - 1))
+            }
+            Token::End(_) => match open.pop() {
+                Some(pair) => (range, Code::Close(pair)),
+                None => return Err(error(text, range.start, "unexpected end tag")),
+            },
+            Token::Other => (range, Code::Standalone),
+        };
+        inline.push(Piece::Kept((text[code].to_owned(), kind)));
+    }
+    Ok(inline)
+}
+
+/// A start tag, with its attributes as written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Tag {
+    range: Range<usize>,
+    name: String,
+    attributes: Vec<(String, String)>,
+    empty: bool,
+}
+
+impl Tag {
+    fn new(name: &str) -> Self {
+        Self {
+            range: 0..0,
+            name: name.to_owned(),
+            attributes: Vec::new(),
+            empty: false,
+        }
+    }
+
+    fn get(&self, name: &str) -> Option<String> {
+        self.attributes
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| unescape(value))
+    }
+
+    /// Writes the tag, not empty, with `changes` made to its attributes.
+    fn write(&self, changes: &[(&str, &str)]) -> String {
+        let mut tag = format!("<{}", self.name);
+        for (name, value) in &self.attributes {
+            match changes.iter().find(|(key, _)| key == name) {
+                Some((_, value)) => tag.push_str(&format!(" {}=\"{}\"", name, escape(value))),
+                None => tag.push_str(&format!(" {}=\"{}\"", name, value)),
+            }
+        }
+        for (name, value) in changes {
+            if !self.attributes.iter().any(|(key, _)| key == name) {
+                tag.push_str(&format!(" {}=\"{}\"", name, escape(value)));
+            }
+        }
+        tag.push('>');
+        tag
+    }
+}
+
+/// The name without its namespace prefix.
+fn local(name: &str) -> &str {
+    name.rsplit(':').next().unwrap_or(name)
+}
+
+/// An element that has not ended yet.
+#[derive(Debug)]
+struct Element {
+    tag: Tag,
+    translate: bool,
+    /// The whitespace before its start tag.
+    indent: String,
+}
+
+/// A unit being read.
+#[derive(Debug)]
+struct UnitStart {
+    id: String,
+    translate: bool,
+    tag_end: usize,
+    indent: String,
+    /// The whitespace before its first child.
+    child_indent: Option<String>,
+    /// Where the notes of a 2.x unit end, and the whitespace before them.
+    notes: Option<(usize, String)>,
+    note_indent: Option<String>,
+    metadata_end: Option<usize>,
+}
+
+/// A segment being read.
+#[derive(Debug, Default)]
+struct SegmentStart {
+    source: Option<Range<usize>>,
+    target: Option<(Range<usize>, Tag, Range<usize>)>,
+    insert_at: usize,
+    indent: String,
+    tag: Option<Tag>,
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    version: Option<Version>,
+    source_language: Option<String>,
+    target_language: Option<String>,
+    language_tags: Vec<Tag>,
+    stack: Vec<Element>,
+    unit: Option<UnitStart>,
+    segment: Option<SegmentStart>,
+    segments: Vec<Segment>,
+    units: Vec<Unit>,
+}
+
+impl Parser<'_> {
+    fn start(&mut self, tag: Tag, indent: String) -> Result<(), SyntaxError> {
+        let parent = self.stack.last().map(|element| local(&element.tag.name));
+        let translate = match tag.get("translate").as_deref() {
+            Some("no") => false,
+            Some("yes") => true,
+            _ => self.stack.last().is_none_or(|element| element.translate),
+        };
+        let name = local(&tag.name);
+        if let (Some(unit), Some("trans-unit" | "unit")) = (&mut self.unit, parent) {
+            unit.child_indent.get_or_insert_with(|| indent.clone());
+        }
+        match (self.version, name, parent) {
+            (None, "xliff", None) => {
+                let version = tag.get("version").unwrap_or_default();
+                self.version = match version.split('.').next() {
+                    Some("1") => Some(Version::V1),
+                    Some("2") => Some(Version::V2),
+                    _ => {
+                        let message = format!("unsupported XLIFF version {:?}", version);
+                        return Err(error(self.text, tag.range.start, message));
+                    }
+                };
+                if self.version == Some(Version::V2) {
+                    self.languages(&tag, "srcLang", "trgLang");
+                }
+            }
+            (None, ..) => {
+                return Err(error(
+                    self.text,
+                    tag.range.start,
+                    "expected an <xliff> element",
+                ))
+            }
+            (Some(Version::V1), "file", _) => {
+                self.languages(&tag, "source-language", "target-language")
+            }
+            (Some(Version::V1), "trans-unit", _) | (Some(Version::V2), "unit", _) => {
+                self.unit = Some(UnitStart {
+                    id: tag.get("id").unwrap_or_default(),
+                    translate,
+                    tag_end: tag.range.end,
+                    indent: indent.clone(),
+                    child_indent: None,
+                    notes: None,
+                    note_indent: None,
+                    metadata_end: None,
+                });
+                if self.version == Some(Version::V1) {
+                    self.segment = Some(SegmentStart::default());
+                }
+            }
+            (Some(Version::V2), "segment", Some("unit")) => {
+                self.segment = Some(SegmentStart {
+                    tag: Some(tag.clone()),
+                    ..SegmentStart::default()
+                });
+            }
+            (Some(Version::V2), "note", Some("notes")) => {
+                if let Some(unit) = &mut self.unit {
+                    unit.note_indent.get_or_insert_with(|| indent.clone());
+                }
+            }
+            _ => {}
+        }
+
+        let empty = tag.empty;
+        let end = tag.range.clone();
+        self.stack.push(Element {
+            tag,
+            translate,
+            indent,
+        });
+        if empty {
+            let element = self.stack.pop().expect("just pushed");
+            self.end(element, end.end..end.end, end.start)?;
+        }
+        Ok(())
+    }
+
+    /// Records the languages of an `<xliff>` or `<file>` tag.
+    fn languages(&mut self, tag: &Tag, source: &str, target: &str) {
+        if self.source_language.is_none() {
+            self.source_language = tag.get(source);
+        }
+        match tag.get(target) {
+            Some(language) => {
+                self.target_language.get_or_insert(language);
+            }
+            None => self.language_tags.push(tag.clone()),
+        }
+    }
+
+    /// Handles the end of `element`, whose end tag is at `end`, after
+    /// whitespace starting at `whitespace`.
+    fn end(
+        &mut self,
+        element: Element,
+        end: Range<usize>,
+        whitespace: usize,
+    ) -> Result<(), SyntaxError> {
+        let name = local(&element.tag.name);
+        let parent = self.stack.last().map(|element| local(&element.tag.name));
+        let content = element.tag.range.end..end.start;
+        let version = self.version.expect("the root is an <xliff> element");
+        match (version, name, parent) {
+            (Version::V1, "source", Some("trans-unit"))
+            | (Version::V2, "source", Some("segment")) => {
+                if let Some(segment) = &mut self.segment {
+                    segment.source = Some(content);
+                    segment.insert_at = end.end;
+                    segment.indent = element.indent;
+                }
+            }
+            (Version::V1, "seg-source", Some("trans-unit")) => {
+                if let Some(segment) = &mut self.segment {
+                    segment.insert_at = end.end;
+                }
+            }
+            (Version::V1, "target", Some("trans-unit"))
+            | (Version::V2, "target", Some("segment")) => {
+                if let Some(segment) = &mut self.segment {
+                    let range = element.tag.range.start..end.end;
+                    segment.target = Some((range, element.tag, content));
+                }
+            }
+            (Version::V2, "segment", Some("unit")) => self.finish_segment(&element)?,
+            (Version::V2, "notes", Some("unit")) => {
+                if let Some(unit) = &mut self.unit {
+                    unit.notes = Some((whitespace, element.indent));
+                }
+            }
+            (Version::V2, "metadata", Some("unit")) => {
+                if let Some(unit) = &mut self.unit {
+                    unit.metadata_end = Some(end.end);
+                }
+            }
+            (Version::V1, "trans-unit", _) => {
+                self.finish_segment(&element)?;
+                let unit = self.unit.take().expect("a unit was started");
+                let indent = unit.child_indent.unwrap_or_default();
+                self.units.push(Unit {
+                    note_at: whitespace,
+                    indent,
+                    wrap: None,
+                });
+            }
+            (Version::V2, "unit", _) => {
+                let unit = self.unit.take().expect("a unit was started");
+                let child_indent = unit.child_indent.unwrap_or_default();
+                let step = child_indent
+                    .strip_prefix(unit.indent.as_str())
+                    .unwrap_or("  ");
+                self.units.push(match unit.notes {
+                    Some((note_at, indent)) => Unit {
+                        note_at,
+                        indent: unit
+                            .note_indent
+                            .unwrap_or_else(|| format!("{}{}", indent, step)),
+                        wrap: None,
+                    },
+                    None => Unit {
+                        note_at: unit.metadata_end.unwrap_or(unit.tag_end),
+                        indent: format!("{}{}", child_indent, step),
+                        wrap: Some(child_indent),
+                    },
+                });
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn finish_segment(&mut self, element: &Element) -> Result<(), SyntaxError> {
+        let segment = self.segment.take().expect("a segment was started");
+        let unit = self.unit.as_ref().expect("segments are in units");
+        let Some(source) = segment.source else {
+            let message = format!("<{}> without a <source>", element.tag.name);
+            return Err(error(self.text, element.tag.range.start, message));
+        };
+        let target = segment
+            .target
+            .as_ref()
+            .map(|(_, _, content)| self.text[content.clone()].to_owned());
+        self.segments.push(Segment {
+            target: target.clone(),
+            id: unit.id.clone(),
+            unit: self.units.len(),
+            translate: unit.translate,
+            source: self.text[source.clone()].to_owned(),
+            inline: inline(self.text, source)?,
+            original: target,
+            target_element: segment.target.map(|(range, tag, _)| (range, tag)),
+            insert_at: segment.insert_at,
+            indent: segment.indent,
+            segment_tag: segment.tag,
+            machine: false,
+        });
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+enum Token {
+    Start(Tag),
+    End(String),
+    /// Text, with its references replaced.
+    Text(String),
+    CData(String),
+    /// A comment, processing instruction or declaration.
+    Other,
+}
+
+/// Splits the XML in a range of a text into tokens.
+struct Tokenizer<'a> {
+    text: &'a str,
+    position: usize,
+    end: usize,
+}
+
+impl Tokenizer<'_> {
+    fn next(&mut self) -> Result<Option<(Range<usize>, Token)>, SyntaxError> {
+        let start = self.position;
+        let rest = &self.text[start..self.end];
+        if rest.is_empty() {
+            return Ok(None);
+        }
+        let until = |delimiter: &str, message: &str| match rest.find(delimiter) {
+            Some(end) => Ok(end + delimiter.len()),
+            None => Err(error(self.text, start, message)),
+        };
+        let (length, token) = if rest.starts_with("<!--") {
+            (until("-->", "unclosed comment")?, Token::Other)
+        } else if let Some(content) = rest.strip_prefix("<![CDATA[") {
+            let length = until("]]>", "unclosed CDATA section")?;
+            let content = &content[..length - "<![CDATA[]]>".len()];
+            (length, Token::CData(content.to_owned()))
+        } else if rest.starts_with("<?") {
+            (
+                until("?>", "unclosed processing instruction")?,
+                Token::Other,
+            )
+        } else if rest.starts_with("<!") {
+            // A document type declaration, with any internal subset.
+            let end = match rest.find(['[', '>']) {
+                Some(open) if rest[open..].starts_with('[') => until("]>", "unclosed declaration")?,
+                _ => until(">", "unclosed declaration")?,
+            };
+            (end, Token::Other)
+        } else if let Some(tag) = rest.strip_prefix("</") {
+            let length = until(">", "unclosed end tag")?;
+            let name = tag[..length - 3].trim().to_owned();
+            (length, Token::End(name))
+        } else if rest.starts_with('<') {
+            let tag = self.tag()?;
+            (tag.range.len(), Token::Start(tag))
+        } else {
+            let length = rest.find('<').unwrap_or(rest.len());
+            (length, Token::Text(unescape(&rest[..length])))
+        };
+        self.position = start + length;
+        Ok(Some((start..self.position, token)))
+    }
+
+    fn tag(&self) -> Result<Tag, SyntaxError> {
+        let start = self.position;
+        let rest = &self.text[start..self.end];
+        let fail = |offset: usize, message: &str| Err(error(self.text, start + offset, message));
+        let name_end = |from: usize| {
+            rest[from..]
+                .find(|c: char| c.is_whitespace() || matches!(c, '/' | '>' | '='))
+                .map(|end| from + end)
+        };
+        let Some(mut position) = name_end(1).filter(|&end| end > 1) else {
+            return fail(0, "malformed tag");
+        };
+        let name = rest[1..position].to_owned();
+        let mut attributes = Vec::new();
+        loop {
+            position += rest[position..].len() - rest[position..].trim_start().len();
+            let after = &rest[position..];
+            let (length, empty) = match after {
+                _ if after.starts_with("/>") => (2, true),
+                _ if after.starts_with('>') => (1, false),
+                "" => return fail(0, "unclosed tag"),
+                _ => {
+                    let Some(end) = name_end(position).filter(|&end| end > position) else {
+                        return fail(position, "malformed attribute");
+                    };
+                    let key = rest[position..end].to_owned();
+                    let value = rest[end..].trim_start();
+                    let Some(value) = value.strip_prefix('=').map(str::trim_start) else {
+                        return fail(end, "expected `=` after an attribute name");
+                    };
+                    let quote = match value.chars().next() {
+                        Some(quote @ ('"' | '\'')) => quote,
+                        _ => return fail(end, "expected a quoted attribute value"),
+                    };
+                    let value_start = rest.len() - value.len() + 1;
+                    let Some(length) = rest[value_start..].find(quote) else {
+                        return fail(end, "unclosed attribute value");
+                    };
+                    attributes.push((key, rest[value_start..value_start + length].to_owned()));
+                    position = value_start + length + 1;
+                    continue;
+                }
+            };
+            return Ok(Tag {
+                range: start..start + position + length,
+                name,
+                attributes,
+                empty,
+            });
+        }
+    }
+
+    /// Skips to the end of the element whose start tag was just read,
+    /// returning where it ends.
+    fn skip_element(&mut self) -> Result<usize, SyntaxError> {
+        let mut depth = 1;
+        while let Some((range, token)) = self.next()? {
+            match token {
+                Token::Start(tag) if !tag.empty => depth += 1,
+                Token::End(_) => depth -= 1,
+                _ => {}
+            }
+            if depth == 0 {
+                return Ok(range.end);
+            }
+        }
+        Err(error(self.text, self.position, "unclosed element"))
+    }
+}
+
+fn error(text: &str, position: usize, message: impl Into<String>) -> SyntaxError {
+    let line = text[..position].matches('\n').count() + 1;
+    SyntaxError::new(line, message)
+}
+
+/// Escapes text for content or attribute values.
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Replaces character and predefined entity references; others are kept.
+fn unescape(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let reference = rest[1..].find(';').map(|end| &rest[1..end + 1]);
+        let c = match reference {
+            Some("lt") => Some('<'),
+            Some("gt") => Some('>'),
+            Some("amp") => Some('&'),
+            Some("quot") => Some('"'),
+            Some("apos") => Some('\''),
+            Some(number) => match number.strip_prefix("#x") {
+                Some(hex) => u32::from_str_radix(hex, 16).ok(),
+                None => number.strip_prefix('#').and_then(|n| n.parse().ok()),
+            }
+            .and_then(char::from_u32),
+            None => None,
+        };
+        match (c, reference) {
+            (Some(c), Some(reference)) => {
+                result.push(c);
+                rest = &rest[reference.len() + 2..];
+            }
+            _ => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::UppercaseBackend;
+
+    const V1: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<xliff version="1.2" xmlns="urn:oasis:names:tc:xliff:document:1.2">
+  <file source-language="en" datatype="plaintext" original="app">
+    <body>
+      <trans-unit id="greeting">
+        <source>Hello, <g id="1">dear</g> friend<x id="2"/>!</source>
+      </trans-unit>
+      <trans-unit id="brand" translate="no">
+        <source>Acme</source>
+      </trans-unit>
+      <trans-unit id="done">
+        <source>Save</source>
+        <target state="translated">Enregistrer</target>
+      </trans-unit>
+      <trans-unit id="empty">
+        <source>Tom &amp; {name}</source>
+        <target/>
+        <note>A button</note>
+      </trans-unit>
+    </body>
+  </file>
+</xliff>
+"#;
+
+    const V2: &str = r#"<xliff xmlns="urn:oasis:names:tc:xliff:document:2.0" version="2.0" srcLang="en" trgLang="fr">
+  <file id="f1">
+    <unit id="u1">
+      <segment>
+        <source>Click <pc id="1">here</pc><ph id="2"/>.</source>
+      </segment>
+      <segment state="final">
+        <source>Bye</source>
+        <target>Au revoir</target>
+      </segment>
+    </unit>
+    <unit id="u2">
+      <notes>
+        <note>Title</note>
+      </notes>
+      <segment>
+        <source>News</source>
+      </segment>
+    </unit>
+  </file>
+</xliff>
+"#;
+
+    #[test]
+    fn test_document_round_trips() {
+        let document = XliffDocument::parse(V1).unwrap();
+        assert_eq!(document.to_string(), V1);
+        assert_eq!(document.source_language(), Some("en"));
+        assert_eq!(document.target_language(), None);
+        let segments: Vec<_> = document.segments().collect();
+        assert_eq!(segments.len(), 4);
+        assert_eq!(
+            segments[0].source(),
+            r#"Hello, <g id="1">dear</g> friend<x id="2"/>!"#
+        );
+        assert!(segments[0].needs_translation());
+        assert!(!segments[1].is_translatable());
+        assert_eq!(segments[2].target.as_deref(), Some("Enregistrer"));
+        assert!(!segments[2].needs_translation());
+        assert_eq!(segments[3].target.as_deref(), Some(""));
+
+        let document = XliffDocument::parse(V2).unwrap();
+        assert_eq!(document.to_string(), V2);
+        assert_eq!(document.target_language(), Some("fr"));
+        let ids: Vec<_> = document.segments().map(Segment::id).collect();
+        assert_eq!(ids, ["u1", "u1", "u2"]);
+
+        let error = XliffDocument::parse("<xliff version=\"1.2\">\n<file>\n</xliff>").unwrap_err();
+        assert_eq!(error.line, 3);
+        let error = XliffDocument::parse("<xliff version=\"3.0\"/>").unwrap_err();
+        assert_eq!(error.message, "unsupported XLIFF version \"3.0\"");
+    }
+
+    #[tokio::test]
+    async fn test_translate_xliff_1() {
+        let translator = Translator::with_backend("en", "fr", UppercaseBackend::default()).unwrap();
+        let mut document = XliffDocument::parse(V1).unwrap();
+        assert_eq!(document.translate(&translator).await.unwrap(), 2);
+        assert_eq!(document.target_language(), Some("fr"));
+        let note = r#"<note from="rustranslate" annotates="target">Machine translation (uppercase), to be reviewed</note>"#;
+        let expected = V1
+            .replace(
+                r#"original="app">"#,
+                r#"original="app" target-language="fr">"#,
+            )
+            .replace(
+                "friend<x id=\"2\"/>!</source>\n",
+                &format!(
+                    "friend<x id=\"2\"/>!</source>\n        \
+                     <target state=\"needs-review-translation\">HELLO, <g id=\"1\">DEAR</g> FRIEND<x id=\"2\"/>!</target>\n        \
+                     {}\n",
+                    note
+                ),
+            )
+            .replace(
+                "<target/>\n        <note>A button</note>\n",
+                &format!(
+                    "<target state=\"needs-review-translation\">TOM &amp; {{NAME}}</target>\n        \
+                     <note>A button</note>\n        {}\n",
+                    note
+                ),
+            );
+        assert_eq!(document.to_string(), expected);
+    }
+
+    #[tokio::test]
+    async fn test_translate_xliff_2() {
+        let translator = Translator::with_backend("en", "fr", UppercaseBackend::default()).unwrap();
+        let mut document = XliffDocument::parse(V2).unwrap();
+        assert_eq!(document.translate(&translator).await.unwrap(), 2);
+        let state =
+            r#"<segment state="translated" subState="rustranslate:needs-review-translation">"#;
+        let note = r#"<note appliesTo="target" category="machine-translation">Machine translation (uppercase), to be reviewed</note>"#;
+        let expected = V2
+            .replace(
+                "<unit id=\"u1\">\n      <segment>",
+                &format!(
+                    "<unit id=\"u1\">\n      <notes>\n        {}\n      </notes>\n      {}",
+                    note, state
+                ),
+            )
+            .replace(
+                "<ph id=\"2\"/>.</source>\n",
+                "<ph id=\"2\"/>.</source>\n        \
+                 <target>CLICK <pc id=\"1\">HERE</pc><ph id=\"2\"/>.</target>\n",
+            )
+            .replace(
+                "<note>Title</note>\n      </notes>\n      <segment>",
+                &format!(
+                    "<note>Title</note>\n        {}\n      </notes>\n      {}",
+                    note, state
+                ),
+            )
+            .replace(
+                "<source>News</source>\n",
+                "<source>News</source>\n        <target>NEWS</target>\n",
+            );
+        assert_eq!(document.to_string(), expected);
+    }
+}
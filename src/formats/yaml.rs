@@ -0,0 +1,946 @@
+//! YAML locale files, as used by Rails.
+//!
+//! [`YamlDocument`] finds the string scalars of block and flow collections
+//! and rewrites only those it translated, in the style they were written
+//! in where the translation allows it. Comments, anchors, aliases, tags
+//! and every other node stay as they were.
+
+use super::{line_ending, translate_values, KeyFilter, SyntaxError};
+use crate::{TranslationError, Translator};
+use std::fmt;
+use std::ops::Range;
+
+/// How a scalar is written.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Style {
+    Plain {
+        flow: bool,
+    },
+    SingleQuoted,
+    DoubleQuoted,
+    /// A `|` block scalar whose lines start with `indent`.
+    Literal {
+        indent: String,
+    },
+    /// A `>` block scalar whose lines start with `indent`.
+    Folded {
+        indent: String,
+    },
+}
+
+/// A string scalar of a document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct YamlString {
+    pub value: String,
+    path: Vec<String>,
+    /// The value as read, with the byte range of its text and its style.
+    original: String,
+    span: Range<usize>,
+    style: Style,
+}
+
+impl YamlString {
+    /// The keys leading to the scalar from the root of its document;
+    /// sequence items are named by their index.
+    pub fn path(&self) -> &[String] {
+        &self.path
+    }
+
+    /// The path joined with dots, as in `en.home.title`.
+    pub fn key(&self) -> String {
+        self.path.join(".")
+    }
+
+    /// The value written in the original style, or in a quoted one when
+    /// that style cannot hold it.
+    fn write(&self, newline: &str) -> String {
+        let value = &self.value;
+        match &self.style {
+            Style::Plain { flow } if is_plain_safe(value, *flow) => value.clone(),
+            Style::SingleQuoted | Style::Plain { .. }
+                if !value.contains(|c| c == '\n' || is_escaped(c)) =>
+            {
+                format!("'{}'", value.replace('\'', "''"))
+            }
+            Style::Literal { indent } if !value.contains(is_escaped) => {
+                let mut text = String::new();
+                for (index, line) in value.split('\n').enumerate() {
+                    if index > 0 {
+                        text.push_str(newline);
+                        if !line.is_empty() {
+                            text.push_str(indent);
+                        }
+                    }
+                    text.push_str(line);
+                }
+                text
+            }
+            // Single line breaks are folded into spaces, so each break is
+            // written as one more empty line.
+            Style::Folded { indent } if !value.contains(is_escaped) => {
+                let mut text = String::new();
+                let mut breaks = 0;
+                for (index, line) in value.split('\n').enumerate() {
+                    if index > 0 {
+                        breaks += 1;
+                    }
+                    if line.is_empty() {
+                        continue;
+                    }
+                    if breaks > 0 && !text.is_empty() {
+                        text.push_str(&newline.repeat(breaks + 1));
+                        text.push_str(indent);
+                    }
+                    breaks = 0;
+                    text.push_str(line);
+                }
+                text
+            }
+            _ => double_quote(value),
+        }
+    }
+}
+
+/// A parsed YAML file, possibly holding several documents.
+#[derive(Debug, Clone)]
+pub struct YamlDocument {
+    text: String,
+    strings: Vec<YamlString>,
+    /// The line ending used by the file.
+    newline: &'static str,
+}
+
+impl YamlDocument {
+    pub fn parse(text: &str) -> Result<Self, SyntaxError> {
+        let mut parser = Parser {
+            text,
+            position: 0,
+            levels: Vec::new(),
+            strings: Vec::new(),
+        };
+        parser.parse()?;
+        Ok(Self {
+            text: text.to_owned(),
+            strings: parser.strings,
+            newline: line_ending(text),
+        })
+    }
+
+    /// The string scalars, in the order they appear.
+    pub fn strings(&self) -> impl Iterator<Item = &YamlString> {
+        self.strings.iter()
+    }
+
+    pub fn strings_mut(&mut self) -> impl Iterator<Item = &mut YamlString> {
+        self.strings.iter_mut()
+    }
+
+    /// Translates the strings whose keys `keys` selects with `translator`.
+    /// Returns the number of strings translated.
+    pub async fn translate(
+        &mut self,
+        translator: &Translator,
+        keys: &KeyFilter,
+    ) -> Result<usize, TranslationError> {
+        let values = self
+            .strings
+            .iter_mut()
+            .map(|string| (string.path.as_slice(), &mut string.value));
+        translate_values(translator, keys, values).await
+    }
+}
+
+impl fmt::Display for YamlDocument {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut position = 0;
+        for string in self.strings.iter().filter(|s| s.value != s.original) {
+            f.write_str(&self.text[position..string.span.start])?;
+            f.write_str(&string.write(self.newline))?;
+            position = string.span.end;
+        }
+        f.write_str(&self.text[position..])
+    }
+}
+
+/// Whether `c` must be escaped, which only double quotes can do.
+fn is_escaped(c: char) -> bool {
+    c.is_control() && c != '\n' && c != '\t'
+}
+
+fn double_quote(value: &str) -> String {
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+/// Whether `value` reads back as the same string when written unquoted.
+fn is_plain_safe(value: &str, flow: bool) -> bool {
+    let Some(first) = value.chars().next() else {
+        return false;
+    };
+    !"-?:,[]{}#&*!|>'\"%@`".contains(first)
+        && value.trim() == value
+        && !value.contains(['\n', '\r'])
+        && !value.contains(is_escaped)
+        && !value.contains(": ")
+        && !value.contains(" #")
+        && !value.ends_with(':')
+        && !(flow && value.contains([',', '[', ']', '{', '}']))
+        && is_plain_string(value)
+}
+
+/// Unquoted scalars that are not strings in YAML 1.1.
+const KEYWORDS: &[&str] = &[
+    "~", "null", "Null", "NULL", "y", "Y", "yes", "Yes", "YES", "n", "N", "no", "No", "NO", "true",
+    "True", "TRUE", "false", "False", "FALSE", "on", "On", "ON", "off", "Off", "OFF",
+];
+
+/// Whether an unquoted scalar is a string rather than a null, boolean,
+/// number or date, following YAML 1.1 as Ruby does.
+fn is_plain_string(value: &str) -> bool {
+    let keyword = KEYWORDS.contains(&value);
+    let digits = value.trim_start_matches(['-', '+']).replace('_', "");
+    let number = digits.parse::<f64>().is_ok()
+        || [".inf", ".Inf", ".INF", ".nan", ".NaN", ".NAN"].contains(&digits.as_str())
+        || ["0x", "0o", "0b"].iter().any(|prefix| {
+            digits
+                .strip_prefix(prefix)
+                .is_some_and(|rest| !rest.is_empty() && rest.chars().all(|c| c.is_ascii_hexdigit()))
+        });
+    let bytes = value.as_bytes();
+    let date = bytes.len() >= 10
+        && bytes[..10]
+            .iter()
+            .enumerate()
+            .all(|(index, byte)| match index {
+                4 | 7 => *byte == b'-',
+                _ => byte.is_ascii_digit(),
+            });
+    !(value.is_empty() || keyword || number || date)
+}
+
+/// A collection the line being parsed is nested in.
+#[derive(Debug)]
+struct Level {
+    /// The column of the key or of the `-` of the item.
+    indent: usize,
+    segment: Segment,
+}
+
+#[derive(Debug)]
+enum Segment {
+    Key(String),
+    Item(usize),
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    position: usize,
+    levels: Vec<Level>,
+    strings: Vec<YamlString>,
+}
+
+impl<'a> Parser<'a> {
+    fn parse(&mut self) -> Result<(), SyntaxError> {
+        while self.position < self.text.len() {
+            let line = self.rest_of_line();
+            let content = line.trim_start_matches(' ');
+            let indent = line.len() - content.len();
+            if content.trim().is_empty() || content.starts_with('#') {
+                self.next_line();
+            } else if content.starts_with('\t') {
+                return Err(self.error("tabs cannot indent YAML"));
+            } else if indent == 0 && content.starts_with('%') {
+                self.next_line();
+            } else if indent == 0 && is_document_marker(content) {
+                self.levels.clear();
+                self.position += 3;
+                self.value(0)?;
+            } else {
+                self.position += indent;
+                self.node(indent, indent)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The text from the position to the end of its line, without the line
+    /// ending.
+    fn rest_of_line(&self) -> &'a str {
+        let rest = &self.text[self.position..];
+        let end = rest.find('\n').unwrap_or(rest.len());
+        rest[..end].trim_end_matches('\r')
+    }
+
+    fn next_line(&mut self) {
+        self.position = match self.text[self.position..].find('\n') {
+            Some(end) => self.position + end + 1,
+            None => self.text.len(),
+        };
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.text[self.position..].chars().next()
+    }
+
+    fn skip_spaces(&mut self) {
+        while matches!(self.peek(), Some(' ' | '\t')) {
+            self.position += 1;
+        }
+    }
+
+    /// Whether only a comment is left on the line.
+    fn at_line_end(&self) -> bool {
+        let rest = self.rest_of_line().trim_start();
+        rest.is_empty() || rest.starts_with('#')
+    }
+
+    fn column(&self) -> usize {
+        let start = self.text[..self.position]
+            .rfind('\n')
+            .map_or(0, |end| end + 1);
+        self.text[start..self.position].chars().count()
+    }
+
+    fn line(&self) -> usize {
+        self.text[..self.position].matches('\n').count() + 1
+    }
+
+    fn error(&self, message: &str) -> SyntaxError {
+        SyntaxError::new(self.line(), message)
+    }
+
+    fn path(&self) -> Vec<String> {
+        self.levels
+            .iter()
+            .map(|level| match &level.segment {
+                Segment::Key(key) => key.clone(),
+                Segment::Item(index) => index.to_string(),
+            })
+            .collect()
+    }
+
+    /// Parses the node starting at the position, at `column` of its line:
+    /// a sequence item, a mapping entry or a scalar, whose parent is at
+    /// column `parent`.
+    fn node(&mut self, column: usize, parent: usize) -> Result<(), SyntaxError> {
+        let rest = self.rest_of_line();
+        if rest == "-" || rest.starts_with("- ") {
+            while self
+                .levels
+                .last()
+                .is_some_and(|level| level.indent > column)
+            {
+                self.levels.pop();
+            }
+            match self.levels.last_mut() {
+                Some(Level {
+                    indent,
+                    segment: Segment::Item(index),
+                }) if *indent == column => *index += 1,
+                _ => self.levels.push(Level {
+                    indent: column,
+                    segment: Segment::Item(0),
+                }),
+            }
+            self.position += 1;
+            self.skip_spaces();
+            if self.at_line_end() {
+                self.next_line();
+                return Ok(());
+            }
+            return self.node(self.column(), column);
+        }
+
+        let start = self.position;
+        match self.key()? {
+            Some(key) => {
+                while self
+                    .levels
+                    .last()
+                    .is_some_and(|level| level.indent >= column)
+                {
+                    self.levels.pop();
+                }
+                self.levels.push(Level {
+                    indent: column,
+                    segment: Segment::Key(key),
+                });
+                self.value(column)
+            }
+            None => {
+                self.position = start;
+                self.value(parent)
+            }
+        }
+    }
+
+    /// Reads a mapping key and its `:`, or returns `None` when the line
+    /// holds no key.
+    fn key(&mut self) -> Result<Option<String>, SyntaxError> {
+        let key = match self.peek() {
+            Some('"' | '\'') => {
+                let (_, key) = self.quoted()?;
+                self.skip_spaces();
+                key
+            }
+            Some('[' | '{' | '&' | '*' | '!' | '|' | '>' | '?' | '#') => return Ok(None),
+            _ => {
+                let rest = self.rest_of_line();
+                let end = rest
+                    .char_indices()
+                    .find(|&(index, c)| {
+                        c == ':' && rest[index + 1..].chars().next().is_none_or(|c| c == ' ')
+                    })
+                    .map(|(index, _)| index);
+                match end {
+                    Some(end) if !rest[..end].contains(" #") => {
+                        self.position += end;
+                        rest[..end].trim_end().to_owned()
+                    }
+                    _ => return Ok(None),
+                }
+            }
+        };
+        if self.peek() != Some(':') {
+            return Ok(None);
+        }
+        self.position += 1;
+        Ok(Some(key))
+    }
+
+    /// Parses the value following a key or `-`, whose parent is at column
+    /// `parent`.
+    fn value(&mut self, parent: usize) -> Result<(), SyntaxError> {
+        self.skip_spaces();
+        let tag = self.properties();
+        if self.at_line_end() {
+            self.next_line();
+            return Ok(());
+        }
+        let is_string = tag.as_deref().is_none_or(|tag| tag == "!!str");
+        match self.peek() {
+            Some('|' | '>') => self.block_scalar(parent, is_string),
+            Some('[' | '{') => {
+                let mut path = self.path();
+                self.flow(&mut path)?;
+                self.next_line();
+                Ok(())
+            }
+            Some('*') => {
+                self.next_line();
+                Ok(())
+            }
+            Some('"' | '\'') => {
+                let style = match self.peek() {
+                    Some('"') => Style::DoubleQuoted,
+                    _ => Style::SingleQuoted,
+                };
+                let (span, value) = self.quoted()?;
+                self.push(span, value, style, is_string);
+                self.next_line();
+                Ok(())
+            }
+            _ => {
+                let (span, value) = self.plain(parent);
+                let is_string = is_string && (tag.is_some() || is_plain_string(&value));
+                self.push(span, value, Style::Plain { flow: false }, is_string);
+                Ok(())
+            }
+        }
+    }
+
+    fn push(&mut self, span: Range<usize>, value: String, style: Style, is_string: bool) {
+        self.push_at(self.path(), span, value, style, is_string);
+    }
+
+    fn push_at(
+        &mut self,
+        path: Vec<String>,
+        span: Range<usize>,
+        value: String,
+        style: Style,
+        is_string: bool,
+    ) {
+        if is_string {
+            self.strings.push(YamlString {
+                path,
+                original: value.clone(),
+                value,
+                span,
+                style,
+            });
+        }
+    }
+
+    /// Skips anchors and tags, returning the tag.
+    fn properties(&mut self) -> Option<String> {
+        let mut tag = None;
+        while let Some('&' | '!') = self.peek() {
+            let rest = self.rest_of_line();
+            let end = rest.find([' ', ',', ']', '}']).unwrap_or(rest.len());
+            if rest.starts_with('!') {
+                tag = Some(rest[..end].to_owned());
+            }
+            self.position += end;
+            self.skip_spaces();
+        }
+        tag
+    }
+
+    /// Reads a plain scalar, including the lines continuing it that are
+    /// indented deeper than `parent`.
+    fn plain(&mut self, parent: usize) -> (Range<usize>, String) {
+        let start = self.position;
+        let mut end = start + plain_len(self.rest_of_line(), false);
+        let mut value = self.text[start..end].to_owned();
+        self.next_line();
+
+        let mut breaks = 0;
+        while self.position < self.text.len() {
+            let line = self.rest_of_line();
+            let content = line.trim_start();
+            let indent = line.len() - content.len();
+            if content.is_empty() {
+                breaks += 1;
+            } else if indent > parent && !content.starts_with('#') {
+                value.push_str(&match breaks {
+                    0 => " ".to_owned(),
+                    breaks => "\n".repeat(breaks),
+                });
+                breaks = 0;
+                let length = plain_len(content, false);
+                value.push_str(&content[..length]);
+                end = self.position + indent + length;
+            } else {
+                break;
+            }
+            self.next_line();
+        }
+        // Lines after the scalar are parsed again.
+        self.position = end;
+        self.next_line();
+        (start..end, value)
+    }
+
+    /// Reads a quoted scalar, which may span lines.
+    fn quoted(&mut self) -> Result<(Range<usize>, String), SyntaxError> {
+        let start = self.position;
+        let quote = self.peek().expect("at a quote");
+        self.position += 1;
+        let mut raw = String::new();
+        loop {
+            let Some(c) = self.peek() else {
+                self.position = start;
+                return Err(self.error("unterminated quoted scalar"));
+            };
+            self.position += c.len_utf8();
+            match c {
+                '\'' if quote == '\'' && self.peek() == Some('\'') => {
+                    self.position += 1;
+                    raw.push_str("''");
+                }
+                '\\' if quote == '"' => {
+                    raw.push('\\');
+                    if let Some(c) = self.peek() {
+                        self.position += c.len_utf8();
+                        raw.push(c);
+                    }
+                }
+                c if c == quote => break,
+                c => raw.push(c),
+            }
+        }
+        let value = match quote {
+            '"' => unescape(&fold(&raw, true)).map_err(|message| self.error(message))?,
+            _ => fold(&raw, false).replace("''", "'"),
+        };
+        Ok((start..self.position, value))
+    }
+
+    /// Reads a `|` or `>` block scalar: the lines after its header that are
+    /// blank or indented deeper than `parent`.
+    fn block_scalar(&mut self, parent: usize, is_string: bool) -> Result<(), SyntaxError> {
+        let header = self.rest_of_line().to_owned();
+        let literal = header.starts_with('|');
+        let explicit = header[1..]
+            .chars()
+            .take_while(|c| !c.is_whitespace())
+            .find_map(|c| c.to_digit(10))
+            .map(|digit| parent + digit as usize);
+        self.next_line();
+
+        let mut indent = explicit;
+        let mut lines: Vec<(usize, &str)> = Vec::new();
+        let mut end = self.position;
+        while self.position < self.text.len() {
+            let line = self.rest_of_line();
+            let content = line.trim_start_matches(' ');
+            let column = line.len() - content.len();
+            if !content.is_empty() {
+                let block_indent = *indent.get_or_insert(column);
+                if column <= parent || column < block_indent {
+                    break;
+                }
+                end = self.position + line.len();
+            }
+            lines.push((self.position, line));
+            self.next_line();
+        }
+        let Some(indent) = indent else {
+            return Ok(());
+        };
+
+        // Blank lines after the content belong to the chomping, not to it.
+        while lines.last().is_some_and(|(start, _)| *start >= end) {
+            lines.pop();
+        }
+        let content: Vec<&str> = lines
+            .iter()
+            .map(|(_, line)| line.get(indent..).unwrap_or(""))
+            .collect();
+        let value = match literal {
+            true => content.join("\n"),
+            false => fold_block(&content),
+        };
+        let Some((start, _)) = lines.first() else {
+            return Ok(());
+        };
+        let span = start + indent..end;
+        let prefix = " ".repeat(indent);
+        let style = match literal {
+            true => Style::Literal { indent: prefix },
+            false => Style::Folded { indent: prefix },
+        };
+        self.push(span, value, style, is_string);
+        self.position = end;
+        self.next_line();
+        Ok(())
+    }
+
+    /// Parses a `[...]` or `{...}` collection, which may span lines.
+    fn flow(&mut self, path: &mut Vec<String>) -> Result<(), SyntaxError> {
+        let start = self.position;
+        let mapping = self.peek() == Some('{');
+        let close = if mapping { '}' } else { ']' };
+        self.position += 1;
+        let mut index = 0;
+        loop {
+            self.skip_flow_space();
+            match self.peek() {
+                None => {
+                    self.position = start;
+                    return Err(self.error("unterminated flow collection"));
+                }
+                Some(c) if c == close => {
+                    self.position += 1;
+                    return Ok(());
+                }
+                Some(',') => self.position += 1,
+                Some(_) if mapping => {
+                    let key = match self.peek() {
+                        Some('"' | '\'') => self.quoted()?.1,
+                        _ => {
+                            let rest = &self.text[self.position..];
+                            let length = plain_len(rest, true);
+                            self.position += length;
+                            rest[..length].to_owned()
+                        }
+                    };
+                    self.skip_flow_space();
+                    path.push(key);
+                    if self.peek() == Some(':') {
+                        self.position += 1;
+                        self.flow_value(path)?;
+                    }
+                    path.pop();
+                }
+                Some(_) => {
+                    path.push(index.to_string());
+                    self.flow_value(path)?;
+                    path.pop();
+                    index += 1;
+                }
+            }
+        }
+    }
+
+    fn flow_value(&mut self, path: &mut Vec<String>) -> Result<(), SyntaxError> {
+        self.skip_flow_space();
+        let tag = self.properties();
+        let is_string = tag.as_deref().is_none_or(|tag| tag == "!!str");
+        match self.peek() {
+            Some('[' | '{') => self.flow(path),
+            Some('"' | '\'') => {
+                let style = match self.peek() {
+                    Some('"') => Style::DoubleQuoted,
+                    _ => Style::SingleQuoted,
+                };
+                let (span, value) = self.quoted()?;
+                self.push_at(path.clone(), span, value, style, is_string);
+                Ok(())
+            }
+            _ => {
+                let start = self.position;
+                let length = plain_len(&self.text[start..], true);
+                self.position += length;
+                let value = self.text[start..self.position].to_owned();
+                let is_string = is_string
+                    && !value.starts_with('*')
+                    && (tag.is_some() || is_plain_string(&value));
+                let style = Style::Plain { flow: true };
+                self.push_at(path.clone(), start..self.position, value, style, is_string);
+                Ok(())
+            }
+        }
+    }
+
+    /// Skips whitespace, line breaks and comments inside a flow collection.
+    fn skip_flow_space(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r' | '\n') => self.position += 1,
+                Some('#') => self.next_line(),
+                _ => return,
+            }
+        }
+    }
+}
+
+fn is_document_marker(line: &str) -> bool {
+    (line.starts_with("---") || line.starts_with("..."))
+        && line[3..].chars().next().is_none_or(|c| c == ' ')
+}
+
+/// The length of the plain scalar `text` starts with, which ends at a
+/// comment, the end of the line, or in flow collections at an indicator.
+fn plain_len(text: &str, flow: bool) -> usize {
+    let mut end = 0;
+    let mut previous = ' ';
+    for (index, c) in text.char_indices() {
+        let next = text[index + c.len_utf8()..].chars().next();
+        let ends = match c {
+            '\n' | '\r' => true,
+            '#' => previous == ' ' || previous == '\t',
+            ':' => flow && next.is_none_or(|c| " \t\r\n,]}".contains(c)),
+            ',' | '[' | ']' | '{' | '}' => flow,
+            _ => false,
+        };
+        if ends {
+            break;
+        }
+        if c != ' ' && c != '\t' {
+            end = index + c.len_utf8();
+        }
+        previous = c;
+    }
+    end
+}
+
+/// Folds the line breaks of a quoted scalar: a single break becomes a
+/// space and each further break a newline, dropping the spaces around
+/// them. Escaped breaks in double quotes join the lines.
+fn fold(raw: &str, escapes: bool) -> String {
+    let mut lines = raw.split('\n').map(|line| line.trim_end_matches('\r'));
+    let mut folded = lines.next().unwrap_or_default().to_owned();
+    let mut breaks = 0;
+    for line in lines {
+        let line = line.trim_start_matches([' ', '\t']);
+        if line.is_empty() {
+            breaks += 1;
+            continue;
+        }
+        let escaped = escapes && folded.ends_with('\\') && !folded.ends_with("\\\\");
+        if escaped {
+            folded.pop();
+        } else {
+            folded.truncate(folded.trim_end_matches([' ', '\t']).len());
+            folded.push_str(&match breaks {
+                0 => " ".to_owned(),
+                breaks => "\n".repeat(breaks),
+            });
+        }
+        breaks = 0;
+        folded.push_str(line);
+    }
+    folded
+}
+
+/// Folds the lines of a `>` block scalar.
+fn fold_block(lines: &[&str]) -> String {
+    let mut folded = String::new();
+    let mut breaks = 0;
+    for line in lines {
+        if line.is_empty() {
+            breaks += 1;
+            continue;
+        }
+        if !folded.is_empty() {
+            let more_indented = line.starts_with([' ', '\t']);
+            folded.push_str(&match (breaks, more_indented) {
+                (0, false) => " ".to_owned(),
+                (breaks, false) => "\n".repeat(breaks),
+                (breaks, true) => "\n".repeat(breaks + 1),
+            });
+        }
+        breaks = 0;
+        folded.push_str(line);
+    }
+    folded
+}
+
+/// Replaces the escape sequences of a double-quoted scalar.
+fn unescape(raw: &str) -> Result<String, &'static str> {
+    let mut value = String::new();
+    let mut chars = raw.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let escaped = match chars.next().ok_or("unfinished escape sequence")? {
+            '0' => '\0',
+            'a' => '\x07',
+            'b' => '\x08',
+            't' | '\t' => '\t',
+            'n' => '\n',
+            'v' => '\x0b',
+            'f' => '\x0c',
+            'r' => '\r',
+            'e' => '\x1b',
+            'N' => '\u{85}',
+            '_' => '\u{a0}',
+            'L' => '\u{2028}',
+            'P' => '\u{2029}',
+            digits @ ('x' | 'u' | 'U') => {
+                let length = match digits {
+                    'x' => 2,
+                    'u' => 4,
+                    _ => 8,
+                };
+                let hex: String = chars.by_ref().take(length).collect();
+                u32::from_str_radix(&hex, 16)
+                    .ok()
+                    .filter(|_| hex.len() == length)
+                    .and_then(char::from_u32)
+                    .ok_or("invalid escape sequence")?
+            }
+            c @ (' ' | '"' | '/' | '\\') => c,
+            _ => return Err("invalid escape sequence"),
+        };
+        value.push(escaped);
+    }
+    Ok(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::UppercaseBackend;
+
+    const SOURCE: &str = "\
+# Rails locale
+en:
+  greeting: Hello %{name}   # shown on the home page
+  defaults: &defaults
+    save: 'Save'
+    count: 3
+    enabled: yes
+  form:
+    <<: *defaults
+    cancel: \"Cancel\\tnow\"
+  days: [Sunday, \"Monday\", 2]
+  list:
+  - first
+  - name: second
+    note: >
+      folded
+      text
+
+  about: |
+    Line one
+      Line two
+  empty: ''
+  long: a plain
+    scalar
+";
+
+    fn values(document: &YamlDocument) -> Vec<(String, String)> {
+        document
+            .strings()
+            .map(|string| (string.key(), string.value.clone()))
+            .collect()
+    }
+
+    #[test]
+    fn test_document_round_trips() {
+        let document = YamlDocument::parse(SOURCE).unwrap();
+        assert_eq!(document.to_string(), SOURCE);
+        let expected = [
+            ("en.greeting", "Hello %{name}"),
+            ("en.defaults.save", "Save"),
+            ("en.form.cancel", "Cancel\tnow"),
+            ("en.days.0", "Sunday"),
+            ("en.days.1", "Monday"),
+            ("en.list.0", "first"),
+            ("en.list.1.name", "second"),
+            ("en.list.1.note", "folded text"),
+            ("en.about", "Line one\n  Line two"),
+            ("en.empty", ""),
+            ("en.long", "a plain scalar"),
+        ];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        assert_eq!(values(&document), expected);
+
+        let document = YamlDocument::parse("- 'It''s'\n- \"a\n\n  b\\\n  c\"\n").unwrap();
+        let expected = [("0", "It's"), ("1", "a\nbc")];
+        let expected: Vec<_> = expected
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect();
+        assert_eq!(values(&document), expected);
+
+        let error = YamlDocument::parse("a:\n  b: \"open\n").unwrap_err();
+        assert_eq!(error.line, 2);
+    }
+
+    #[tokio::test]
+    async fn test_translate_document() {
+        let translator = Translator::with_backend("en", "fr", UppercaseBackend::default()).unwrap();
+        let mut document = YamlDocument::parse(SOURCE).unwrap();
+        let keys = KeyFilter::new().with_exclude("en.list.**");
+        assert_eq!(document.translate(&translator, &keys).await.unwrap(), 7);
+        let translated = SOURCE
+            .replace("Hello %{name} ", "HELLO %{name} ")
+            .replace("'Save'", "'SAVE'")
+            .replace("Cancel\\tnow", "CANCEL\\tNOW")
+            .replace("[Sunday, \"Monday\"", "[SUNDAY, \"MONDAY\"")
+            .replace("Line one\n      Line two", "LINE ONE\n      LINE TWO")
+            .replace("a plain\n    scalar", "A PLAIN SCALAR");
+        assert_eq!(document.to_string(), translated);
+
+        // Translations that would change meaning unquoted are quoted.
+        let mut document = YamlDocument::parse("answer: yes please\nlist: [a]\n").unwrap();
+        for string in document.strings_mut() {
+            string.value = match string.key().as_str() {
+                "answer" => "no".to_owned(),
+                _ => "b, c".to_owned(),
+            };
+        }
+        assert_eq!(document.to_string(), "answer: 'no'\nlist: ['b, c']\n");
+    }
+}
@@ -0,0 +1,297 @@
+use crate::TranslationError;
+use std::fmt;
+use std::str::FromStr;
+
+/// Known languages as (canonical code, ISO 639-3 code, English name). The
+/// canonical code is the ISO 639-1 code when one exists.
+const REGISTRY: &[(&str, &str, &str)] = &[
+    ("af", "afr", "Afrikaans"),
+    ("am", "amh", "Amharic"),
+    ("ar", "ara", "Arabic"),
+    ("az", "aze", "Azerbaijani"),
+    ("be", "bel", "Belarusian"),
+    ("bg", "bul", "Bulgarian"),
+    ("bn", "ben", "Bengali"),
+    ("bs", "bos", "Bosnian"),
+    ("ca", "cat", "Catalan"),
+    ("ceb", "ceb", "Cebuano"),
+    ("co", "cos", "Corsican"),
+    ("cs", "ces", "Czech"),
+    ("cy", "cym", "Welsh"),
+    ("da", "dan", "Danish"),
+    ("de", "deu", "German"),
+    ("el", "ell", "Greek"),
+    ("en", "eng", "English"),
+    ("eo", "epo", "Esperanto"),
+    ("es", "spa", "Spanish"),
+    ("et", "est", "Estonian"),
+    ("eu", "eus", "Basque"),
+    ("fa", "fas", "Persian"),
+    ("fi", "fin", "Finnish"),
+    ("fil", "fil", "Filipino"),
+    ("fr", "fra", "French"),
+    ("fy", "fry", "Western Frisian"),
+    ("ga", "gle", "Irish"),
+    ("gd", "gla", "Scottish Gaelic"),
+    ("gl", "glg", "Galician"),
+    ("gu", "guj", "Gujarati"),
+    ("ha", "hau", "Hausa"),
+    ("haw", "haw", "Hawaiian"),
+    ("he", "heb", "Hebrew"),
+    ("hi", "hin", "Hindi"),
+    ("hmn", "hmn", "Hmong"),
+    ("hr", "hrv", "Croatian"),
+    ("ht", "hat", "Haitian Creole"),
+    ("hu", "hun", "Hungarian"),
+    ("hy", "hye", "Armenian"),
+    ("id", "ind", "Indonesian"),
+    ("ig", "ibo", "Igbo"),
+    ("is", "isl", "Icelandic"),
+    ("it", "ita", "Italian"),
+    ("ja", "jpn", "Japanese"),
+    ("jv", "jav", "Javanese"),
+    ("ka", "kat", "Georgian"),
+    ("kk", "kaz", "Kazakh"),
+    ("km", "khm", "Khmer"),
+    ("kn", "kan", "Kannada"),
+    ("ko", "kor", "Korean"),
+    ("ku", "kur", "Kurdish"),
+    ("ky", "kir", "Kyrgyz"),
+    ("la", "lat", "Latin"),
+    ("lb", "ltz", "Luxembourgish"),
+    ("lo", "lao", "Lao"),
+    ("lt", "lit", "Lithuanian"),
+    ("lv", "lav", "Latvian"),
+    ("mg", "mlg", "Malagasy"),
+    ("mi", "mri", "Maori"),
+    ("mk", "mkd", "Macedonian"),
+    ("ml", "mal", "Malayalam"),
+    ("mn", "mon", "Mongolian"),
+    ("mr", "mar", "Marathi"),
+    ("ms", "msa", "Malay"),
+    ("mt", "mlt", "Maltese"),
+    ("my", "mya", "Burmese"),
+    ("nb", "nob", "Norwegian Bokmål"),
+    ("ne", "nep", "Nepali"),
+    ("nl", "nld", "Dutch"),
+    ("nn", "nno", "Norwegian Nynorsk"),
+    ("no", "nor", "Norwegian"),
+    ("ny", "nya", "Chichewa"),
+    ("pa", "pan", "Punjabi"),
+    ("pl", "pol", "Polish"),
+    ("ps", "pus", "Pashto"),
+    ("pt", "por", "Portuguese"),
+    ("ro", "ron", "Romanian"),
+    ("ru", "rus", "Russian"),
+    ("sd", "snd", "Sindhi"),
+    ("si", "sin", "Sinhala"),
+    ("sk", "slk", "Slovak"),
+    ("sl", "slv", "Slovenian"),
+    ("sm", "smo", "Samoan"),
+    ("sn", "sna", "Shona"),
+    ("so", "som", "Somali"),
+    ("sq", "sqi", "Albanian"),
+    ("sr", "srp", "Serbian"),
+    ("st", "sot", "Southern Sotho"),
+    ("su", "sun", "Sundanese"),
+    ("sv", "swe", "Swedish"),
+    ("sw", "swa", "Swahili"),
+    ("ta", "tam", "Tamil"),
+    ("te", "tel", "Telugu"),
+    ("tg", "tgk", "Tajik"),
+    ("th", "tha", "Thai"),
+    ("tl", "tgl", "Tagalog"),
+    ("tr", "tur", "Turkish"),
+    ("uk", "ukr", "Ukrainian"),
+    ("ur", "urd", "Urdu"),
+    ("uz", "uzb", "Uzbek"),
+    ("vi", "vie", "Vietnamese"),
+    ("xh", "xho", "Xhosa"),
+    ("yi", "yid", "Yiddish"),
+    ("yo", "yor", "Yoruba"),
+    ("yue", "yue", "Cantonese"),
+    ("zh", "zho", "Chinese"),
+    ("zu", "zul", "Zulu"),
+];
+
+/// Deprecated or non-standard codes still used by some services, mapped to
+/// their canonical code.
+const ALIASES: &[(&str, &str)] = &[
+    ("iw", "he"),
+    ("in", "id"),
+    ("ji", "yi"),
+    ("jw", "jv"),
+    ("mo", "ro"),
+];
+
+/// A validated language tag: a primary language with optional script and
+/// region subtags, e.g. `en`, `pt-BR` or `zh-Hant-TW`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Language {
+    code: &'static str,
+    script: Option<String>,
+    region: Option<String>,
+}
+
+impl Language {
+    /// Parses a BCP-47 tag such as `en`, `fra`, `zh_CN` or `sr-Latn-RS`,
+    /// resolving deprecated codes like `iw` to their current form.
+    pub fn parse(tag: &str) -> Result<Self, TranslationError> {
+        let invalid = || TranslationError::InvalidLanguage(tag.to_owned());
+        let mut subtags = tag.trim().split(['-', '_']);
+
+        let primary = subtags.next().unwrap_or_default().to_ascii_lowercase();
+        let primary = ALIASES
+            .iter()
+            .find(|(alias, _)| *alias == primary)
+            .map_or(primary.as_str(), |(_, code)| code);
+        let code = REGISTRY
+            .iter()
+            .find(|(code, iso639_3, _)| *code == primary || *iso639_3 == primary)
+            .map(|(code, _, _)| *code)
+            .ok_or_else(invalid)?;
+
+        let mut language = Self {
+            code,
+            script: None,
+            region: None,
+        };
+        for subtag in subtags {
+            let is_alphabetic = subtag.chars().all(|c| c.is_ascii_alphabetic());
+            let is_numeric = subtag.chars().all(|c| c.is_ascii_digit());
+
+            if subtag.len() == 4 && is_alphabetic && language.script.is_none() {
+                if language.region.is_some() {
+                    return Err(invalid());
+                }
+                let (first, rest) = subtag.split_at(1);
+                language.script = Some(first.to_ascii_uppercase() + &rest.to_ascii_lowercase());
+            } else if (subtag.len() == 2 && is_alphabetic || subtag.len() == 3 && is_numeric)
+                && language.region.is_none()
+            {
+                language.region = Some(subtag.to_ascii_uppercase());
+            } else {
+                return Err(invalid());
+            }
+        }
+
+        Ok(language)
+    }
+
+    /// The primary language code, ISO 639-1 where one exists.
+    pub fn code(&self) -> &'static str {
+        self.code
+    }
+
+    /// The ISO 639-3 code of the primary language.
+    pub fn iso639_3(&self) -> &'static str {
+        self.entry().1
+    }
+
+    /// The English name of the primary language.
+    pub fn name(&self) -> &'static str {
+        self.entry().2
+    }
+
+    /// The script subtag, either given explicitly or implied by the region
+    /// (`zh-TW` is written in `Hant`).
+    pub fn script(&self) -> Option<&str> {
+        if let Some(script) = &self.script {
+            return Some(script);
+        }
+        match (self.code, self.region.as_deref()) {
+            ("zh", Some("TW" | "HK" | "MO")) => Some("Hant"),
+            ("zh", Some(_)) => Some("Hans"),
+            _ => None,
+        }
+    }
+
+    pub fn region(&self) -> Option<&str> {
+        self.region.as_deref()
+    }
+
+    fn entry(&self) -> &'static (&'static str, &'static str, &'static str) {
+        REGISTRY
+            .iter()
+            .find(|(code, _, _)| *code == self.code)
+            .expect("language codes come from the registry")
+    }
+}
+
+impl FromStr for Language {
+    type Err = TranslationError;
+
+    fn from_str(tag: &str) -> Result<Self, Self::Err> {
+        Self::parse(tag)
+    }
+}
+
+impl fmt::Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.code)?;
+        if let Some(script) = &self.script {
+            write!(f, "-{}", script)?;
+        }
+        if let Some(region) = &self.region {
+            write!(f, "-{}", region)?;
+        }
+        Ok(())
+    }
+}
+
+/// Canonicalizes a language code reported by a backend, keeping it as it is
+/// when it is not a known tag.
+pub(crate) fn normalize_code(code: &str) -> String {
+    Language::parse(code).map_or_else(|_| code.to_owned(), |language| language.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_tags() {
+        let language = Language::parse("zh_hant_tw").unwrap();
+        assert_eq!(language.to_string(), "zh-Hant-TW");
+        assert_eq!(language.script(), Some("Hant"));
+        assert_eq!(language.region(), Some("TW"));
+
+        assert_eq!(Language::parse("fra").unwrap().to_string(), "fr");
+        assert_eq!(Language::parse("ceb").unwrap().name(), "Cebuano");
+        assert_eq!(Language::parse("pt-BR").unwrap().iso639_3(), "por");
+        assert_eq!(Language::parse("es-419").unwrap().region(), Some("419"));
+        assert_eq!(Language::parse("zh-CN").unwrap().script(), Some("Hans"));
+    }
+
+    #[test]
+    fn test_aliases() {
+        assert_eq!(
+            Language::parse("iw").unwrap(),
+            Language::parse("he").unwrap()
+        );
+        assert_eq!(Language::parse("jw").unwrap().code(), "jv");
+        assert_eq!(normalize_code("iw"), "he");
+        assert_eq!(normalize_code("xx"), "xx");
+    }
+
+    #[test]
+    fn test_invalid_tags() {
+        for tag in [
+            "",
+            "fre",
+            "english",
+            "en-US-GB",
+            "en-Latn-Latn",
+            "en-US-Latn",
+            "en-1",
+        ] {
+            assert!(
+                matches!(
+                    Language::parse(tag),
+                    Err(TranslationError::InvalidLanguage(_))
+                ),
+                "{tag} should be rejected"
+            );
+        }
+    }
+}
@@ -0,0 +1,768 @@
+//! Translate text through Google, DeepL, LibreTranslate, an LLM or offline
+//! dictionaries.
+//!
+//! A [`Translator`] sends text in one language pair to a
+//! [`TranslationBackend`], validating languages, splitting long texts,
+//! retrying failed requests and staying within the provider's rate limits:
+//!
+//! ```no_run
+//! # async fn example() -> Result<(), rustranslate::TranslationError> {
+//! let translator = rustranslate::Translator::new("en", "fr")?;
+//! let translation = translator.translate("Good morning").await?;
+//! println!("{}", translation.text);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Other providers are set up through [`Translator::with_backend`] with one
+//! of the types in [`backend`], or any implementation of
+//! [`TranslationBackend`]. [`Translator::builder`] configures the HTTP client
+//! instead: timeouts, proxies, certificates, headers or another server.
+//!
+//! Localization files, such as gettext catalogs, Fluent resources, XLIFF
+//! files or JSON, YAML and TOML locale files, are translated in place by the
+//! types in [`formats`].
+//!
+//! # Features
+//!
+//! Each backend is behind a feature of the same name: `google`, `deepl`,
+//! `libretranslate`, `llm` and `offline`; all but `offline` enable `http`,
+//! which provides [`TranslatorBuilder`]. `cache` adds the persistent
+//! [`DiskCache`], and `cli` the `rustranslate` command line together with
+//! every backend. Only `cli` is enabled by default, so library users
+//! disable default features and pick what they need:
+//!
+//! ```toml
+//! rustranslate = { version = "0.1", default-features = false, features = ["deepl"] }
+//! ```
+//!
+//! The library expects to run inside a Tokio runtime.
+
+#[cfg(feature = "google")]
+use backend::GoogleGtxBackend;
+pub use backend::TranslationBackend;
+#[cfg(feature = "http")]
+pub use builder::TranslatorBuilder;
+pub use cache::{Cache, CacheKey, CacheStats, TranslationCache};
+#[cfg(feature = "cache")]
+pub use cache::{CacheEntry, CacheError, DiskCache, DiskCacheInfo};
+pub use detect::{detect_offline, Detection};
+pub use error::{HttpError, ParseError, TranslationError};
+use futures_util::stream::{self, StreamExt};
+use language::normalize_code;
+pub use language::Language;
+pub use lookup::{
+    Alternatives, Candidate, Definition, DictionaryEntry, DictionaryTerm, Lookup, Romanization,
+    Synonyms,
+};
+pub use rate_limit::{RateLimit, RateLimiter};
+#[cfg(feature = "http")]
+pub use reqwest;
+pub use retry::{Clock, Jitter, RetryPolicy, TokioClock};
+pub use segment::Segmenter;
+use std::sync::Arc;
+pub use translation::{Segment, Translation};
+
+pub mod backend;
+#[cfg(feature = "http")]
+mod builder;
+mod cache;
+#[cfg(feature = "cli")]
+pub mod cli;
+mod detect;
+mod error;
+pub mod formats;
+mod language;
+mod lookup;
+mod rate_limit;
+mod retry;
+mod segment;
+#[cfg(test)]
+mod test_support;
+mod translation;
+
+/// Source language that asks the backend to detect the language itself.
+pub const AUTO_DETECT: &str = "auto";
+
+/// Number of requests `Translator::translate_batch` runs at once by default.
+const DEFAULT_CONCURRENCY: usize = 4;
+
+/// Translates text through a backend. Clones share the backend, retry
+/// policy, cache and rate limiter.
+#[derive(Debug, Clone)]
+pub struct Translator {
+    /// `None` when the source language is detected for each text.
+    source_lang: Option<Language>,
+    target_lang: Language,
+    backend: Arc<dyn TranslationBackend>,
+    retry_policy: Arc<RetryPolicy>,
+    concurrency: usize,
+    cache: Option<Arc<dyn Cache>>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+}
+
+impl Translator {
+    /// Creates a translator using Google's gtx endpoint. The languages are
+    /// BCP-47 tags; a source language of [`AUTO_DETECT`] detects it instead.
+    #[cfg(feature = "google")]
+    pub fn new(source_lang: &str, target_lang: &str) -> Result<Self, TranslationError> {
+        Self::with_backend(source_lang, target_lang, GoogleGtxBackend::new())
+    }
+
+    /// Creates a translator that detects the source language of each text.
+    #[cfg(feature = "google")]
+    pub fn auto_detect(target_lang: &str) -> Result<Self, TranslationError> {
+        Self::new(AUTO_DETECT, target_lang)
+    }
+
+    /// Starts building a translator with its own HTTP client settings, such
+    /// as timeouts or a proxy.
+    #[cfg(feature = "http")]
+    pub fn builder(source_lang: &str, target_lang: &str) -> TranslatorBuilder {
+        TranslatorBuilder::new(source_lang, target_lang)
+    }
+
+    /// Creates a translator sending its requests to `backend`.
+    pub fn with_backend(
+        source_lang: &str,
+        target_lang: &str,
+        backend: impl TranslationBackend + 'static,
+    ) -> Result<Self, TranslationError> {
+        let source_lang = match source_lang {
+            AUTO_DETECT => None,
+            tag => Some(Language::parse(tag)?),
+        };
+
+        let rate_limiter = backend
+            .rate_limit()
+            .map(|limit| Arc::new(RateLimiter::new(limit)));
+        Ok(Self {
+            source_lang,
+            target_lang: Language::parse(target_lang)?,
+            backend: Arc::new(backend),
+            retry_policy: Arc::new(RetryPolicy::default()),
+            concurrency: DEFAULT_CONCURRENCY,
+            cache: None,
+            rate_limiter,
+        })
+    }
+
+    /// Replaces the policy deciding how failed requests are retried.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Arc::new(retry_policy);
+        self
+    }
+
+    /// Limits how many requests `translate_batch` sends at the same time.
+    pub fn with_concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency.max(1);
+        self
+    }
+
+    /// Serves repeated translations from `cache`, either in memory or on
+    /// disk, which may be shared with other translators.
+    pub fn with_cache(mut self, cache: Arc<dyn Cache>) -> Self {
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Replaces the backend's default rate limit.
+    pub fn with_rate_limit(self, limit: RateLimit) -> Self {
+        self.with_rate_limiter(Arc::new(RateLimiter::new(limit)))
+    }
+
+    /// Shares `rate_limiter` with other translators using the same
+    /// provider, so that together they stay within its limits.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Sends requests as fast as the backend answers them.
+    pub fn without_rate_limit(mut self) -> Self {
+        self.rate_limiter = None;
+        self
+    }
+
+    pub fn source_lang(&self) -> Option<&Language> {
+        self.source_lang.as_ref()
+    }
+
+    pub fn target_lang(&self) -> &Language {
+        &self.target_lang
+    }
+
+    pub fn backend(&self) -> &dyn TranslationBackend {
+        self.backend.as_ref()
+    }
+
+    pub async fn translate(&self, word: &str) -> Result<Translation, TranslationError> {
+        self.translate_in_context(word, None).await
+    }
+
+    /// Translates `text` given `context` describing where it appears, e.g.
+    /// a comment left for translators. Backends that cannot use context
+    /// translate the text alone.
+    pub async fn translate_with_context(
+        &self,
+        text: &str,
+        context: &str,
+    ) -> Result<Translation, TranslationError> {
+        self.translate_in_context(text, Some(context)).await
+    }
+
+    async fn translate_in_context(
+        &self,
+        text: &str,
+        context: Option<&str>,
+    ) -> Result<Translation, TranslationError> {
+        let (before, content, after) = split_whitespace(text);
+        if content.is_empty() {
+            return Err(TranslationError::EmptyInput);
+        }
+
+        if let Some(translation) = self.cached(content, context).await {
+            return Ok(translation.padded(before, after));
+        }
+        let translation = self.translate_content(content, context).await?;
+        self.store(content, context, &translation).await;
+        Ok(translation.padded(before, after))
+    }
+
+    /// Translates trimmed, non-empty text, splitting it when it is longer
+    /// than the backend accepts.
+    async fn translate_content(
+        &self,
+        text: &str,
+        context: Option<&str>,
+    ) -> Result<Translation, TranslationError> {
+        match self.backend.max_text_len() {
+            Some(max_len) if text.chars().count() > max_len => {
+                self.translate_long(text, context, max_len).await
+            }
+            _ => self.translate_text(text, context).await,
+        }
+    }
+
+    async fn translate_text(
+        &self,
+        text: &str,
+        context: Option<&str>,
+    ) -> Result<Translation, TranslationError> {
+        let (source_code, target_code) = (self.source_code(), self.target_code());
+        let translation = self
+            .retry_policy
+            .run(|| async {
+                self.throttle(text.chars().count()).await;
+                match context {
+                    Some(context) => {
+                        self.backend
+                            .translate_with_context(text, &source_code, &target_code, context)
+                            .await
+                    }
+                    None => {
+                        self.backend
+                            .translate(text, &source_code, &target_code)
+                            .await
+                    }
+                }
+            })
+            .await?;
+        Ok(self.with_detection(translation))
+    }
+
+    /// Translates text longer than the backend accepts by splitting it into
+    /// chunks on sentence boundaries and joining the translated chunks with
+    /// the original whitespace.
+    async fn translate_long(
+        &self,
+        text: &str,
+        context: Option<&str>,
+        max_len: usize,
+    ) -> Result<Translation, TranslationError> {
+        let segmenter = Segmenter::new(self.source_lang.as_ref(), max_len);
+        let translations: Vec<_> = stream::iter(segmenter.chunks(text))
+            .map(|chunk| async move {
+                let (before, content, after) = split_whitespace(chunk);
+                if content.is_empty() {
+                    return Ok(Translation::new(chunk, chunk));
+                }
+                Ok(self
+                    .translate_text(content, context)
+                    .await?
+                    .padded(before, after))
+            })
+            .buffered(self.concurrency)
+            .collect()
+            .await;
+        let translations = translations
+            .into_iter()
+            .collect::<Result<Vec<_>, TranslationError>>()?;
+
+        let detected = translations.iter().find_map(|translation| {
+            let lang = translation.detected_source_lang.clone()?;
+            Some((lang, translation.confidence))
+        });
+        let segments = translations
+            .into_iter()
+            .flat_map(|translation| translation.segments)
+            .collect();
+        let translation = Translation::from_segments(segments);
+
+        Ok(match detected {
+            Some((lang, confidence)) => translation.with_detected_source_lang(lang, confidence),
+            None => translation,
+        })
+    }
+
+    /// Translates `texts`, returning one result per text in input order.
+    /// Cached texts are answered without a request. Backends that accept
+    /// several texts per request receive the rest in batches; a failed
+    /// batch is retried text by text so that one bad input does not fail
+    /// the others.
+    pub async fn translate_batch(
+        &self,
+        texts: &[&str],
+    ) -> Vec<Result<Translation, TranslationError>> {
+        let mut results: Vec<Option<Result<Translation, TranslationError>>> = Vec::new();
+        let mut pending = Vec::new();
+        for text in texts {
+            let (_, content, _) = split_whitespace(text);
+            if content.is_empty() {
+                results.push(Some(Err(TranslationError::EmptyInput)));
+            } else if let Some(translation) = self.cached(content, None).await {
+                results.push(Some(Ok(translation)));
+            } else {
+                pending.push((results.len(), content));
+                results.push(None);
+            }
+        }
+
+        let contents: Vec<&str> = pending.iter().map(|(_, content)| *content).collect();
+        let batch_size = self.backend.max_batch_size().max(1);
+        let translated: Vec<_> = stream::iter(contents.chunks(batch_size))
+            .map(|chunk| self.translate_chunk(chunk))
+            .buffered(self.concurrency)
+            .flat_map(stream::iter)
+            .collect()
+            .await;
+
+        for ((i, content), result) in pending.into_iter().zip(translated) {
+            if let Ok(translation) = &result {
+                self.store(content, None, translation).await;
+            }
+            results[i] = Some(result);
+        }
+
+        texts
+            .iter()
+            .zip(results)
+            .map(|(text, result)| {
+                let (before, _, after) = split_whitespace(text);
+                let result = result.expect("every text has a result");
+                result.map(|translation| translation.padded(before, after))
+            })
+            .collect()
+    }
+
+    /// Translates a batch of trimmed, non-empty texts.
+    async fn translate_chunk(&self, texts: &[&str]) -> Vec<Result<Translation, TranslationError>> {
+        if texts.len() > 1 {
+            let (source_code, target_code) = (self.source_code(), self.target_code());
+            let chars = texts.iter().map(|text| text.chars().count()).sum();
+            let batch = self
+                .retry_policy
+                .run(|| async {
+                    self.throttle(chars).await;
+                    self.backend
+                        .translate_batch(texts, &source_code, &target_code)
+                        .await
+                })
+                .await;
+
+            if let Ok(translations) = batch {
+                if translations.len() == texts.len() {
+                    return translations
+                        .into_iter()
+                        .map(|translation| Ok(self.with_detection(translation)))
+                        .collect();
+                }
+            }
+        }
+
+        let mut results = Vec::with_capacity(texts.len());
+        for text in texts {
+            results.push(self.translate_content(text, None).await);
+        }
+        results
+    }
+
+    /// Waits until a request sending `chars` characters is within the rate
+    /// limit.
+    async fn throttle(&self, chars: usize) {
+        if let Some(rate_limiter) = &self.rate_limiter {
+            rate_limiter.acquire(chars).await;
+        }
+    }
+
+    /// The cache key of `text`. A context may change the translation, so
+    /// it counts as one of the backend's options.
+    fn cache_key(&self, text: &str, context: Option<&str>) -> CacheKey {
+        let mut options = self.backend.options();
+        if let Some(context) = context {
+            options = format!("{} context={:?}", options, context);
+        }
+        CacheKey::new(
+            self.backend.name(),
+            options,
+            self.source_code(),
+            self.target_code(),
+            text,
+        )
+    }
+
+    async fn cached(&self, text: &str, context: Option<&str>) -> Option<Translation> {
+        let mut translation = self
+            .cache
+            .as_ref()?
+            .get(&self.cache_key(text, context))
+            .await?;
+        // Keys ignore spacing, so the entry may have been stored for
+        // differently spaced text, whose segments no longer fit.
+        if translation.source_text != text {
+            translation.source_text = text.to_owned();
+            translation.segments = vec![Segment {
+                source: text.to_owned(),
+                target: translation.text.clone(),
+            }];
+        }
+        Some(translation)
+    }
+
+    async fn store(&self, text: &str, context: Option<&str>, translation: &Translation) {
+        if let Some(cache) = &self.cache {
+            cache
+                .insert(self.cache_key(text, context), translation.clone())
+                .await;
+        }
+    }
+
+    /// Returns alternatives, dictionary entries, definitions and other
+    /// word-level details for `word`, if the backend provides them.
+    pub async fn lookup(&self, word: &str) -> Result<Lookup, TranslationError> {
+        let (source_code, target_code) = (self.source_code(), self.target_code());
+        self.retry_policy
+            .run(|| async {
+                self.throttle(word.chars().count()).await;
+                self.backend.lookup(word, &source_code, &target_code).await
+            })
+            .await
+    }
+
+    /// Detects the language of `text` with the backend, falling back to
+    /// the local detector when the backend cannot be reached.
+    pub async fn detect(&self, text: &str) -> Result<Detection, TranslationError> {
+        if text.trim().is_empty() {
+            return Err(TranslationError::EmptyInput);
+        }
+
+        let detection = self
+            .retry_policy
+            .run(|| async {
+                self.throttle(text.chars().count()).await;
+                self.backend.detect(text).await
+            })
+            .await;
+        match detection {
+            Ok(mut detection) => {
+                detection.language = normalize_code(&detection.language);
+                Ok(detection)
+            }
+            Err(
+                err @ (TranslationError::Timeout(_)
+                | TranslationError::Connect(_)
+                | TranslationError::RequestFailed(_)),
+            ) => detect_offline(text).ok_or(err),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn source_code(&self) -> String {
+        match &self.source_lang {
+            Some(language) => self.backend.language_code(language),
+            None => AUTO_DETECT.to_owned(),
+        }
+    }
+
+    fn target_code(&self) -> String {
+        self.backend.language_code(&self.target_lang)
+    }
+
+    /// Fills in the source language of `translation` when the backend did
+    /// not report one: the configured language, or a local detection when
+    /// auto-detecting. Reported codes are canonicalized.
+    fn with_detection(&self, translation: Translation) -> Translation {
+        if let Some(lang) = &translation.detected_source_lang {
+            let (lang, confidence) = (normalize_code(lang), translation.confidence);
+            return translation.with_detected_source_lang(lang, confidence);
+        }
+
+        if let Some(language) = &self.source_lang {
+            return translation.with_detected_source_lang(language.to_string(), None);
+        }
+
+        match detect_offline(&translation.source_text) {
+            Some(detection) => {
+                translation.with_detected_source_lang(detection.language, detection.confidence)
+            }
+            None => translation,
+        }
+    }
+
+    pub async fn supported_languages(&self) -> Result<Vec<String>, TranslationError> {
+        self.backend.supported_languages().await
+    }
+
+    /// Returns the source/target pairs the backend can translate between.
+    pub async fn supported_pairs(&self) -> Result<Vec<(Language, Language)>, TranslationError> {
+        self.backend.supported_pairs().await
+    }
+}
+
+/// Splits `text` into its leading whitespace, content and trailing
+/// whitespace.
+fn split_whitespace(text: &str) -> (&str, &str, &str) {
+    let content = text.trim();
+    let before = &text[..text.len() - text.trim_start().len()];
+    let after = &text[before.len() + content.len()..];
+    (before, content, after)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::FakeClock;
+    use std::time::Duration;
+
+    #[cfg(feature = "google")]
+    #[tokio::test]
+    async fn test_translation_success() {
+        let translator = Translator::new("en", "fr").unwrap();
+        let translation = translator.translate("hello").await.unwrap();
+        assert_eq!(translation.text, "Bonjour");
+    }
+
+    #[derive(Debug)]
+    struct EchoBackend;
+
+    #[async_trait::async_trait]
+    impl TranslationBackend for EchoBackend {
+        fn name(&self) -> &'static str {
+            "echo"
+        }
+
+        async fn translate(
+            &self,
+            text: &str,
+            source_lang: &str,
+            target_lang: &str,
+        ) -> Result<Translation, TranslationError> {
+            if text == "fail" {
+                return Err(TranslationError::NoTranslationFound(text.to_owned()));
+            }
+            Ok(Translation::new(
+                text,
+                format!("{}->{}: {}", source_lang, target_lang, text),
+            ))
+        }
+
+        fn max_text_len(&self) -> Option<usize> {
+            Some(20)
+        }
+
+        fn max_batch_size(&self) -> usize {
+            2
+        }
+
+        async fn translate_batch(
+            &self,
+            texts: &[&str],
+            _source_lang: &str,
+            _target_lang: &str,
+        ) -> Result<Vec<Translation>, TranslationError> {
+            texts
+                .iter()
+                .map(|text| match *text {
+                    "fail" => Err(TranslationError::NoTranslationFound(text.to_string())),
+                    text => Ok(Translation::new(text, format!("batch: {}", text))),
+                })
+                .collect()
+        }
+
+        async fn detect(&self, _text: &str) -> Result<Detection, TranslationError> {
+            Err(TranslationError::RequestFailed(None))
+        }
+
+        async fn supported_languages(&self) -> Result<Vec<String>, TranslationError> {
+            Ok(vec!["en".to_owned(), "fr".to_owned()])
+        }
+    }
+
+    #[tokio::test]
+    async fn test_translation_uses_custom_backend() {
+        let translator = Translator::with_backend("en", "fr", EchoBackend).unwrap();
+        assert_eq!(translator.backend().name(), "echo");
+        assert_eq!(
+            translator.translate("hello").await.unwrap().text,
+            "en->fr: hello"
+        );
+        assert!(matches!(
+            translator.translate(" \n").await,
+            Err(TranslationError::EmptyInput)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_translate_batch_keeps_order_and_partial_results() {
+        let translator = Translator::with_backend("en", "fr", EchoBackend)
+            .unwrap()
+            .with_concurrency(2);
+
+        let results = translator
+            .translate_batch(&["a", "b", "fail", "c", "d"])
+            .await;
+        let texts: Vec<_> = results
+            .iter()
+            .map(|result| {
+                result
+                    .as_ref()
+                    .ok()
+                    .map(|translation| translation.text.as_str())
+            })
+            .collect();
+
+        assert_eq!(texts[..2], [Some("batch: a"), Some("batch: b")]);
+        assert!(matches!(
+            results[2],
+            Err(TranslationError::NoTranslationFound(_))
+        ));
+        assert_eq!(texts[3..], [Some("en->fr: c"), Some("en->fr: d")]);
+    }
+
+    #[tokio::test]
+    async fn test_cache_serves_repeated_translations() {
+        let cache = Arc::new(TranslationCache::new(16));
+        let translator = Translator::with_backend("en", "fr", EchoBackend)
+            .unwrap()
+            .with_cache(cache.clone());
+
+        let first = translator.translate("hello").await.unwrap();
+        assert!(!first.from_cache);
+        let second = translator.translate(" hello\n").await.unwrap();
+        assert!(second.from_cache);
+        assert_eq!(second.text, " en->fr: hello\n");
+        assert_eq!(second.source_text, " hello\n");
+
+        let results = translator.translate_batch(&["hello", "bye"]).await;
+        assert!(results[0].as_ref().unwrap().from_cache);
+        assert!(!results[1].as_ref().unwrap().from_cache);
+        assert_eq!(
+            cache.stats(),
+            CacheStats {
+                hits: 2,
+                misses: 2,
+                entries: 2
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_cache_hits_keep_the_callers_source_text() {
+        let translator = Translator::with_backend("en", "fr", EchoBackend)
+            .unwrap()
+            .with_cache(Arc::new(TranslationCache::new(16)));
+        translator.translate("hello world").await.unwrap();
+
+        let spaced = translator.translate("hello \t world").await.unwrap();
+        assert!(spaced.from_cache);
+        assert_eq!(spaced.text, "en->fr: hello world");
+        assert_eq!(spaced.source_text, "hello \t world");
+        assert_eq!(
+            spaced.segments,
+            [Segment {
+                source: "hello \t world".to_owned(),
+                target: "en->fr: hello world".to_owned(),
+            }]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_clones_share_the_rate_limiter() {
+        let clock = FakeClock::new();
+        let limit = RateLimit::new().with_requests_per_second(1.0);
+        let translator = Translator::with_backend("en", "fr", EchoBackend)
+            .unwrap()
+            .with_rate_limiter(Arc::new(RateLimiter::with_clock(limit, clock.clone())));
+        let clone = translator.clone();
+
+        translator.translate("a").await.unwrap();
+        clone.translate("b").await.unwrap();
+        translator.translate("c").await.unwrap();
+        assert_eq!(clock.sleeps(), [Duration::from_secs(1); 2]);
+    }
+
+    #[tokio::test]
+    async fn test_long_text_is_translated_in_chunks() {
+        let translator = Translator::with_backend("en", "fr", EchoBackend).unwrap();
+
+        let text = " First sentence here. Second one!\n\nThird.\n";
+        let translation = translator.translate(text).await.unwrap();
+        assert_eq!(
+            translation.text,
+            " en->fr: First sentence here. en->fr: Second one!\n\nThird.\n"
+        );
+        assert_eq!(translation.source_text, text);
+        assert_eq!(translation.segments.len(), 2);
+        assert_eq!(translation.detected_source_lang.as_deref(), Some("en"));
+    }
+
+    #[tokio::test]
+    async fn test_auto_detect_falls_back_to_offline_detection() {
+        let translator = Translator::with_backend(AUTO_DETECT, "en", EchoBackend)
+            .unwrap()
+            .with_retry_policy(RetryPolicy::none());
+
+        let translation = translator
+            .translate("Wir haben noch nicht über den Preis gesprochen.")
+            .await
+            .unwrap();
+        assert_eq!(translation.detected_source_lang.as_deref(), Some("de"));
+
+        let detection = translator
+            .detect("Elle ne sait pas pourquoi nous sommes partis si tôt.")
+            .await
+            .unwrap();
+        assert_eq!(detection.language, "fr");
+        assert!(detection.offline);
+    }
+
+    #[tokio::test]
+    async fn test_languages_are_validated() {
+        assert!(matches!(
+            Translator::with_backend("fre", "en", EchoBackend),
+            Err(TranslationError::InvalidLanguage(tag)) if tag == "fre"
+        ));
+
+        let translator = Translator::with_backend("iw", "pt_br", EchoBackend).unwrap();
+        assert_eq!(translator.target_lang().to_string(), "pt-BR");
+        assert_eq!(
+            translator.translate("shalom").await.unwrap().text,
+            "he->pt-BR: shalom"
+        );
+
+        let pairs = translator.supported_pairs().await.unwrap();
+        let en = Language::parse("en").unwrap();
+        let fr = Language::parse("fr").unwrap();
+        assert_eq!(pairs, [(en.clone(), fr.clone()), (fr, en)]);
+    }
+}
@@ -0,0 +1,70 @@
+use crate::Translation;
+
+/// Word-level details about a translation: alternatives, dictionary
+/// entries, definitions, examples and related data.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lookup {
+    pub translation: Translation,
+    /// Alternative translations for each source segment.
+    pub alternatives: Vec<Alternatives>,
+    /// Dictionary entries grouped by part of speech.
+    pub dictionary: Vec<DictionaryEntry>,
+    /// Definitions of the source word.
+    pub definitions: Vec<Definition>,
+    /// Example sentences using the source word.
+    pub examples: Vec<String>,
+    /// Synonyms of the source word grouped by part of speech.
+    pub synonyms: Vec<Synonyms>,
+    pub romanization: Option<Romanization>,
+    /// The corrected input, when the source looks misspelled.
+    pub spelling_correction: Option<String>,
+}
+
+/// Candidate translations for one source segment.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alternatives {
+    pub source: String,
+    pub candidates: Vec<Candidate>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub text: String,
+    pub score: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct DictionaryEntry {
+    pub part_of_speech: String,
+    pub base_form: Option<String>,
+    pub terms: Vec<DictionaryTerm>,
+}
+
+/// A translation of the source word along with the source-language words
+/// it translates back to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DictionaryTerm {
+    pub word: String,
+    pub reverse_translations: Vec<String>,
+    pub score: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Definition {
+    pub part_of_speech: String,
+    pub gloss: String,
+    pub example: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Synonyms {
+    pub part_of_speech: String,
+    pub words: Vec<String>,
+}
+
+/// Latin-script readings of the source and translated text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Romanization {
+    pub source: Option<String>,
+    pub target: Option<String>,
+}
@@ -0,0 +1,198 @@
+use crate::retry::{Clock, TokioClock};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Request and character budgets a backend's provider allows.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RateLimit {
+    requests_per_second: Option<f64>,
+    chars_per_minute: Option<f64>,
+    burst: u32,
+}
+
+impl Default for RateLimit {
+    /// No limit, with a burst of one request.
+    fn default() -> Self {
+        Self {
+            requests_per_second: None,
+            chars_per_minute: None,
+            burst: 1,
+        }
+    }
+}
+
+impl RateLimit {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_requests_per_second(mut self, requests: f64) -> Self {
+        self.requests_per_second = Some(requests).filter(|requests| *requests > 0.0);
+        self
+    }
+
+    /// Limits the characters of text sent per minute. Up to a minute's worth
+    /// may be sent at once.
+    pub fn with_chars_per_minute(mut self, chars: u32) -> Self {
+        self.chars_per_minute = Some(f64::from(chars)).filter(|chars| *chars > 0.0);
+        self
+    }
+
+    /// Number of requests that may be sent at once after a quiet period.
+    pub fn with_burst(mut self, burst: u32) -> Self {
+        self.burst = burst.max(1);
+        self
+    }
+
+    pub fn requests_per_second(&self) -> Option<f64> {
+        self.requests_per_second
+    }
+
+    pub fn chars_per_minute(&self) -> Option<f64> {
+        self.chars_per_minute
+    }
+
+    pub fn burst(&self) -> u32 {
+        self.burst
+    }
+}
+
+/// A token bucket refilling at `rate` tokens per second up to `capacity`.
+/// Its tokens go negative when more are taken than are available, which
+/// makes later callers wait their turn.
+#[derive(Debug)]
+struct Bucket {
+    rate: f64,
+    capacity: f64,
+    tokens: f64,
+}
+
+impl Bucket {
+    fn new(rate: f64, capacity: f64) -> Self {
+        Self {
+            rate,
+            capacity,
+            tokens: capacity,
+        }
+    }
+
+    fn refill(&mut self, elapsed: Duration) {
+        self.tokens = (self.tokens + elapsed.as_secs_f64() * self.rate).min(self.capacity);
+    }
+
+    /// Takes `cost` tokens, returning how long to wait until they are paid
+    /// for.
+    fn take(&mut self, cost: f64) -> Duration {
+        self.tokens -= cost;
+        if self.tokens >= 0.0 {
+            Duration::ZERO
+        } else {
+            Duration::from_secs_f64(-self.tokens / self.rate)
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    updated: Instant,
+    requests: Option<Bucket>,
+    chars: Option<Bucket>,
+}
+
+/// Spaces out requests to stay within a [`RateLimit`]. Translators sharing
+/// a limiter share its budget.
+#[derive(Debug)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    clock: Arc<dyn Clock>,
+    state: Mutex<State>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self::with_clock(limit, TokioClock)
+    }
+
+    pub fn with_clock(limit: RateLimit, clock: impl Clock + 'static) -> Self {
+        let state = State {
+            updated: clock.now(),
+            requests: limit
+                .requests_per_second
+                .map(|rate| Bucket::new(rate, f64::from(limit.burst))),
+            chars: limit
+                .chars_per_minute
+                .map(|per_minute| Bucket::new(per_minute / 60.0, per_minute)),
+        };
+        Self {
+            limit,
+            clock: Arc::new(clock),
+            state: Mutex::new(state),
+        }
+    }
+
+    pub fn limit(&self) -> &RateLimit {
+        &self.limit
+    }
+
+    /// Waits until a request sending `chars` characters fits the budget.
+    /// Callers are served in the order they call.
+    pub async fn acquire(&self, chars: usize) {
+        let delay = {
+            let mut state = self
+                .state
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            let now = self.clock.now();
+            let elapsed = now.saturating_duration_since(state.updated);
+            state.updated = now;
+
+            let mut delay = Duration::ZERO;
+            if let Some(bucket) = &mut state.requests {
+                bucket.refill(elapsed);
+                delay = delay.max(bucket.take(1.0));
+            }
+            if let Some(bucket) = &mut state.chars {
+                bucket.refill(elapsed);
+                delay = delay.max(bucket.take(chars as f64));
+            }
+            delay
+        };
+
+        if !delay.is_zero() {
+            self.clock.sleep(delay).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::FakeClock;
+
+    #[tokio::test]
+    async fn test_requests_per_second_with_burst() {
+        let clock = FakeClock::new();
+        let limit = RateLimit::new().with_requests_per_second(2.0).with_burst(2);
+        let limiter = RateLimiter::with_clock(limit, clock.clone());
+
+        for _ in 0..4 {
+            limiter.acquire(10).await;
+        }
+        assert_eq!(clock.sleeps(), [Duration::from_millis(500); 2]);
+    }
+
+    #[tokio::test]
+    async fn test_chars_per_minute() {
+        let clock = FakeClock::new();
+        let limit = RateLimit::new().with_chars_per_minute(600);
+        let limiter = RateLimiter::with_clock(limit, clock.clone());
+
+        limiter.acquire(500).await;
+        limiter.acquire(200).await;
+        limiter.acquire(10).await;
+        assert_eq!(
+            clock.sleeps(),
+            [Duration::from_secs(10), Duration::from_secs(1)]
+        );
+    }
+}
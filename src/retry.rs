@@ -0,0 +1,320 @@
+use crate::TranslationError;
+use async_trait::async_trait;
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::fmt;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// How the delay between attempts is randomized.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Jitter {
+    /// Wait exactly the exponential backoff.
+    None,
+    /// Wait a random time between zero and the exponential backoff.
+    Full,
+    /// Wait a random time between the base delay and three times the
+    /// previous delay.
+    Decorrelated,
+}
+
+/// Source of time for retries, replaceable to make tests deterministic.
+#[async_trait]
+pub trait Clock: fmt::Debug + Send + Sync {
+    fn now(&self) -> Instant;
+
+    async fn sleep(&self, duration: Duration);
+}
+
+/// The real clock, sleeping with `tokio::time::sleep`.
+#[derive(Debug, Default)]
+pub struct TokioClock;
+
+#[async_trait]
+impl Clock for TokioClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+}
+
+type RetryIf = dyn Fn(&TranslationError) -> bool + Send + Sync;
+
+/// Decides whether and when a failed request is sent again.
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    max_elapsed: Option<Duration>,
+    jitter: Jitter,
+    retry_if: Arc<RetryIf>,
+    clock: Arc<dyn Clock>,
+    rng: Mutex<Box<dyn RngCore + Send>>,
+}
+
+impl Default for RetryPolicy {
+    /// Four attempts with full jitter starting at 500ms, giving up after a
+    /// minute, retrying the errors [`TranslationError::is_retryable`] accepts.
+    fn default() -> Self {
+        Self {
+            max_attempts: 4,
+            base_delay: Duration::from_millis(500),
+            max_delay: Duration::from_secs(30),
+            max_elapsed: Some(Duration::from_secs(60)),
+            jitter: Jitter::Full,
+            retry_if: Arc::new(TranslationError::is_retryable),
+            clock: Arc::new(TokioClock),
+            rng: Mutex::new(Box::new(StdRng::from_entropy())),
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A policy that makes a single attempt.
+    pub fn none() -> Self {
+        Self::default().with_max_attempts(1)
+    }
+
+    /// Total number of attempts, including the first one.
+    pub fn with_max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry, doubled for each further retry.
+    pub fn with_base_delay(mut self, base_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self
+    }
+
+    /// Upper bound of the computed backoff. A longer `Retry-After` sent by
+    /// the server is still honored.
+    pub fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Stops retrying once the next attempt would start later than
+    /// `max_elapsed` after the first one.
+    pub fn with_max_elapsed(mut self, max_elapsed: Option<Duration>) -> Self {
+        self.max_elapsed = max_elapsed;
+        self
+    }
+
+    pub fn with_jitter(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Chooses which errors are retried.
+    pub fn with_retry_if(
+        mut self,
+        retry_if: impl Fn(&TranslationError) -> bool + Send + Sync + 'static,
+    ) -> Self {
+        self.retry_if = Arc::new(retry_if);
+        self
+    }
+
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Uses `rng` for jitter instead of a randomly seeded generator.
+    pub fn with_rng(mut self, rng: impl RngCore + Send + 'static) -> Self {
+        self.rng = Mutex::new(Box::new(rng));
+        self
+    }
+
+    /// Runs `operation` until it succeeds, fails with an error that is not
+    /// retried, or the attempts or time run out.
+    pub async fn run<T, F, Fut>(&self, mut operation: F) -> Result<T, TranslationError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, TranslationError>>,
+    {
+        let start = self.clock.now();
+        let mut previous_delay = self.base_delay;
+        let mut attempt = 1;
+
+        loop {
+            let error = match operation().await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+            if attempt >= self.max_attempts || !(self.retry_if)(&error) {
+                return Err(error);
+            }
+
+            let backoff = self.backoff(attempt, previous_delay);
+            previous_delay = backoff;
+            let delay = error
+                .retry_after()
+                .map_or(backoff, |after| after.max(backoff));
+
+            if let Some(max_elapsed) = self.max_elapsed {
+                let elapsed = self.clock.now().saturating_duration_since(start);
+                if elapsed + delay > max_elapsed {
+                    return Err(error);
+                }
+            }
+
+            self.clock.sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// The delay after the `attempt`-th failure.
+    fn backoff(&self, attempt: u32, previous_delay: Duration) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt - 1))
+            .min(self.max_delay);
+        let mut rng = self
+            .rng
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+        match self.jitter {
+            Jitter::None => exponential,
+            Jitter::Full => exponential.mul_f64(rng.gen::<f64>()),
+            Jitter::Decorrelated => {
+                let upper = previous_delay.saturating_mul(3).max(self.base_delay);
+                let spread = upper - self.base_delay;
+                (self.base_delay + spread.mul_f64(rng.gen::<f64>())).min(self.max_delay)
+            }
+        }
+    }
+}
+
+impl fmt::Debug for RetryPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("RetryPolicy")
+            .field("max_attempts", &self.max_attempts)
+            .field("base_delay", &self.base_delay)
+            .field("max_delay", &self.max_delay)
+            .field("max_elapsed", &self.max_elapsed)
+            .field("jitter", &self.jitter)
+            .field("clock", &self.clock)
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_support::FakeClock;
+    use crate::HttpError;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    fn rate_limited(retry_after: Option<Duration>) -> TranslationError {
+        TranslationError::RateLimited(HttpError {
+            status: 429,
+            body: String::new(),
+            retry_after,
+        })
+    }
+
+    /// Fails with `error()` for the first `failures` attempts.
+    async fn flaky(
+        policy: &RetryPolicy,
+        failures: u32,
+        error: impl Fn() -> TranslationError,
+    ) -> (Result<u32, TranslationError>, u32) {
+        let attempts = AtomicU32::new(0);
+        let result = policy
+            .run(|| {
+                let attempt = attempts.fetch_add(1, Ordering::SeqCst) + 1;
+                let result = if attempt <= failures {
+                    Err(error())
+                } else {
+                    Ok(attempt)
+                };
+                async move { result }
+            })
+            .await;
+        (result, attempts.into_inner())
+    }
+
+    #[tokio::test]
+    async fn test_exponential_backoff() {
+        let clock = FakeClock::new();
+        let policy = RetryPolicy::new()
+            .with_jitter(Jitter::None)
+            .with_base_delay(Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(3))
+            .with_max_attempts(5)
+            .with_clock(clock.clone());
+
+        let (result, attempts) = flaky(&policy, 4, || rate_limited(None)).await;
+        assert_eq!(result.unwrap(), 5);
+        assert_eq!(attempts, 5);
+        assert_eq!(
+            clock.sleeps(),
+            [1, 2, 3, 3].map(Duration::from_secs).to_vec()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_after_and_max_elapsed() {
+        let clock = FakeClock::new();
+        let policy = RetryPolicy::new()
+            .with_jitter(Jitter::None)
+            .with_max_elapsed(Some(Duration::from_secs(10)))
+            .with_clock(clock.clone());
+
+        let retry_after = Some(Duration::from_secs(6));
+        let (result, attempts) = flaky(&policy, 3, || rate_limited(retry_after)).await;
+        assert!(matches!(result, Err(TranslationError::RateLimited(_))));
+        assert_eq!(attempts, 2);
+        assert_eq!(clock.sleeps(), [Duration::from_secs(6)]);
+    }
+
+    #[tokio::test]
+    async fn test_errors_that_are_not_retried() {
+        let clock = FakeClock::new();
+        let policy = RetryPolicy::new().with_clock(clock.clone());
+
+        let (result, attempts) = flaky(&policy, 1, || TranslationError::EmptyInput).await;
+        assert!(matches!(result, Err(TranslationError::EmptyInput)));
+        assert_eq!(attempts, 1);
+
+        let policy =
+            policy.with_retry_if(|error| matches!(error, TranslationError::NoTranslationFound(_)));
+        let (result, _) = flaky(&policy, 1, || {
+            TranslationError::NoTranslationFound("hello".to_owned())
+        })
+        .await;
+        assert_eq!(result.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_seeded_jitter_is_deterministic() {
+        let delays = |jitter| async move {
+            let clock = FakeClock::new();
+            let policy = RetryPolicy::new()
+                .with_jitter(jitter)
+                .with_max_elapsed(None)
+                .with_rng(StdRng::seed_from_u64(7))
+                .with_clock(clock.clone());
+            let (result, _) = flaky(&policy, 3, || rate_limited(None)).await;
+            assert!(result.is_ok());
+            clock.sleeps()
+        };
+
+        for jitter in [Jitter::Full, Jitter::Decorrelated] {
+            let sleeps = delays(jitter).await;
+            assert_eq!(sleeps.len(), 3);
+            assert_eq!(sleeps, delays(jitter).await);
+            assert!(sleeps.iter().all(|delay| *delay <= Duration::from_secs(30)));
+        }
+    }
+}
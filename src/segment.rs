@@ -0,0 +1,355 @@
+use crate::Language;
+
+/// Characters that end a sentence when followed by whitespace.
+const TERMINATORS: &[char] = &['.', '!', '?', '…', '‽', '؟', '।', '۔'];
+
+/// Characters that end a sentence even without following whitespace, as
+/// in Chinese and Japanese text.
+const FULL_WIDTH_TERMINATORS: &[char] = &['。', '！', '？', '．'];
+
+/// Closing quotes and brackets that stay with the sentence they end.
+const CLOSING: &[char] = &['"', '\'', '”', '’', '»', ')', ']', '}', '」', '』', '）'];
+
+/// Abbreviations after which a period does not end the sentence, per
+/// language. These play the role of SRX no-break rules.
+const ABBREVIATIONS: &[(&str, &[&str])] = &[
+    (
+        "en",
+        &[
+            "mr", "mrs", "ms", "dr", "prof", "sr", "jr", "st", "vs", "etc", "e.g", "i.e", "inc",
+            "ltd", "co", "no", "fig", "approx", "dept", "est", "jan", "feb", "mar", "apr", "jun",
+            "jul", "aug", "sep", "sept", "oct", "nov", "dec",
+        ],
+    ),
+    (
+        "de",
+        &[
+            "z.b", "bzw", "usw", "ca", "dr", "prof", "nr", "str", "vgl", "evtl", "ggf", "u.a",
+            "d.h", "s", "hr", "fr", "bspw", "inkl", "zzgl",
+        ],
+    ),
+    (
+        "fr",
+        &[
+            "m", "mme", "mlle", "dr", "etc", "p.ex", "cf", "av", "bd", "env", "n°", "st", "ste",
+        ],
+    ),
+    (
+        "es",
+        &[
+            "sr", "sra", "srta", "dr", "dra", "etc", "p.ej", "ud", "uds", "núm", "pág", "av",
+        ],
+    ),
+    (
+        "it",
+        &[
+            "sig", "sigg", "dott", "prof", "ecc", "es", "pag", "n", "s.p.a",
+        ],
+    ),
+    (
+        "pt",
+        &["sr", "sra", "dr", "dra", "etc", "p.ex", "av", "nº", "pág"],
+    ),
+    (
+        "nl",
+        &[
+            "dhr", "mevr", "dr", "prof", "bijv", "enz", "d.w.z", "o.a", "nr",
+        ],
+    ),
+    (
+        "ru",
+        &[
+            "т.е", "т.д", "т.п", "г", "гг", "ул", "д", "стр", "им", "проф",
+        ],
+    ),
+];
+
+/// Languages that write ordinals as a number followed by a period
+/// ("3. Oktober"), which then does not end the sentence.
+const ORDINAL_PERIOD: &[&str] = &[
+    "cs", "da", "de", "et", "fi", "hr", "hu", "is", "lv", "nb", "nn", "no", "pl", "sk", "sl", "sr",
+];
+
+/// Splits text into sentences and packs them into chunks short enough for
+/// a single request.
+#[derive(Debug, Clone)]
+pub struct Segmenter {
+    abbreviations: &'static [&'static str],
+    ordinal_period: bool,
+    max_len: usize,
+}
+
+impl Segmenter {
+    /// Creates a segmenter producing chunks of at most `max_len` characters,
+    /// using the rules for `language` (English rules when unknown).
+    pub fn new(language: Option<&Language>, max_len: usize) -> Self {
+        let code = language.map_or("en", |language| language.code());
+        let abbreviations = ABBREVIATIONS
+            .iter()
+            .find(|(lang, _)| *lang == code)
+            .map_or(ABBREVIATIONS[0].1, |(_, abbreviations)| abbreviations);
+
+        Self {
+            abbreviations,
+            ordinal_period: ORDINAL_PERIOD.contains(&code),
+            max_len: max_len.max(1),
+        }
+    }
+
+    /// Splits `text` into sentences. Each sentence keeps the whitespace that
+    /// follows it, so the sentences concatenate back to `text`.
+    pub fn sentences<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let chars: Vec<(usize, char)> = text.char_indices().collect();
+        let mut sentences = Vec::new();
+        let mut start = 0;
+        let mut i = 0;
+
+        while i < chars.len() {
+            let (_, c) = chars[i];
+            let mut end = None;
+
+            if FULL_WIDTH_TERMINATORS.contains(&c) {
+                end = Some(skip_closing(&chars, i + 1));
+            } else if TERMINATORS.contains(&c) {
+                let after = skip_closing(&chars, skip_terminators(&chars, i + 1));
+                let followed_by_space = chars.get(after).is_none_or(|(_, c)| c.is_whitespace());
+                if followed_by_space && !self.is_exception(text, &chars, i, after) {
+                    end = Some(after);
+                }
+            } else if c == '\n' && is_paragraph_break(&chars, i) {
+                end = Some(i);
+            }
+
+            match end {
+                Some(end) => {
+                    let next = skip_whitespace(&chars, end);
+                    let byte = chars.get(next).map_or(text.len(), |(byte, _)| *byte);
+                    if byte > start {
+                        sentences.push(&text[start..byte]);
+                        start = byte;
+                    }
+                    i = next.max(i + 1);
+                }
+                None => i += 1,
+            }
+        }
+
+        if start < text.len() {
+            sentences.push(&text[start..]);
+        }
+        sentences
+    }
+
+    /// Splits `text` into chunks of at most `max_len` characters, breaking
+    /// between sentences where possible. The chunks concatenate back to
+    /// `text`; a URL or number longer than `max_len` is kept whole.
+    pub fn chunks<'a>(&self, text: &'a str) -> Vec<&'a str> {
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        let mut end = 0;
+
+        let pieces = self
+            .sentences(text)
+            .into_iter()
+            .flat_map(|sentence| self.split_long(sentence));
+        for piece in pieces {
+            let candidate = text[start..end + piece.len()].trim();
+            if end > start && candidate.chars().count() > self.max_len {
+                chunks.push(&text[start..end]);
+                start = end;
+            }
+            end += piece.len();
+        }
+
+        if start < text.len() {
+            chunks.push(&text[start..]);
+        }
+        chunks
+    }
+
+    /// Splits a sentence longer than `max_len` at whitespace, falling back
+    /// to grapheme boundaries inside over-long words.
+    fn split_long<'a>(&self, sentence: &'a str) -> Vec<&'a str> {
+        if sentence.trim().chars().count() <= self.max_len {
+            return vec![sentence];
+        }
+
+        let mut pieces = Vec::new();
+        let mut rest = sentence;
+        while !rest.is_empty() {
+            let word_end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+            let space_end = rest[word_end..]
+                .find(|c: char| !c.is_whitespace())
+                .map_or(rest.len(), |offset| word_end + offset);
+            let word = &rest[..word_end];
+
+            if word.chars().count() > self.max_len && !is_unbreakable(word) {
+                let mut word_rest = word;
+                while word_rest.chars().count() > self.max_len {
+                    let split = grapheme_boundary(word_rest, self.max_len);
+                    pieces.push(&word_rest[..split]);
+                    word_rest = &word_rest[split..];
+                }
+                pieces.push(&rest[word.len() - word_rest.len()..space_end]);
+            } else {
+                pieces.push(&rest[..space_end]);
+            }
+            rest = &rest[space_end..];
+        }
+        pieces
+    }
+
+    /// Whether the terminator at `i` does not end a sentence: after an
+    /// abbreviation, initial or ordinal, or before a lowercase word.
+    fn is_exception(&self, text: &str, chars: &[(usize, char)], i: usize, after: usize) -> bool {
+        let next = chars.get(skip_whitespace(chars, after)).map(|(_, c)| *c);
+        if next.is_some_and(|c| c.is_lowercase()) {
+            return true;
+        }
+        if chars[i].1 != '.' {
+            return false;
+        }
+
+        let (byte, _) = chars[i];
+        let word_start = text[..byte]
+            .char_indices()
+            .rev()
+            .find(|(_, c)| c.is_whitespace() || *c == '(' || *c == '"')
+            .map_or(0, |(start, c)| start + c.len_utf8());
+        let word = text[word_start..byte].to_lowercase();
+
+        let is_initial = word.chars().count() == 1 && word.chars().all(char::is_alphabetic);
+        let is_ordinal =
+            self.ordinal_period && !word.is_empty() && word.chars().all(|c| c.is_ascii_digit());
+        is_initial || is_ordinal || self.abbreviations.contains(&word.as_str())
+    }
+}
+
+fn skip_terminators(chars: &[(usize, char)], mut i: usize) -> usize {
+    while chars.get(i).is_some_and(|(_, c)| TERMINATORS.contains(c)) {
+        i += 1;
+    }
+    i
+}
+
+fn skip_closing(chars: &[(usize, char)], mut i: usize) -> usize {
+    while chars.get(i).is_some_and(|(_, c)| CLOSING.contains(c)) {
+        i += 1;
+    }
+    i
+}
+
+fn skip_whitespace(chars: &[(usize, char)], mut i: usize) -> usize {
+    while chars.get(i).is_some_and(|(_, c)| c.is_whitespace()) {
+        i += 1;
+    }
+    i
+}
+
+/// Whether the newline at `i` starts a blank line.
+fn is_paragraph_break(chars: &[(usize, char)], i: usize) -> bool {
+    chars[i + 1..]
+        .iter()
+        .take_while(|(_, c)| c.is_whitespace())
+        .any(|(_, c)| *c == '\n')
+}
+
+/// Whether `word` is a URL or number that must not be split.
+fn is_unbreakable(word: &str) -> bool {
+    word.contains("://")
+        || word.starts_with("www.")
+        || word
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | ',' | '-' | '+'))
+}
+
+/// Byte offset of the last grapheme boundary within the first `max_chars`
+/// characters of `word`.
+fn grapheme_boundary(word: &str, max_chars: usize) -> usize {
+    let chars: Vec<(usize, char)> = word.char_indices().collect();
+    let mut i = max_chars.min(chars.len());
+    while i > 1 && !is_grapheme_break(chars[i - 1].1, chars.get(i).map(|(_, c)| *c)) {
+        i -= 1;
+    }
+    chars.get(i).map_or(word.len(), |(byte, _)| *byte)
+}
+
+/// Whether a grapheme may end between `before` and `after`: not before a
+/// combining mark, variation selector or skin tone modifier, and not
+/// around a zero-width joiner.
+fn is_grapheme_break(before: char, after: Option<char>) -> bool {
+    let Some(after) = after else {
+        return true;
+    };
+    let extends = matches!(
+        after as u32,
+        0x0300..=0x036F
+            | 0x1AB0..=0x1AFF
+            | 0x1DC0..=0x1DFF
+            | 0x20D0..=0x20FF
+            | 0xFE00..=0xFE0F
+            | 0xFE20..=0xFE2F
+            | 0x1F3FB..=0x1F3FF
+            | 0x200D
+    );
+    !extends && before != '\u{200D}'
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segmenter(lang: &str, max_len: usize) -> Segmenter {
+        Segmenter::new(Some(&Language::parse(lang).unwrap()), max_len)
+    }
+
+    #[test]
+    fn test_sentences() {
+        let text = "Dr. Smith paid $3.50 at https://example.com/a.b?c=d. Was it worth it?! \
+                    He said \"yes.\" Then he left.\n\nNew paragraph";
+        assert_eq!(
+            segmenter("en", 100).sentences(text),
+            [
+                "Dr. Smith paid $3.50 at https://example.com/a.b?c=d. ",
+                "Was it worth it?! ",
+                "He said \"yes.\" ",
+                "Then he left.\n\n",
+                "New paragraph",
+            ]
+        );
+        assert_eq!(
+            segmenter("de", 100).sentences("Das ist z.B. gut. Am 3. Oktober."),
+            ["Das ist z.B. gut. ", "Am 3. Oktober."]
+        );
+        assert_eq!(
+            segmenter("ja", 100).sentences("今日は晴れです。明日は雨です。"),
+            ["今日は晴れです。", "明日は雨です。"]
+        );
+    }
+
+    #[test]
+    fn test_chunks_reassemble_to_the_input() {
+        let text = "  One sentence here.  Another one follows!\n\nA third\tone.\n";
+        let chunks = segmenter("en", 25).chunks(text);
+
+        assert_eq!(chunks.concat(), text);
+        assert_eq!(chunks.len(), 3);
+        assert!(chunks
+            .iter()
+            .all(|chunk| chunk.trim().chars().count() <= 25));
+    }
+
+    #[test]
+    fn test_long_sentences_are_split_safely() {
+        let url = "https://example.com/a-very-long-path";
+        let text = format!("see {} now", url);
+        let chunks = segmenter("en", 10).chunks(&text);
+        assert_eq!(chunks.concat(), text);
+        assert!(chunks.iter().any(|chunk| chunk.trim() == url));
+
+        let word = "e\u{301}".repeat(6);
+        let chunks = segmenter("en", 5).chunks(&word);
+        assert_eq!(chunks.concat(), word);
+        assert!(chunks.iter().all(|chunk| !chunk.starts_with('\u{301}')));
+    }
+}
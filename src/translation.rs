@@ -0,0 +1,94 @@
+use std::fmt;
+
+/// The result of translating a piece of text.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Translation {
+    /// The translated text.
+    pub text: String,
+    /// The text that was translated.
+    pub source_text: String,
+    /// Sentence-level source/target pairs, in order.
+    pub segments: Vec<Segment>,
+    /// The source language reported by the backend, if any.
+    pub detected_source_lang: Option<String>,
+    /// The backend's confidence in the detected source language, from 0 to 1.
+    pub confidence: Option<f64>,
+    /// Whether the translation was served from a cache.
+    pub from_cache: bool,
+}
+
+/// A translated sentence together with the source it was produced from.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    pub source: String,
+    pub target: String,
+}
+
+impl Translation {
+    /// Creates a translation made of a single segment.
+    pub fn new(source_text: impl Into<String>, text: impl Into<String>) -> Self {
+        let source_text = source_text.into();
+        let text = text.into();
+        Self {
+            segments: vec![Segment {
+                source: source_text.clone(),
+                target: text.clone(),
+            }],
+            text,
+            source_text,
+            detected_source_lang: None,
+            confidence: None,
+            from_cache: false,
+        }
+    }
+
+    /// Creates a translation by concatenating `segments`.
+    pub fn from_segments(segments: Vec<Segment>) -> Self {
+        Self {
+            text: segments
+                .iter()
+                .map(|segment| segment.target.as_str())
+                .collect(),
+            source_text: segments
+                .iter()
+                .map(|segment| segment.source.as_str())
+                .collect(),
+            segments,
+            detected_source_lang: None,
+            confidence: None,
+            from_cache: false,
+        }
+    }
+
+    /// Adds whitespace that was trimmed from the source before translating
+    /// back around both the source and the translation.
+    pub(crate) fn padded(mut self, before: &str, after: &str) -> Self {
+        if let Some(first) = self.segments.first_mut() {
+            first.source.insert_str(0, before);
+            first.target.insert_str(0, before);
+        }
+        if let Some(last) = self.segments.last_mut() {
+            last.source.push_str(after);
+            last.target.push_str(after);
+        }
+        self.source_text = format!("{}{}{}", before, self.source_text, after);
+        self.text = format!("{}{}{}", before, self.text, after);
+        self
+    }
+
+    pub fn with_detected_source_lang(
+        mut self,
+        lang: impl Into<String>,
+        confidence: Option<f64>,
+    ) -> Self {
+        self.detected_source_lang = Some(lang.into());
+        self.confidence = confidence;
+        self
+    }
+}
+
+impl fmt::Display for Translation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}